@@ -1,31 +1,83 @@
 use js_sys::Object;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{Document, Element, HtmlCanvasElement, WebGl2RenderingContext, Window};
+use webgl_common::{ClearContext, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample};
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document: Document = window.document().unwrap();
+webgl_common::example_info!();
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    gl: C,
+}
+
+impl<C: ClearContext> Scene<C> {
+    pub fn new(mut gl: C) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+
+        // Set the clear color to darkish green.
+        gl.clear_color(0.0, 0.5, 0.0, 1.0);
+
+        Scene { gl }
+    }
+
+    pub fn render(&mut self) {
+        // Clear the context with the clear color. This is
+        // the function call that actually does the drawing.
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    }
+}
+
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[300, 150]];
 
-    let paragraph: Element = document.query_selector("p").unwrap().unwrap();
-    let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
+    fn new(mut contexts: Vec<SoftwareContext>, _rng: Rng) -> Self {
+        Scene::new(contexts.remove(0))
+    }
 
-    let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
-    let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+    fn render(&mut self) {
+        Scene::render(self);
+    }
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+pub struct ClearingWithColors {
+    scene: Scene<Gl>,
+}
+
+impl Example for ClearingWithColors {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document: Document = window.document().unwrap();
+
+        let paragraph: Element = document.query_selector("p").unwrap().unwrap();
+        let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
 
-    paragraph.set_inner_html(&format!("Congratulations! Your browser supports WebGL2.",));
+        let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
+        let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        let gl: Gl = context.unchecked_into::<Gl>();
 
-    // Set the clear color to darkish green.
-    gl.clear_color(0.0, 0.5, 0.0, 1.0);
+        paragraph.set_inner_html("Congratulations! Your browser supports WebGL2.");
 
-    // Clear the context with the newly set color. This is
-    // the function call that actually does the drawing.
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
 
+        Ok(ClearingWithColors {
+            scene: Scene::new(gl),
+        })
+    }
+
+    fn render(&mut self) {
+        self.scene.render();
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<ClearingWithColors>()?.keep_until_unload();
     Ok(())
 }
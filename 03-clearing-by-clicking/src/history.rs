@@ -0,0 +1,212 @@
+use webgl_common::Color;
+
+/// Clear colors in the order they were used, with a position undo and redo
+/// move through. Only the last `capacity` colors are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorHistory {
+    colors: Vec<Color>,
+    // Index of the current color, the colors after it can be redone.
+    current: Option<usize>,
+    capacity: usize,
+}
+
+impl ColorHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            colors: Vec::with_capacity(capacity),
+            current: None,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// All kept colors, oldest first.
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current(&self) -> Option<Color> {
+        self.current.map(|index| self.colors[index])
+    }
+
+    /// Makes `color` the current color, the colors which could be redone
+    /// are dropped.
+    pub fn push(&mut self, color: Color) {
+        self.colors
+            .truncate(self.current.map_or(0, |index| index + 1));
+        if self.colors.len() == self.capacity {
+            self.colors.remove(0);
+        }
+        self.colors.push(color);
+        self.current = Some(self.colors.len() - 1);
+    }
+
+    /// Goes back to the previous color and returns it, `None` if there is
+    /// none.
+    pub fn undo(&mut self) -> Option<Color> {
+        let index = self.current.filter(|&index| index > 0)? - 1;
+        self.current = Some(index);
+        self.current()
+    }
+
+    /// Goes forward to the color last undone and returns it, `None` if
+    /// there is none.
+    pub fn redo(&mut self) -> Option<Color> {
+        let index = self.current.map_or(0, |index| index + 1);
+        if index >= self.colors.len() {
+            return None;
+        }
+        self.current = Some(index);
+        self.current()
+    }
+
+    /// Uses the color at `index` again, as a new color, so that using it
+    /// can be undone.
+    pub fn reuse(&mut self, index: usize) -> Option<Color> {
+        let color = *self.colors.get(index)?;
+        self.push(color);
+        Some(color)
+    }
+}
+
+/// Text formats a color can be copied in, as used in CSS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+impl ColorFormat {
+    pub fn from_name(name: &str) -> Option<ColorFormat> {
+        match name {
+            "hex" => Some(ColorFormat::Hex),
+            "rgb" => Some(ColorFormat::Rgb),
+            "hsl" => Some(ColorFormat::Hsl),
+            _ => None,
+        }
+    }
+
+    pub fn format(self, color: Color) -> String {
+        let color = color.clamped();
+        match self {
+            ColorFormat::Hex => color.to_hex(),
+            ColorFormat::Rgb => {
+                let [r, g, b] = color.rgb_array().map(|channel| (channel * 255.0).round());
+                format!("rgb({r}, {g}, {b})")
+            }
+            ColorFormat::Hsl => {
+                let [hue, saturation, lightness] = color.to_hsl();
+                format!(
+                    "hsl({:.0}, {:.0}%, {:.0}%)",
+                    hue,
+                    saturation * 100.0,
+                    lightness * 100.0
+                )
+            }
+        }
+    }
+}
+
+/// Black or white, whichever is easier to read on `background`.
+pub fn label_color(background: Color) -> Color {
+    let background = background.clamped().with_alpha(1.0);
+    if background.contrast_ratio(Color::BLACK) >= background.contrast_ratio(Color::WHITE) {
+        Color::BLACK
+    } else {
+        Color::WHITE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(1.0, 0.0, 0.0);
+    const GREEN: Color = Color::rgb(0.0, 1.0, 0.0);
+    const BLUE: Color = Color::rgb(0.0, 0.0, 1.0);
+
+    fn history(colors: &[Color]) -> ColorHistory {
+        let mut history = ColorHistory::new(4);
+        for &color in colors {
+            history.push(color);
+        }
+        history
+    }
+
+    #[test]
+    fn undo_and_redo_move_through_colors() {
+        let mut history = history(&[RED, GREEN, BLUE]);
+        assert_eq!(history.undo(), Some(GREEN));
+        assert_eq!(history.undo(), Some(RED));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.current(), Some(RED));
+        assert_eq!(history.redo(), Some(GREEN));
+        assert_eq!(history.redo(), Some(BLUE));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.current_index(), Some(2));
+    }
+
+    #[test]
+    fn empty_history_has_nothing_to_undo() {
+        let mut history = ColorHistory::new(4);
+        assert_eq!(history.current(), None);
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn new_color_drops_undone_colors() {
+        let mut history = history(&[RED, GREEN, BLUE]);
+        history.undo();
+        history.undo();
+        history.push(BLUE);
+        assert_eq!(history.colors(), [RED, BLUE]);
+        assert_eq!(history.redo(), None);
+    }
+
+    #[test]
+    fn oldest_colors_are_dropped() {
+        let history = history(&[RED, GREEN, BLUE, RED, GREEN]);
+        assert_eq!(history.colors(), [GREEN, BLUE, RED, GREEN]);
+        assert_eq!(history.current_index(), Some(3));
+    }
+
+    #[test]
+    fn reused_color_can_be_undone() {
+        let mut history = history(&[RED, GREEN]);
+        assert_eq!(history.reuse(0), Some(RED));
+        assert_eq!(history.colors(), [RED, GREEN, RED]);
+        assert_eq!(history.undo(), Some(GREEN));
+        assert_eq!(history.reuse(5), None);
+    }
+
+    #[test]
+    fn formats() {
+        let color = Color::rgb(1.0, 0.5, 0.0);
+        assert_eq!(ColorFormat::Hex.format(color), "#ff8000");
+        assert_eq!(ColorFormat::Rgb.format(color), "rgb(255, 128, 0)");
+        assert_eq!(ColorFormat::Hsl.format(color), "hsl(30, 100%, 50%)");
+        assert_eq!(
+            ColorFormat::Hsl.format(Color::rgb(0.5, 0.5, 0.5)),
+            "hsl(0, 0%, 50%)"
+        );
+        assert_eq!(ColorFormat::Hsl.format(BLUE), "hsl(240, 100%, 50%)");
+        assert_eq!(ColorFormat::from_name("rgb"), Some(ColorFormat::Rgb));
+        assert_eq!(ColorFormat::from_name("cmyk"), None);
+    }
+
+    #[test]
+    fn labels_contrast_with_the_swatch() {
+        assert_eq!(label_color(Color::WHITE), Color::BLACK);
+        assert_eq!(label_color(Color::BLACK), Color::WHITE);
+        assert_eq!(label_color(Color::rgb(1.0, 1.0, 0.0)), Color::BLACK);
+        assert_eq!(label_color(BLUE), Color::WHITE);
+        // Mid gray is darker than it looks in sRGB values.
+        assert_eq!(label_color(Color::rgb(0.5, 0.5, 0.5)), Color::BLACK);
+        assert_eq!(label_color(Color::rgb(0.35, 0.35, 0.35)), Color::WHITE);
+    }
+}
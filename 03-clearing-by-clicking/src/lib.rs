@@ -1,49 +1,237 @@
-use js_sys::{Function, Math, Object};
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod history;
+
+pub use history::{label_color, ColorFormat, ColorHistory};
+
+use js_sys::Object;
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
-use web_sys::{Document, Element, HtmlCanvasElement, MouseEvent, WebGl2RenderingContext, Window};
+use web_sys::{
+    Document, Element, Event, EventTarget, HtmlCanvasElement, KeyboardEvent,
+    WebGl2RenderingContext, Window,
+};
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample,
+};
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document: Document = window.document().unwrap();
+webgl_common::example_info!();
+
+// Swatches shown in the history strip.
+const HISTORY_LENGTH: usize = 12;
 
-    let canvas: Element = document.query_selector("#canvas-view").unwrap().unwrap();
-    let button: Element = document.query_selector("#color-switcher").unwrap().unwrap();
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    gl: C,
+    rng: Rng,
+    history: ColorHistory,
+}
 
-    let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
-    let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+impl<C: ClearContext> Scene<C> {
+    pub fn new(mut gl: C, rng: Rng) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+        Scene {
+            gl,
+            rng,
+            history: ColorHistory::new(HISTORY_LENGTH),
+        }
+    }
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    pub fn history(&self) -> &ColorHistory {
+        &self.history
+    }
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+    pub fn render(&mut self) {
+        // Clear the context with the current clear color. This is
+        // the function call that actually does the drawing.
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    }
 
-    let closure = Closure::<dyn Fn(MouseEvent)>::new(move |_event: MouseEvent| {
-        // Get a random color value using a helper function.
-        let color = get_random_color();
+    pub fn switch_color(&mut self) {
+        // Get a random color value.
+        let color = Color::random(&mut self.rng);
+        self.history.push(color);
 
         // Set the clear color to the random color.
-        gl.clear_color(color[0], color[1], color[2], 1.0);
+        self.set_color(color);
+    }
 
-        // Clear the context with the newly set color. This is
-        // the function call that actually does the drawing.
-        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-    });
+    /// Clears with the previous color again, false if there is none.
+    pub fn undo(&mut self) -> bool {
+        let color = self.history.undo();
+        color.map(|color| self.set_color(color)).is_some()
+    }
 
-    let listener: &Function = closure.as_ref().unchecked_ref();
+    /// Clears with the color last undone again, false if there is none.
+    pub fn redo(&mut self) -> bool {
+        let color = self.history.redo();
+        color.map(|color| self.set_color(color)).is_some()
+    }
 
-    drop(canvas.add_event_listener_with_callback("click", listener));
-    drop(button.add_event_listener_with_callback("click", listener));
+    /// Clears with the color at `index` of the history again.
+    pub fn reuse(&mut self, index: usize) -> bool {
+        let color = self.history.reuse(index);
+        color.map(|color| self.set_color(color)).is_some()
+    }
 
-    closure.forget();
+    fn set_color(&mut self, color: Color) {
+        self.gl.clear_color(color.r, color.g, color.b, color.a);
+    }
+}
 
-    Ok(())
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[300, 150]];
+
+    // Clicked once.
+    fn new(mut contexts: Vec<SoftwareContext>, rng: Rng) -> Self {
+        let mut scene = Scene::new(contexts.remove(0), rng);
+        scene.switch_color();
+        scene
+    }
+
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+pub struct ClearingByClicking {
+    scene: Scene<Gl>,
+    document: Document,
+    history_strip: Element,
+    copy_buttons: Element,
+    color_text: Element,
+}
+
+impl ClearingByClicking {
+    // Shows a swatch per color of the history, the current one marked,
+    // numbered in black or white depending on the color.
+    fn show_history(&self) {
+        self.history_strip.set_inner_html("");
+        let history = self.scene.history();
+        for (index, &color) in history.colors().iter().enumerate() {
+            let swatch = self.document.create_element("button").unwrap();
+            swatch.set_class_name(if history.current_index() == Some(index) {
+                "swatch current"
+            } else {
+                "swatch"
+            });
+            let text = ColorFormat::Hex.format(color);
+            drop(swatch.set_attribute("data-index", &index.to_string()));
+            drop(swatch.set_attribute("title", &text));
+            swatch.set_text_content(Some(&(index + 1).to_string()));
+            let label = label_color(color).to_hex();
+            drop(swatch.set_attribute(
+                "style",
+                &format!("background-color: {text}; color: {label}"),
+            ));
+            drop(self.history_strip.append_child(&swatch));
+        }
+    }
+
+    fn on_key(&mut self, event: &KeyboardEvent) {
+        if !(event.ctrl_key() || event.meta_key()) || event.alt_key() {
+            return;
+        }
+        let changed = match event.key().to_lowercase().as_str() {
+            "z" if event.shift_key() => self.scene.redo(),
+            "z" => self.scene.undo(),
+            "y" => self.scene.redo(),
+            _ => return,
+        };
+        event.prevent_default();
+        if changed {
+            self.show_history();
+        }
+    }
+
+    // Copies the current color in the format named by the `data-format`
+    // attribute of the clicked button, and shows it.
+    fn copy(&self, button: &Element) {
+        let format = button
+            .get_attribute("data-format")
+            .and_then(|name| ColorFormat::from_name(&name));
+        let (Some(format), Some(color)) = (format, self.scene.history().current()) else {
+            return;
+        };
+        let text = format.format(color);
+        let clipboard = web_sys::window().unwrap().navigator().clipboard();
+        drop(clipboard.write_text(&text));
+        self.color_text.set_text_content(Some(&text));
+    }
 }
 
-fn get_random_color() -> [f32; 3] {
-    let r = Math::random() as f32;
-    let g = Math::random() as f32;
-    let b = Math::random() as f32;
-    [r, g, b]
+impl Example for ClearingByClicking {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document: Document = window.document().unwrap();
+
+        let canvas: Element = document.query_selector("#canvas-view").unwrap().unwrap();
+        let button: Element = document.query_selector("#color-switcher").unwrap().unwrap();
+        let history_strip: Element = document.query_selector("#color-history").unwrap().unwrap();
+        let copy_buttons: Element = document.query_selector("#color-copy").unwrap().unwrap();
+        let color_text: Element = document.query_selector("#color-text").unwrap().unwrap();
+
+        let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
+        let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+
+        let gl: Gl = context.unchecked_into::<Gl>();
+
+        // Clicking either the canvas or the button switches the color.
+        setup.listen(&canvas, "click");
+        setup.listen(&button, "click");
+        // Swatches are replaced on every change, their clicks are handled
+        // by the strip.
+        setup.listen(&history_strip, "click");
+        setup.listen(&copy_buttons, "click");
+        setup.listen(&document, "keydown");
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+
+        Ok(ClearingByClicking {
+            scene: Scene::new(gl, Rng::from_entropy()),
+            document,
+            history_strip,
+            copy_buttons,
+            color_text,
+        })
+    }
+
+    fn render(&mut self) {
+        self.scene.render();
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+            self.on_key(event);
+            return;
+        }
+
+        let current_target: EventTarget = event.current_target().unwrap();
+        let target = event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok());
+        if AsRef::<EventTarget>::as_ref(&self.history_strip) == &current_target {
+            let index = target
+                .and_then(|target| target.get_attribute("data-index"))
+                .and_then(|index| index.parse().ok());
+            if index.is_some_and(|index| self.scene.reuse(index)) {
+                self.show_history();
+            }
+        } else if AsRef::<EventTarget>::as_ref(&self.copy_buttons) == &current_target {
+            if let Some(button) = target {
+                self.copy(&button);
+            }
+        } else {
+            self.scene.switch_color();
+            self.show_history();
+        }
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<ClearingByClicking>()?.keep_until_unload();
+    Ok(())
 }
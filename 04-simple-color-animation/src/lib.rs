@@ -1,139 +1,285 @@
-use js_sys::{Function, Math, Object};
-use std::cell::{Cell, RefCell};
-use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod transition;
+
+pub use transition::{ColorSpace, Easing, Transition};
+
+use js_sys::Object;
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Document, Element, EventListener, HtmlCanvasElement, MouseEvent, PointerEvent,
+    Document, Element, Event, EventTarget, HtmlCanvasElement, HtmlInputElement, HtmlSelectElement,
     WebGl2RenderingContext, Window,
 };
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample,
+};
 
-fn get_random_color() -> [f32; 3] {
-    let r = Math::random() as f32;
-    let g = Math::random() as f32;
-    let b = Math::random() as f32;
-    [r, g, b]
+webgl_common::example_info!();
+
+// The color changes once every second while the animation runs.
+const COLOR_DURATION: f64 = 1.0;
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    gl: C,
+    rng: Rng,
+    running: bool,
+    // Time since the color last changed.
+    elapsed: f64,
+    // From the previous color to the current one, `None` before the
+    // animation first started.
+    transition: Option<Transition>,
+    duration: f64,
+    easing: Easing,
+    space: ColorSpace,
 }
 
-fn draw_animation(gl: &WebGl2RenderingContext) {
-    // Get a random color value using a helper function.
-    let color = get_random_color();
+impl<C: ClearContext> Scene<C> {
+    pub fn new(mut gl: C, rng: Rng) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+        Scene {
+            gl,
+            rng,
+            running: false,
+            elapsed: 0.0,
+            transition: None,
+            duration: 0.5,
+            easing: Easing::EaseInOut,
+            space: ColorSpace::Oklab,
+        }
+    }
+
+    /// Sets how the following color changes go from one color to the next,
+    /// taking `duration` seconds of the second each color is shown. An
+    /// `easing` of `None` keeps the current one.
+    pub fn set_transition(&mut self, duration: f64, easing: Option<Easing>, space: ColorSpace) {
+        self.duration = duration.clamp(0.0, COLOR_DURATION);
+        self.easing = easing.unwrap_or(self.easing);
+        self.space = space;
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    pub fn space(&self) -> ColorSpace {
+        self.space
+    }
+
+    fn draw_animation(&mut self) {
+        // Get a random color value.
+        let color = Color::random(&mut self.rng);
+
+        // Move from the color shown right now to the random color, the
+        // very first one is shown right away.
+        let from = self
+            .transition
+            .map_or(color, |transition| transition.color());
+        let transition = Transition::new(from, color, self.duration, self.easing, self.space);
+        self.transition = Some(transition);
+        self.elapsed = 0.0;
+        self.apply_color();
+    }
+
+    // Sets the clear color to the color of the transition.
+    fn apply_color(&mut self) {
+        if let Some(transition) = &self.transition {
+            let color = transition.color();
+            self.gl.clear_color(color.r, color.g, color.b, color.a);
+        }
+    }
+
+    /// Starts or stops the animation, returns whether it runs.
+    pub fn toggle(&mut self) -> bool {
+        self.running = !self.running;
+        if self.running {
+            // Give immediate feedback to user after clicking, by
+            // drawing one animation frame.
+            self.draw_animation();
+        }
+        self.running
+    }
 
-    // Set the clear color to the random color.
-    gl.clear_color(color[0], color[1], color[2], 1.0);
+    pub fn update(&mut self, dt: f64) {
+        if !self.running {
+            return;
+        }
+        self.elapsed += dt;
+        if self.elapsed >= COLOR_DURATION {
+            self.draw_animation();
+        } else if let Some(transition) = &mut self.transition {
+            transition.advance(dt);
+            self.apply_color();
+        }
+    }
 
-    // Clear the context with the newly set color. This is
-    // the function call that actually does the drawing.
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    pub fn render(&mut self) {
+        // Clear the context with the current color. This is
+        // the function call that actually does the drawing.
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    }
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document: Document = window.document().unwrap();
-
-    let button: Element = document
-        .query_selector("#animation-onoff")
-        .unwrap()
-        .unwrap();
-
-    let verb = document.query_selector("strong").unwrap().unwrap();
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[300, 150]];
 
-    let button1: Rc<Element> = Rc::new(button);
-    let button2 = Rc::clone(&button1);
+    // The animation is started.
+    fn new(mut contexts: Vec<SoftwareContext>, rng: Rng) -> Self {
+        let mut scene = Scene::new(contexts.remove(0), rng);
+        scene.toggle();
+        scene
+    }
 
-    let verb1: Rc<Element> = Rc::new(verb);
-    let verb2 = Rc::clone(&verb1);
+    fn update(&mut self, dt: f64) {
+        Scene::update(self, dt);
+    }
 
-    let canvas: Element = document.query_selector("#canvas-view").unwrap().unwrap();
-    let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
-    let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+    fn render(&mut self) {
+        Scene::render(self);
+    }
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
-
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
-
-    let gl1 = Rc::new(gl);
-    let gl2 = Rc::clone(&gl1);
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
 
-    let draw_animation_closure = Closure::<dyn Fn()>::new(move || draw_animation(&gl2));
+pub struct SimpleColorAnimation {
+    scene: Scene<Gl>,
+    button: Element,
+    verb: Element,
+    duration: HtmlInputElement,
+    duration_text: Element,
+    easing: HtmlSelectElement,
+    bezier: HtmlInputElement,
+    space: HtmlSelectElement,
+}
 
-    let timer1: Rc<Cell<Option<i32>>> = Rc::new(Cell::new(None));
-    let timer2: Rc<Cell<Option<i32>>> = Rc::clone(&timer1);
+impl SimpleColorAnimation {
+    // Passes the settings of the form to the scene. An invalid Bézier curve
+    // is marked and keeps the previous easing, the other settings still
+    // apply.
+    fn read_settings(&mut self) {
+        let duration = self.duration.value_as_number() / 1000.0;
+        self.duration_text
+            .set_text_content(Some(&format!("{} ms", self.duration.value())));
+
+        self.bezier.set_custom_validity("");
+        self.bezier
+            .set_disabled(self.easing.value() != "cubic-bezier");
+        let easing = match self.easing.value().as_str() {
+            "linear" => Some(Easing::Linear),
+            "cubic-bezier" => Easing::cubic_bezier(&self.bezier.value()),
+            _ => Some(Easing::EaseInOut),
+        };
+        if easing.is_none() {
+            self.bezier
+                .set_custom_validity("Expected x1, y1, x2, y2 with x in [0, 1]");
+        }
+        let space = ColorSpace::from_name(&self.space.value()).unwrap_or(ColorSpace::Oklab);
+        self.scene.set_transition(duration, easing, space);
+    }
+}
 
-    let start_animation_event_handler = Rc::new(RefCell::new(EventListener::new()));
-    let stop_animation_event_handler = Rc::new(RefCell::new(EventListener::new()));
+impl Example for SimpleColorAnimation {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document: Document = window.document().unwrap();
 
-    let start_animation_event_handler1 = Rc::clone(&start_animation_event_handler);
-    let start_animation_event_handler2 = Rc::clone(&start_animation_event_handler);
-    let stop_animation_event_handler1 = Rc::clone(&stop_animation_event_handler);
-    let stop_animation_event_handler2 = Rc::clone(&stop_animation_event_handler);
+        let button: Element = document
+            .query_selector("#animation-onoff")
+            .unwrap()
+            .unwrap();
 
-    let start_animation_closure = Closure::<dyn Fn(MouseEvent)>::new(move |event: MouseEvent| {
-        verb1.set_text_content(Some("stop"));
-        drop(button1.remove_event_listener_with_event_listener(
-            event.type_().as_str(),
-            &start_animation_event_handler1.borrow(),
-        ));
-        drop(button1.add_event_listener_with_event_listener(
-            "click",
-            &stop_animation_event_handler1.borrow(),
-        ));
+        let verb = document.query_selector("strong").unwrap().unwrap();
+        verb.set_text_content(Some("start"));
 
-        // Setup animation loop by redrawing every second
-        let interval = web_sys::window()
+        let settings: Element = document
+            .query_selector("#transition-settings")
             .unwrap()
-            .set_interval_with_callback_and_timeout_and_arguments_0(
-                draw_animation_closure.as_ref().unchecked_ref(),
-                1000,
-            );
-
-        timer1.set(Some(interval.unwrap()));
-
-        // Give immediate feedback to user after clicking, by
-        // drawing one animation frame.
-        draw_animation(&gl1);
-    });
-
-    let stop_animation_closure = Closure::<dyn Fn(MouseEvent)>::new(move |event: MouseEvent| {
-        verb2.set_text_content(Some("start"));
-
-        drop(button2.remove_event_listener_with_event_listener(
-            event.type_().as_str(),
-            &stop_animation_event_handler2.borrow(),
-        ));
-        drop(button2.add_event_listener_with_event_listener(
-            "click",
-            &start_animation_event_handler2.borrow(),
-        ));
-
-        if let Some(handle) = timer2.take() {
-            web_sys::window()
-                .unwrap()
-                .clear_interval_with_handle(handle)
+            .unwrap();
+        let input = |selector: &str| document.query_selector(selector).unwrap().unwrap();
+
+        let canvas: Element = document.query_selector("#canvas-view").unwrap().unwrap();
+        let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
+        let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+
+        let gl: Gl = context.unchecked_into::<Gl>();
+
+        setup.listen(&button, "click");
+        setup.listen(&settings, "input");
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+        setup.animate();
+
+        let mut example = SimpleColorAnimation {
+            scene: Scene::new(gl, Rng::from_entropy()),
+            button,
+            verb,
+            duration: input("#duration").unchecked_into(),
+            duration_text: input("#duration-text"),
+            easing: input("#easing").unchecked_into(),
+            bezier: input("#bezier").unchecked_into(),
+            space: input("#color-space").unchecked_into(),
+        };
+        example.read_settings();
+        Ok(example)
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.scene.update(dt);
+    }
+
+    fn render(&mut self) {
+        self.scene.render();
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let event_target: EventTarget = event.current_target().unwrap();
+        if AsRef::<EventTarget>::as_ref(&self.button) != &event_target {
+            self.read_settings();
+            return;
         }
-    });
-
-    let start_animation_function: &Function = start_animation_closure.as_ref().unchecked_ref();
-
-    start_animation_event_handler
-        .borrow_mut()
-        .handle_event(start_animation_function);
-
-    let stop_animation_function: &Function = stop_animation_closure.as_ref().unchecked_ref();
 
-    stop_animation_event_handler
-        .borrow_mut()
-        .handle_event(stop_animation_function);
-
-    let pointer_event: PointerEvent = PointerEvent::new("click").unwrap();
-
-    drop(stop_animation_function.call1(&JsValue::UNDEFINED, pointer_event.as_ref()));
-
-    // draw_animation_closure.forget(); // Cannot forget, due to move to start_animation_closure closure
-    start_animation_closure.forget();
-    stop_animation_closure.forget();
+        let running = self.scene.toggle();
+        self.verb
+            .set_text_content(Some(if running { "stop" } else { "start" }));
+    }
+}
 
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<SimpleColorAnimation>()?.keep_until_unload();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> Scene<SoftwareContext> {
+        Scene::new(SoftwareContext::new(300, 150), Rng::new(1))
+    }
+
+    #[test]
+    fn invalid_easing_keeps_the_other_settings() {
+        let mut scene = scene();
+        scene.set_transition(0.5, Some(Easing::Linear), ColorSpace::Oklab);
+
+        // What the form passes on while the Bézier curve is being typed.
+        scene.set_transition(0.25, Easing::cubic_bezier("0.25, 0.1"), ColorSpace::Hsl);
+        assert_eq!(scene.duration(), 0.25);
+        assert_eq!(scene.easing(), Easing::Linear);
+        assert_eq!(scene.space(), ColorSpace::Hsl);
+    }
+
+    #[test]
+    fn duration_fits_into_the_second_of_each_color() {
+        let mut scene = scene();
+        scene.set_transition(2.0, None, ColorSpace::Srgb);
+        assert_eq!(scene.duration(), COLOR_DURATION);
+        assert_eq!(scene.easing(), Easing::EaseInOut);
+    }
+}
@@ -0,0 +1,343 @@
+use webgl_common::Color;
+
+/// How the progress of a transition speeds up and slows down over time,
+/// like the CSS easing functions of the same names.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseInOut,
+    /// Control points `(x1, y1)` and `(x2, y2)` of a cubic Bézier curve
+    /// from `(0, 0)` to `(1, 1)`, the x coordinates in `[0, 1]`.
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    /// Parses the four numbers of a cubic Bézier curve separated by commas,
+    /// like in `cubic-bezier(0.25, 0.1, 0.25, 1)`.
+    pub fn cubic_bezier(text: &str) -> Option<Easing> {
+        let numbers: Vec<f64> = text
+            .split(',')
+            .map(|number| number.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        match numbers[..] {
+            [x1, y1, x2, y2]
+                if (0.0..=1.0).contains(&x1)
+                    && (0.0..=1.0).contains(&x2)
+                    && y1.is_finite()
+                    && y2.is_finite() =>
+            {
+                Some(Easing::CubicBezier(x1, y1, x2, y2))
+            }
+            _ => None,
+        }
+    }
+
+    /// Eased progress at `t`, which is clamped to `[0, 1]`.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+// One coordinate of the curve with control points `p1` and `p2` at `t`.
+fn bezier(p1: f64, p2: f64, t: f64) -> f64 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    ((a * t + b) * t + c) * t
+}
+
+// Derivative of `bezier` by `t`.
+fn bezier_slope(p1: f64, p2: f64, t: f64) -> f64 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    (3.0 * a * t + 2.0 * b) * t + c
+}
+
+// Finds the curve parameter at which x is `x`, with Newton's method and
+// bisection where the curve is too flat for it, and returns y there.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-7;
+
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, t) - x;
+        if error.abs() < EPSILON {
+            return bezier(y1, y2, t);
+        }
+        let slope = bezier_slope(x1, x2, t);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        t -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    t = x;
+    while high - low > EPSILON {
+        let value = bezier(x1, x2, t);
+        if (value - x).abs() < EPSILON {
+            break;
+        }
+        if value < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.0;
+    }
+    bezier(y1, y2, t)
+}
+
+/// Space in which colors are interpolated. Each gives different colors in
+/// between, e.g. sRGB passes through darker, duller colors than OKLab.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    LinearRgb,
+    Hsl,
+    Oklab,
+}
+
+impl ColorSpace {
+    pub fn from_name(name: &str) -> Option<ColorSpace> {
+        match name {
+            "srgb" => Some(ColorSpace::Srgb),
+            "linear-rgb" => Some(ColorSpace::LinearRgb),
+            "hsl" => Some(ColorSpace::Hsl),
+            "oklab" => Some(ColorSpace::Oklab),
+            _ => None,
+        }
+    }
+
+    /// Color at `t` between `from` and `to`.
+    pub fn interpolate(self, from: Color, to: Color, t: f32) -> Color {
+        match self {
+            ColorSpace::Srgb => from.lerp(to, t),
+            ColorSpace::LinearRgb => Color::from_linear(lerp(from.to_linear(), to.to_linear(), t)),
+            ColorSpace::Hsl => Color::from_hsl(lerp_hsl(from.to_hsl(), to.to_hsl(), t)),
+            ColorSpace::Oklab => Color::from_oklab(lerp(from.to_oklab(), to.to_oklab(), t)),
+        }
+    }
+}
+
+fn lerp(from: [f32; 3], to: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+// Takes the shorter way around the hue circle. Grays have no hue, so the
+// hue of the other color is kept.
+fn lerp_hsl(mut from: [f32; 3], mut to: [f32; 3], t: f32) -> [f32; 3] {
+    if from[1] == 0.0 {
+        from[0] = to[0];
+    } else if to[1] == 0.0 {
+        to[0] = from[0];
+    }
+    let mut delta = (to[0] - from[0]).rem_euclid(360.0);
+    if delta > 180.0 {
+        delta -= 360.0;
+    }
+    let mut hsl = lerp(from, to, t);
+    hsl[0] = (from[0] + delta * t).rem_euclid(360.0);
+    hsl
+}
+
+/// A transition from one color to another over `duration` seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transition {
+    pub from: Color,
+    pub to: Color,
+    pub duration: f64,
+    pub easing: Easing,
+    pub space: ColorSpace,
+    elapsed: f64,
+}
+
+impl Transition {
+    pub fn new(from: Color, to: Color, duration: f64, easing: Easing, space: ColorSpace) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing,
+            space,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Progress in `[0, 1]` before easing.
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        self.elapsed / self.duration
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// The color at the current point of the transition, clamped to the
+    /// sRGB gamut.
+    pub fn color(&self) -> Color {
+        let t = self.easing.apply(self.progress()) as f32;
+        self.space.interpolate(self.from, self.to, t).clamped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACES: [ColorSpace; 4] = [
+        ColorSpace::Srgb,
+        ColorSpace::LinearRgb,
+        ColorSpace::Hsl,
+        ColorSpace::Oklab,
+    ];
+
+    // Hues wrap around.
+    fn hue_distance(a: f32, b: f32) -> f32 {
+        let distance = (a - b).rem_euclid(360.0);
+        distance.min(360.0 - distance)
+    }
+
+    fn assert_close(actual: Color, expected: Color) {
+        for (actual_channel, expected_channel) in actual.to_array().iter().zip(expected.to_array())
+        {
+            assert!(
+                (actual_channel - expected_channel).abs() < 1e-3,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            Easing::CubicBezier(0.5, -0.5, 0.5, 1.5),
+        ] {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{easing:?}");
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0));
+        }
+    }
+
+    #[test]
+    fn ease_in_out_is_symmetric_and_monotonic() {
+        let easing = Easing::EaseInOut;
+        assert!((easing.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!(easing.apply(0.1) < 0.1);
+        let mut previous = 0.0;
+        for step in 1..=100 {
+            let t = step as f64 / 100.0;
+            let value = easing.apply(t);
+            assert!((value + easing.apply(1.0 - t) - 1.0).abs() < 1e-6);
+            assert!(value >= previous);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn straight_bezier_is_linear() {
+        let easing = Easing::CubicBezier(0.0, 0.0, 1.0, 1.0);
+        for step in 0..=10 {
+            let t = step as f64 / 10.0;
+            assert!((easing.apply(t) - t).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn parses_cubic_bezier() {
+        assert_eq!(
+            Easing::cubic_bezier(" 0.25, 0.1,0.25 , 1"),
+            Some(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0))
+        );
+        assert_eq!(Easing::cubic_bezier("0.25, 0.1, 0.25"), None);
+        assert_eq!(Easing::cubic_bezier("1.5, 0, 0.5, 1"), None);
+        assert_eq!(Easing::cubic_bezier("a, b, c, d"), None);
+    }
+
+    #[test]
+    fn interpolation_starts_and_ends_at_the_colors() {
+        let from = Color::rgb(0.9, 0.2, 0.1);
+        let to = Color::rgb(0.1, 0.4, 0.8);
+        for space in SPACES {
+            assert_close(space.interpolate(from, to, 0.0), from);
+            assert_close(space.interpolate(from, to, 1.0), to);
+        }
+    }
+
+    #[test]
+    fn midpoints_depend_on_the_space() {
+        let (black, white) = (Color::BLACK, Color::WHITE);
+        let gray = |value| Color::rgb(value, value, value);
+        assert_close(ColorSpace::Srgb.interpolate(black, white, 0.5), gray(0.5));
+        // Half the light is brighter than half the sRGB value.
+        assert_close(
+            ColorSpace::LinearRgb.interpolate(black, white, 0.5),
+            gray(0.735_357),
+        );
+        // Red to blue goes the short way around the hue circle.
+        assert_close(
+            ColorSpace::Hsl.interpolate(Color::rgb(1.0, 0.0, 0.0), Color::rgb(0.0, 0.0, 1.0), 0.5),
+            Color::rgb(1.0, 0.0, 1.0),
+        );
+        // Grays stay gray.
+        let [_, a, b] = ColorSpace::Oklab.interpolate(black, white, 0.5).to_oklab();
+        assert!(a.abs() < 1e-4 && b.abs() < 1e-4);
+    }
+
+    #[test]
+    fn hsl_from_gray_keeps_the_hue() {
+        let red = Color::rgb(1.0, 0.0, 0.0);
+        let hsl = lerp_hsl(gray_hsl(), red.to_hsl(), 0.5);
+        assert!(hue_distance(hsl[0], 0.0) < 1e-6);
+        let hsl = lerp_hsl(Color::rgb(0.0, 1.0, 0.0).to_hsl(), gray_hsl(), 0.5);
+        assert!(hue_distance(hsl[0], 120.0) < 1e-4);
+    }
+
+    fn gray_hsl() -> [f32; 3] {
+        Color::rgb(0.5, 0.5, 0.5).to_hsl()
+    }
+
+    #[test]
+    fn transition_ends_at_the_target() {
+        let target = Color::rgb(1.0, 0.5, 0.0);
+        let mut transition = Transition::new(
+            Color::BLACK,
+            target,
+            0.5,
+            Easing::EaseInOut,
+            ColorSpace::Oklab,
+        );
+        assert_close(transition.color(), Color::BLACK);
+        transition.advance(0.25);
+        assert!(!transition.is_finished());
+        transition.advance(0.5);
+        assert!(transition.is_finished());
+        assert_close(transition.color(), target);
+
+        let instant = Transition::new(
+            Color::BLACK,
+            Color::WHITE,
+            0.0,
+            Easing::Linear,
+            ColorSpace::Srgb,
+        );
+        assert!(instant.is_finished());
+        assert_close(instant.color(), Color::WHITE);
+    }
+}
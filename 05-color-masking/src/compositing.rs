@@ -0,0 +1,133 @@
+/// How the page combines the drawing buffer of the canvas with what is
+/// behind it, chosen with the `alpha` and `premultipliedAlpha` attributes of
+/// the context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compositing {
+    /// `alpha: false`, the canvas covers the page.
+    Opaque,
+    /// `premultipliedAlpha: true`, the default.
+    Premultiplied,
+    /// `premultipliedAlpha: false`.
+    Straight,
+}
+
+impl Compositing {
+    /// Read from the `alpha=0` and `premultiplied=0` parameters of the query
+    /// string of the page.
+    pub fn from_query(search: &str) -> Compositing {
+        let parameters: Vec<&str> = search.trim_start_matches('?').split('&').collect();
+        if parameters.contains(&"alpha=0") {
+            Compositing::Opaque
+        } else if parameters.contains(&"premultiplied=0") {
+            Compositing::Straight
+        } else {
+            Compositing::Premultiplied
+        }
+    }
+
+    /// The query string selecting `self`, see [`from_query`](Self::from_query).
+    pub fn to_query(self) -> &'static str {
+        match self {
+            Compositing::Opaque => "?alpha=0",
+            Compositing::Premultiplied => "",
+            Compositing::Straight => "?premultiplied=0",
+        }
+    }
+
+    /// Values of the `alpha` and `premultipliedAlpha` context attributes.
+    pub fn context_attributes(self) -> (bool, bool) {
+        match self {
+            Compositing::Opaque => (false, true),
+            Compositing::Premultiplied => (true, true),
+            Compositing::Straight => (true, false),
+        }
+    }
+
+    /// The `blendFunc` factors which give the same result, the canvas
+    /// being the source and the page the destination.
+    pub fn blend_func(self) -> &'static str {
+        match self {
+            Compositing::Opaque => "ONE, ZERO",
+            Compositing::Premultiplied => "ONE, ONE_MINUS_SRC_ALPHA",
+            Compositing::Straight => "SRC_ALPHA, ONE_MINUS_SRC_ALPHA",
+        }
+    }
+
+    /// Color the page shows for `pixel` of the drawing buffer in front of
+    /// `background`. Premultiplied pixels with more color than alpha, which
+    /// masking the alpha channel easily produces, add light to the page.
+    pub fn composite(self, pixel: [f32; 4], background: [f32; 3]) -> [f32; 3] {
+        let alpha = pixel[3];
+        let (source, destination) = match self {
+            Compositing::Opaque => (1.0, 0.0),
+            Compositing::Premultiplied => (1.0, 1.0 - alpha),
+            Compositing::Straight => (alpha, 1.0 - alpha),
+        };
+        [0, 1, 2]
+            .map(|index| (pixel[index] * source + background[index] * destination).clamp(0.0, 1.0))
+    }
+}
+
+/// The drawing buffer after clearing it from transparent black, as every
+/// frame starts, with `color` writing only the channels in `mask`.
+pub fn masked_clear(color: [f32; 4], mask: [bool; 4]) -> [f32; 4] {
+    [0, 1, 2, 3].map(|index| if mask[index] { color[index] } else { 0.0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_round_trips() {
+        for compositing in [
+            Compositing::Opaque,
+            Compositing::Premultiplied,
+            Compositing::Straight,
+        ] {
+            assert_eq!(Compositing::from_query(compositing.to_query()), compositing);
+        }
+        assert_eq!(
+            Compositing::from_query("?hud&premultiplied=0"),
+            Compositing::Straight
+        );
+    }
+
+    #[test]
+    fn masked_channels_stay_cleared() {
+        assert_eq!(
+            masked_clear([0.2, 0.4, 0.6, 0.8], [true, false, true, false]),
+            [0.2, 0.0, 0.6, 0.0]
+        );
+    }
+
+    #[test]
+    fn half_transparent_red_over_white() {
+        let white = [1.0; 3];
+        // The same color, stored premultiplied or not.
+        assert_eq!(
+            Compositing::Premultiplied.composite([0.5, 0.0, 0.0, 0.5], white),
+            [1.0, 0.5, 0.5]
+        );
+        assert_eq!(
+            Compositing::Straight.composite([1.0, 0.0, 0.0, 0.5], white),
+            [1.0, 0.5, 0.5]
+        );
+        assert_eq!(
+            Compositing::Opaque.composite([1.0, 0.0, 0.0, 0.5], white),
+            [1.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn premultiplied_color_without_alpha_adds_light() {
+        let gray = [0.5; 3];
+        let pixel = masked_clear([0.25, 0.125, 0.0, 1.0], [true, true, true, false]);
+        assert_eq!(
+            Compositing::Premultiplied.composite(pixel, gray),
+            [0.75, 0.625, 0.5]
+        );
+        // Without premultiplied alpha the color disappears instead.
+        assert_eq!(Compositing::Straight.composite(pixel, gray), gray);
+    }
+}
@@ -1,94 +1,302 @@
-use js_sys::{Math, Object};
-use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod compositing;
+
+pub use compositing::{masked_clear, Compositing};
+
+use js_sys::Object;
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Element, EventTarget, HtmlCanvasElement, MouseEvent, Node, WebGl2RenderingContext, Window,
+    Element, Event, EventTarget, HtmlCanvasElement, HtmlInputElement, HtmlSelectElement, Node,
+    WebGl2RenderingContext, WebGlContextAttributes, Window,
+};
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample,
 };
 
-fn get_random_color() -> [f32; 3] {
-    let r = Math::random() as f32;
-    let g = Math::random() as f32;
-    let b = Math::random() as f32;
-    [r, g, b]
-}
+webgl_common::example_info!();
+
+// A new random color is drawn every second.
+const COLOR_DURATION: f64 = 1.0;
 
-fn draw_animation(gl: &WebGl2RenderingContext) {
-    let color = get_random_color();
-    gl.clear_color(color[0], color[1], color[2], 1.0);
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+// Colors of the pattern behind the canvas, see `color-masking.css`.
+const BACKGROUND_COLORS: [[f32; 3]; 2] = [[0.867, 0.867, 0.867], [0.533, 0.533, 0.533]];
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    gl: C,
+    rng: Rng,
+    mask: [bool; 4],
+    color: Color,
+    // Alpha of the clear color, the random colors are opaque.
+    alpha: f32,
+    compositing: Compositing,
+    elapsed: f64,
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
+impl<C: ClearContext> Scene<C> {
+    pub fn new(mut gl: C, rng: Rng) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+        let mut scene = Scene {
+            gl,
+            rng,
+            mask: [true; 4],
+            color: Color::BLACK,
+            alpha: 1.0,
+            compositing: Compositing::Premultiplied,
+            elapsed: 0.0,
+        };
+        scene.draw_animation();
+        scene
+    }
+
+    fn draw_animation(&mut self) {
+        self.color = Color::random(&mut self.rng);
+        self.apply_clear_color();
+        self.elapsed = 0.0;
+    }
 
-    let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
-    let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
-    let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+    /// The clear color, premultiplied when the page expects it.
+    pub fn clear_value(&self) -> [f32; 4] {
+        let color = self.color.with_alpha(self.alpha);
+        match self.compositing {
+            Compositing::Premultiplied => color.premultiplied(),
+            Compositing::Opaque | Compositing::Straight => color.to_array(),
+        }
+    }
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    /// The drawing buffer after rendering.
+    pub fn pixel(&self) -> [f32; 4] {
+        let pixel = masked_clear(self.clear_value(), self.mask);
+        match self.compositing {
+            // Without an alpha channel alpha reads as one.
+            Compositing::Opaque => [pixel[0], pixel[1], pixel[2], 1.0],
+            Compositing::Premultiplied | Compositing::Straight => pixel,
+        }
+    }
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+    pub fn compositing(&self) -> Compositing {
+        self.compositing
+    }
 
-    let gl1 = Rc::new(gl);
-    let gl2 = Rc::clone(&gl1);
+    /// Tells the scene how the canvas is composited, which decides whether
+    /// the clear color is premultiplied.
+    pub fn set_compositing(&mut self, compositing: Compositing) {
+        self.compositing = compositing;
+        self.apply_clear_color();
+    }
 
-    let redtoggle: Element = document.query_selector("#red-toggle").unwrap().unwrap();
-    let greentoggle: Element = document.query_selector("#green-toggle").unwrap().unwrap();
-    let bluetoggle: Element = document.query_selector("#blue-toggle").unwrap().unwrap();
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha.clamp(0.0, 1.0);
+        self.apply_clear_color();
+    }
 
-    let greentoggle: Rc<Element> = Rc::new(greentoggle);
-    let bluetoggle: Rc<Element> = Rc::new(bluetoggle);
+    fn apply_clear_color(&mut self) {
+        let [r, g, b, a] = self.clear_value();
+        self.gl.clear_color(r, g, b, a);
+    }
 
-    let greentoggle_closure: Rc<Element> = Rc::clone(&greentoggle);
-    let bluetoggle_closure: Rc<Element> = Rc::clone(&bluetoggle);
+    /// Switches writing of the red, green, blue or alpha channel, returns
+    /// whether it is written.
+    pub fn toggle_channel(&mut self, index: usize) -> bool {
+        self.mask[index] = !self.mask[index];
+        let [red, green, blue, alpha] = self.mask;
+        self.gl.color_mask(red, green, blue, alpha);
+        self.draw_animation();
+        self.mask[index]
+    }
 
-    let draw_animation_closure = Closure::<dyn Fn()>::new(move || draw_animation(&gl2));
-    drop(
-        window.set_interval_with_callback_and_timeout_and_arguments_0(
-            draw_animation_closure.as_ref().unchecked_ref(),
-            1000,
-        ),
-    );
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
+        if self.elapsed >= COLOR_DURATION {
+            self.draw_animation();
+        }
+    }
 
-    let mut mask: [bool; 3] = [true, true, true];
+    pub fn render(&mut self) {
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    }
+}
+
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[300, 150]];
+
+    // All channels are written.
+    fn new(mut contexts: Vec<SoftwareContext>, rng: Rng) -> Self {
+        Scene::new(contexts.remove(0), rng)
+    }
+
+    fn update(&mut self, dt: f64) {
+        Scene::update(self, dt);
+    }
+
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+pub struct ColorMasking {
+    scene: Scene<Gl>,
+    toggles: [Element; 4],
+    alpha: HtmlInputElement,
+    context_alpha: HtmlInputElement,
+    premultiplied: HtmlSelectElement,
+    buffer_pixel: Element,
+    blend_func: Element,
+    swatches: [Element; 2],
+    // Drawing buffer shown in the panel.
+    shown_pixel: Option<[f32; 4]>,
+}
 
-    let set_color_mask_closure = Closure::<dyn FnMut(MouseEvent)>::new(move |event: MouseEvent| {
-        let event_target: EventTarget = event.target().unwrap();
+impl ColorMasking {
+    // Shows the drawing buffer, the equivalent blend function and the
+    // colors the page should show over both colors of the pattern.
+    fn show_panel(&mut self) {
+        let pixel = self.scene.pixel();
+        if self.shown_pixel == Some(pixel) {
+            return;
+        }
+        self.shown_pixel = Some(pixel);
 
-        let greentoggle_event_target: &EventTarget = greentoggle_closure.as_ref();
-        let bluetoggle_event_target: &EventTarget = bluetoggle_closure.as_ref();
+        let [r, g, b, a] = pixel;
+        self.buffer_pixel
+            .set_text_content(Some(&format!("rgba({r:.2}, {g:.2}, {b:.2}, {a:.2})")));
+        let compositing = self.scene.compositing();
+        self.blend_func
+            .set_text_content(Some(&format!("blendFunc({})", compositing.blend_func())));
+        for (swatch, background) in self.swatches.iter().zip(BACKGROUND_COLORS) {
+            let color = Color::from(compositing.composite(pixel, background));
+            drop(swatch.set_attribute("style", &format!("background-color: {}", color.to_hex())));
+        }
+    }
 
-        let index = if &event_target == greentoggle_event_target {
-            1
-        } else if &event_target == bluetoggle_event_target {
-            2
+    // Reloads the page with the context attributes of the form, they can
+    // only be chosen when the context is created.
+    fn reload_with_context_attributes(&self) {
+        let compositing = if !self.context_alpha.checked() {
+            Compositing::Opaque
+        } else if self.premultiplied.value() == "false" {
+            Compositing::Straight
         } else {
-            0
+            Compositing::Premultiplied
         };
+        let location = web_sys::window().unwrap().location();
+        drop(location.set_search(compositing.to_query()));
+    }
+}
 
-        mask[index] = !mask[index];
+impl Example for ColorMasking {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
 
-        event_target
-            .unchecked_into::<Node>()
-            .set_text_content(Some(if mask[index] { "On" } else { "Off" }));
+        let search = window.location().search().unwrap_or_default();
+        let compositing = Compositing::from_query(&search);
+        let (alpha, premultiplied_alpha) = compositing.context_attributes();
+        let attributes = WebGlContextAttributes::new();
+        attributes.set_alpha(alpha);
+        attributes.set_premultiplied_alpha(premultiplied_alpha);
+
+        let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
+        let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
+        let context: Object = canvas
+            .get_context_with_context_options("webgl2", &attributes)
+            .unwrap()
+            .unwrap();
+
+        let gl: Gl = context.unchecked_into::<Gl>();
+
+        let redtoggle: Element = document.query_selector("#red-toggle").unwrap().unwrap();
+        let greentoggle: Element = document.query_selector("#green-toggle").unwrap().unwrap();
+        let bluetoggle: Element = document.query_selector("#blue-toggle").unwrap().unwrap();
+        let alphatoggle: Element = document.query_selector("#alpha-toggle").unwrap().unwrap();
+        let element = |selector: &str| document.query_selector(selector).unwrap().unwrap();
+
+        let alpha: HtmlInputElement = element("#clear-alpha").unchecked_into();
+        let context_alpha: HtmlInputElement = element("#context-alpha").unchecked_into();
+        let premultiplied: HtmlSelectElement = element("#premultiplied-alpha").unchecked_into();
+        context_alpha.set_checked(compositing != Compositing::Opaque);
+        premultiplied.set_value(if compositing == Compositing::Straight {
+            "false"
+        } else {
+            "true"
+        });
+        premultiplied.set_disabled(compositing == Compositing::Opaque);
+
+        setup.listen(&redtoggle, "click");
+        setup.listen(&greentoggle, "click");
+        setup.listen(&bluetoggle, "click");
+        setup.listen(&alphatoggle, "click");
+        setup.listen(&alpha, "input");
+        setup.listen(&context_alpha, "change");
+        setup.listen(&premultiplied, "change");
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+        setup.animate();
 
-        gl1.color_mask(mask[0], mask[1], mask[2], true);
+        let mut scene = Scene::new(gl, Rng::from_entropy());
+        scene.set_compositing(compositing);
+        scene.set_alpha(alpha.value_as_number() as f32);
 
-        draw_animation(&gl1);
-    });
+        let mut example = ColorMasking {
+            scene,
+            toggles: [redtoggle, greentoggle, bluetoggle, alphatoggle],
+            alpha,
+            context_alpha,
+            premultiplied,
+            buffer_pixel: element("#buffer-pixel"),
+            blend_func: element("#blend-func"),
+            swatches: [element("#over-light"), element("#over-dark")],
+            shown_pixel: None,
+        };
+        example.show_panel();
+        Ok(example)
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.scene.update(dt);
+    }
 
-    let set_color_mask = set_color_mask_closure.as_ref().unchecked_ref();
+    fn render(&mut self) {
+        self.scene.render();
+        self.show_panel();
+    }
 
-    drop(redtoggle.add_event_listener_with_callback("click", set_color_mask));
-    drop(greentoggle.add_event_listener_with_callback("click", set_color_mask));
-    drop(bluetoggle.add_event_listener_with_callback("click", set_color_mask));
+    fn on_event(&mut self, event: &Event) {
+        let event_target: EventTarget = event.current_target().unwrap();
 
-    set_color_mask_closure.forget();
-    draw_animation_closure.forget();
+        if AsRef::<EventTarget>::as_ref(&self.alpha) == &event_target {
+            self.scene.set_alpha(self.alpha.value_as_number() as f32);
+            return;
+        }
+        if AsRef::<EventTarget>::as_ref(&self.context_alpha) == &event_target
+            || AsRef::<EventTarget>::as_ref(&self.premultiplied) == &event_target
+        {
+            self.reload_with_context_attributes();
+            return;
+        }
 
+        let index = match self
+            .toggles
+            .iter()
+            .position(|toggle| AsRef::<EventTarget>::as_ref(toggle) == &event_target)
+        {
+            Some(index) => index,
+            None => return,
+        };
+
+        let written = self.scene.toggle_channel(index);
+        event_target
+            .unchecked_into::<Node>()
+            .set_text_content(Some(if written { "On" } else { "Off" }));
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<ColorMasking>()?.keep_until_unload();
     Ok(())
 }
@@ -1,35 +1,319 @@
+mod region;
+
+pub use region::{Corner, Grab, Region, Regions};
+
 use js_sys::Object;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlCanvasElement, WebGl2RenderingContext, Window};
+use web_sys::{
+    Element, Event, EventTarget, HtmlButtonElement, HtmlCanvasElement, HtmlInputElement,
+    MouseEvent, PointerEvent, WebGl2RenderingContext, Window,
+};
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample,
+};
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
+webgl_common::example_info!();
+
+/// Size of the drawing buffer the default region is placed in.
+const REFERENCE_SIZE: [i32; 2] = [280, 210];
+
+/// Size of the corner handles in CSS pixels.
+const HANDLE_SIZE: f64 = 10.0;
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    gl: C,
+    regions: Regions,
+    // Size of the corner handles in drawing buffer pixels.
+    handle_size: i32,
+}
+
+impl<C: ClearContext> Scene<C> {
+    /// Handles of the selected region are `handle_size` pixels large.
+    pub fn new(mut gl: C, handle_size: i32) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+
+        // Enable scissoring operation, the position and size of the
+        // scissoring area are defined by each region before clearing.
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
 
-    let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
-    let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
-    let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+        // A solid yellow region, placed like in a 280x210 drawing buffer.
+        let mut regions = Regions::new(REFERENCE_SIZE);
+        regions.add(Region::new(40, 20, 60, 130), Color::rgb(1.0, 1.0, 0.0));
+        regions.select(None);
+        regions.resize([width, height]);
 
-    // The following two lines set the size (in CSS pixels) of
-    // the drawing buffer to be identical to the size of the
-    // canvas HTML element, as determined by CSS.
-    canvas.set_width(canvas.client_width() as u32);
-    canvas.set_height(canvas.client_height() as u32);
+        Scene {
+            gl,
+            regions,
+            handle_size,
+        }
+    }
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+    pub fn regions_mut(&mut self) -> &mut Regions {
+        &mut self.regions
+    }
 
-    // Enable scissoring operation and define the position and
-    // size of the scissoring area.
-    gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
-    gl.scissor(40, 20, 60, 130);
+    pub fn handle_size(&self) -> i32 {
+        self.handle_size
+    }
 
-    // Clear the drawing buffer solid yellow.
-    gl.clear_color(1.0, 1.0, 0.0, 1.0);
+    pub fn resize(&mut self, width: i32, height: i32, handle_size: i32) {
+        self.gl.viewport(0, 0, width, height);
+        self.regions.resize([width, height]);
+        self.handle_size = handle_size;
+    }
+
+    pub fn render(&mut self) {
+        // Clear each region with its own color, later ones over
+        // earlier ones.
+        for &(region, color) in self.regions.regions() {
+            clear_rect(&mut self.gl, region, color);
+        }
+
+        // Draw the handles of the selected region, white squares with a
+        // black border, by clearing them too.
+        if let Some((region, _)) = self.regions.selected_region() {
+            let border = (self.handle_size / 5).max(1);
+            for corner in Corner::ALL {
+                let handle = region.handle(corner, self.handle_size);
+                clear_rect(&mut self.gl, handle, Color::BLACK);
+                clear_rect(&mut self.gl, handle.inset(border), Color::WHITE);
+            }
+        }
+    }
+}
+
+// Clears `region` of the drawing buffer with `color`.
+fn clear_rect(gl: &mut impl ClearContext, region: Region, color: Color) {
+    gl.scissor(region.x, region.y, region.width, region.height);
+    gl.clear_color(color.r, color.g, color.b, color.a);
     gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+}
+
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[280, 210]];
+
+    fn new(mut contexts: Vec<SoftwareContext>, _rng: Rng) -> Self {
+        Scene::new(contexts.remove(0), HANDLE_SIZE as i32)
+    }
+
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+// Drawing buffer pixels per CSS pixel of `canvas`.
+fn buffer_scale(canvas: &HtmlCanvasElement) -> [f64; 2] {
+    [
+        canvas.width() as f64 / canvas.client_width().max(1) as f64,
+        canvas.height() as f64 / canvas.client_height().max(1) as f64,
+    ]
+}
+
+// Size of the corner handles in drawing buffer pixels of `canvas`.
+fn handle_size(canvas: &HtmlCanvasElement) -> i32 {
+    ((HANDLE_SIZE * buffer_scale(canvas)[0]).round() as i32).max(1)
+}
 
+pub struct BasicScissoring {
+    scene: Scene<Gl>,
+    rng: Rng,
+    canvas: HtmlCanvasElement,
+    // Position, size and color of the selected region.
+    inputs: [HtmlInputElement; 4],
+    color: HtmlInputElement,
+    add: Element,
+    remove: HtmlButtonElement,
+}
+
+impl BasicScissoring {
+    // Converts the position of a pointer event to drawing buffer
+    // coordinates. The event is in CSS pixels from the top left corner
+    // of the canvas, the drawing buffer in device pixels from its bottom
+    // left corner.
+    fn pointer_position(&self, event: &MouseEvent) -> [i32; 2] {
+        let scale = buffer_scale(&self.canvas);
+        let height = self.canvas.height() as i32;
+        [
+            (event.offset_x() as f64 * scale[0]) as i32,
+            height - 1 - (event.offset_y() as f64 * scale[1]) as i32,
+        ]
+    }
+
+    // Shows the selected region in the inputs, which are disabled
+    // while none is selected.
+    fn show_selected(&self) {
+        let selected = self.scene.regions().selected_region();
+        if let Some((region, color)) = selected {
+            let values = [region.x, region.y, region.width, region.height];
+            for (input, value) in self.inputs.iter().zip(values) {
+                input.set_value_as_number(value as f64);
+            }
+            self.color.set_value(&color.with_alpha(1.0).to_hex());
+        }
+        for input in self.inputs.iter().chain([&self.color]) {
+            input.set_disabled(selected.is_none());
+        }
+        self.remove.set_disabled(selected.is_none());
+    }
+
+    // Applies the inputs to the selected region, then shows it again as
+    // it may have been clamped to the drawing buffer.
+    fn apply_inputs(&mut self) {
+        let [x, y, width, height] = self.inputs.each_ref().map(|input| {
+            let value = input.value_as_number();
+            if value.is_finite() {
+                value as i32
+            } else {
+                0
+            }
+        });
+        let color = self.color.value().parse().unwrap_or(Color::BLACK);
+        self.scene
+            .regions_mut()
+            .set_selected(Region::new(x, y, width, height), color);
+        self.show_selected();
+    }
+
+    // Limits the inputs to the drawing buffer.
+    fn set_input_limits(&self) {
+        let size = [self.canvas.width(), self.canvas.height()];
+        for (input, limit) in self.inputs.iter().zip([size[0], size[1], size[0], size[1]]) {
+            input.set_max(&limit.to_string());
+        }
+    }
+
+    fn set_cursor(&self, grab: Option<Grab>) {
+        let cursor = match grab {
+            None => "default",
+            Some(Grab::Move) => "move",
+            Some(Grab::Resize(Corner::BottomLeft | Corner::TopRight)) => "nesw-resize",
+            Some(Grab::Resize(Corner::TopLeft | Corner::BottomRight)) => "nwse-resize",
+        };
+        drop(self.canvas.style().set_property("cursor", cursor));
+    }
+}
+
+impl Example for BasicScissoring {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        let element = |selector: &str| document.query_selector(selector).unwrap().unwrap();
+
+        let canvas: Element = element("canvas");
+        let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
+        let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+
+        // The following two lines set the size (in CSS pixels) of
+        // the drawing buffer to be identical to the size of the
+        // canvas HTML element, as determined by CSS.
+        canvas.set_width(canvas.client_width() as u32);
+        canvas.set_height(canvas.client_height() as u32);
+
+        let gl: Gl = context.unchecked_into::<Gl>();
+
+        let inputs = ["#region-x", "#region-y", "#region-width", "#region-height"]
+            .map(|selector| element(selector).unchecked_into::<HtmlInputElement>());
+        let color: HtmlInputElement = element("#region-color").unchecked_into();
+        let add = element("#add-region");
+        let remove: HtmlButtonElement = element("#remove-region").unchecked_into();
+
+        for event_type in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+            setup.listen(&canvas, event_type);
+        }
+        for input in inputs.iter().chain([&color]) {
+            setup.listen(input, "input");
+        }
+        setup.listen(&add, "click");
+        setup.listen(&remove, "click");
+        setup.observe_resize(&canvas, &gl);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+
+        let example = BasicScissoring {
+            scene: Scene::new(gl, handle_size(&canvas)),
+            rng: Rng::from_entropy(),
+            canvas,
+            inputs,
+            color,
+            add,
+            remove,
+        };
+        example.set_input_limits();
+        example.show_selected();
+        Ok(example)
+    }
+
+    fn render(&mut self) {
+        self.scene.render();
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.scene
+            .resize(width as i32, height as i32, handle_size(&self.canvas));
+        self.set_input_limits();
+        self.show_selected();
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let event_target: EventTarget = event.current_target().unwrap();
+
+        if AsRef::<EventTarget>::as_ref(&self.canvas) == &event_target {
+            let event: &PointerEvent = event.unchecked_ref();
+            let point = self.pointer_position(event);
+            let handle_size = self.scene.handle_size();
+            match event.type_().as_str() {
+                "pointerdown" => {
+                    if self.scene.regions_mut().pointer_down(point, handle_size) {
+                        drop(self.canvas.set_pointer_capture(event.pointer_id()));
+                        self.show_selected();
+                    }
+                }
+                "pointermove" => {
+                    if self.scene.regions_mut().pointer_move(point) {
+                        self.show_selected();
+                    }
+                    if !self.scene.regions().is_dragging() {
+                        let grab = self.scene.regions().grab_at(point, handle_size);
+                        self.set_cursor(grab.map(|(_, grab)| grab));
+                    }
+                }
+                _ => self.scene.regions_mut().pointer_up(),
+            }
+            return;
+        }
+
+        if AsRef::<EventTarget>::as_ref(&self.add) == &event_target {
+            // A quarter of the drawing buffer in the middle.
+            let (width, height) = (self.canvas.width() as i32, self.canvas.height() as i32);
+            let region = Region::new(width * 3 / 8, height * 3 / 8, width / 4, height / 4);
+            let color = Color::random(&mut self.rng);
+            self.scene.regions_mut().add(region, color);
+            self.show_selected();
+            return;
+        }
+        if AsRef::<EventTarget>::as_ref(&self.remove) == &event_target {
+            self.scene.regions_mut().remove_selected();
+            self.show_selected();
+            return;
+        }
+
+        self.apply_inputs();
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<BasicScissoring>()?.keep_until_unload();
     Ok(())
 }
@@ -0,0 +1,428 @@
+use webgl_common::Color;
+
+/// Regions are never made smaller than this many pixels.
+pub const MIN_SIZE: i32 = 8;
+
+/// A scissor box in drawing buffer pixels, measured from the bottom left
+/// corner like in WebGL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::BottomLeft,
+        Corner::BottomRight,
+        Corner::TopLeft,
+        Corner::TopRight,
+    ];
+
+    fn is_right(self) -> bool {
+        matches!(self, Corner::BottomRight | Corner::TopRight)
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+}
+
+/// What dragging a region changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grab {
+    Move,
+    Resize(Corner),
+}
+
+impl Region {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(self, point: [i32; 2]) -> bool {
+        (self.x..self.x + self.width).contains(&point[0])
+            && (self.y..self.y + self.height).contains(&point[1])
+    }
+
+    pub fn corner(self, corner: Corner) -> [i32; 2] {
+        [
+            if corner.is_right() {
+                self.x + self.width
+            } else {
+                self.x
+            },
+            if corner.is_top() {
+                self.y + self.height
+            } else {
+                self.y
+            },
+        ]
+    }
+
+    /// Square of `size` pixels centered on `corner`, where a handle is
+    /// drawn and grabbed.
+    pub fn handle(self, corner: Corner, size: i32) -> Region {
+        let [x, y] = self.corner(corner);
+        Region::new(x - size / 2, y - size / 2, size, size)
+    }
+
+    /// The region shrunk by `amount` pixels on every side, never to a
+    /// negative size, which the scissor box does not accept.
+    pub fn inset(self, amount: i32) -> Region {
+        Region::new(
+            self.x + amount,
+            self.y + amount,
+            (self.width - 2 * amount).max(0),
+            (self.height - 2 * amount).max(0),
+        )
+    }
+
+    /// How the region is dragged from `point`, handles of `handle_size`
+    /// pixels taking precedence over the inside.
+    pub fn grab_at(self, point: [i32; 2], handle_size: i32) -> Option<Grab> {
+        Corner::ALL
+            .into_iter()
+            .find(|&corner| self.handle(corner, handle_size).contains(point))
+            .map(Grab::Resize)
+            .or_else(|| self.contains(point).then_some(Grab::Move))
+    }
+
+    /// The region moved by `delta`.
+    pub fn moved(self, delta: [i32; 2]) -> Region {
+        Region::new(
+            self.x + delta[0],
+            self.y + delta[1],
+            self.width,
+            self.height,
+        )
+    }
+
+    /// The region with `corner` moved by `delta` and the opposite corner in
+    /// place, at least [`MIN_SIZE`] pixels wide and high.
+    pub fn resized(self, corner: Corner, delta: [i32; 2]) -> Region {
+        let (mut left, mut right) = (self.x, self.x + self.width);
+        let (mut bottom, mut top) = (self.y, self.y + self.height);
+        if corner.is_right() {
+            right = (right + delta[0]).max(left + MIN_SIZE);
+        } else {
+            left = (left + delta[0]).min(right - MIN_SIZE);
+        }
+        if corner.is_top() {
+            top = (top + delta[1]).max(bottom + MIN_SIZE);
+        } else {
+            bottom = (bottom + delta[1]).min(top - MIN_SIZE);
+        }
+        Region::new(left, bottom, right - left, top - bottom)
+    }
+
+    /// The region shrunk to fit a drawing buffer of `size`, at least
+    /// [`MIN_SIZE`] pixels large, and moved inside it.
+    pub fn clamped(self, size: [i32; 2]) -> Region {
+        let width = self.width.clamp(MIN_SIZE.min(size[0]), size[0].max(0));
+        let height = self.height.clamp(MIN_SIZE.min(size[1]), size[1].max(0));
+        Region::new(
+            self.x.clamp(0, size[0] - width),
+            self.y.clamp(0, size[1] - height),
+            width,
+            height,
+        )
+    }
+
+    /// The region covering the same part of a drawing buffer resized from
+    /// `from` to `to`.
+    pub fn scaled(self, from: [i32; 2], to: [i32; 2]) -> Region {
+        let scale = |value: i32, axis: usize| {
+            (value as f64 * to[axis] as f64 / from[axis].max(1) as f64).round() as i32
+        };
+        Region::new(
+            scale(self.x, 0),
+            scale(self.y, 1),
+            scale(self.width, 0),
+            scale(self.height, 1),
+        )
+        .clamped(to)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    index: usize,
+    grab: Grab,
+    start: [i32; 2],
+    original: Region,
+}
+
+/// Scissor regions, each cleared with its own color in order, and the
+/// state of dragging them with the pointer.
+#[derive(Clone, Debug)]
+pub struct Regions {
+    regions: Vec<(Region, Color)>,
+    size: [i32; 2],
+    selected: Option<usize>,
+    drag: Option<Drag>,
+}
+
+impl Regions {
+    /// No regions in a drawing buffer of `size`.
+    pub fn new(size: [i32; 2]) -> Self {
+        Self {
+            regions: Vec::new(),
+            size,
+            selected: None,
+            drag: None,
+        }
+    }
+
+    pub fn regions(&self) -> &[(Region, Color)] {
+        &self.regions
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn selected_region(&self) -> Option<(Region, Color)> {
+        self.selected.map(|index| self.regions[index])
+    }
+
+    /// Adds a region, clamped to the drawing buffer, and selects it.
+    pub fn add(&mut self, region: Region, color: Color) {
+        self.regions.push((region.clamped(self.size), color));
+        self.selected = Some(self.regions.len() - 1);
+    }
+
+    pub fn remove_selected(&mut self) {
+        if let Some(index) = self.selected.take() {
+            self.regions.remove(index);
+            self.drag = None;
+        }
+    }
+
+    /// Replaces the selected region, clamped to the drawing buffer.
+    pub fn set_selected(&mut self, region: Region, color: Color) {
+        if let Some(index) = self.selected {
+            self.regions[index] = (region.clamped(self.size), color);
+        }
+    }
+
+    /// Scales all regions to a resized drawing buffer.
+    pub fn resize(&mut self, size: [i32; 2]) {
+        for (region, _) in &mut self.regions {
+            *region = region.scaled(self.size, size);
+        }
+        self.size = size;
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|&index| index < self.regions.len());
+    }
+
+    /// The region at `point` and how it is dragged from there: the handles
+    /// of the selected region, which may stick out of it, come first, then
+    /// the topmost region.
+    pub fn grab_at(&self, point: [i32; 2], handle_size: i32) -> Option<(usize, Grab)> {
+        self.selected
+            .and_then(|index| {
+                let grab = self.regions[index].0.grab_at(point, handle_size)?;
+                Some((index, grab))
+            })
+            .or_else(|| {
+                self.regions
+                    .iter()
+                    .rposition(|(region, _)| region.contains(point))
+                    .map(|index| (index, Grab::Move))
+            })
+    }
+
+    /// Selects the region at `point` and starts dragging it, returns
+    /// whether the selection changed or a drag started.
+    pub fn pointer_down(&mut self, point: [i32; 2], handle_size: i32) -> bool {
+        let grab = self.grab_at(point, handle_size);
+        let previous = self.selected;
+        self.selected = grab.map(|(index, _)| index);
+        self.drag = grab.map(|(index, grab)| Drag {
+            index,
+            grab,
+            start: point,
+            original: self.regions[index].0,
+        });
+        self.drag.is_some() || previous != self.selected
+    }
+
+    /// Drags the region grabbed by [`pointer_down`](Self::pointer_down),
+    /// returns whether it changed.
+    pub fn pointer_move(&mut self, point: [i32; 2]) -> bool {
+        let Some(drag) = self.drag else {
+            return false;
+        };
+        let delta = [point[0] - drag.start[0], point[1] - drag.start[1]];
+        let region = match drag.grab {
+            Grab::Move => drag.original.moved(delta),
+            Grab::Resize(corner) => drag.original.resized(corner, delta),
+        };
+        let region = region.clamped(self.size);
+        let changed = self.regions[drag.index].0 != region;
+        self.regions[drag.index].0 = region;
+        changed
+    }
+
+    pub fn pointer_up(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: [i32; 2] = [280, 210];
+
+    fn regions() -> Regions {
+        let mut regions = Regions::new(SIZE);
+        regions.add(Region::new(40, 20, 60, 130), Color::rgb(1.0, 1.0, 0.0));
+        regions.add(Region::new(80, 100, 100, 80), Color::rgb(0.0, 1.0, 1.0));
+        regions
+    }
+
+    #[test]
+    fn handles_are_centered_on_corners() {
+        let region = Region::new(40, 20, 60, 130);
+        assert_eq!(region.corner(Corner::TopRight), [100, 150]);
+        assert_eq!(
+            region.handle(Corner::BottomLeft, 8),
+            Region::new(36, 16, 8, 8)
+        );
+        assert_eq!(
+            region.grab_at([99, 149], 8),
+            Some(Grab::Resize(Corner::TopRight))
+        );
+        assert_eq!(region.grab_at([60, 60], 8), Some(Grab::Move));
+        assert_eq!(region.grab_at([10, 10], 8), None);
+    }
+
+    #[test]
+    fn resizing_keeps_the_opposite_corner() {
+        let region = Region::new(40, 20, 60, 130);
+        assert_eq!(
+            region.resized(Corner::BottomLeft, [10, -5]),
+            Region::new(50, 15, 50, 135)
+        );
+        // Dragging past the opposite corner stops at the minimum size.
+        assert_eq!(
+            region.resized(Corner::TopRight, [-100, -200]),
+            Region::new(40, 20, MIN_SIZE, MIN_SIZE)
+        );
+    }
+
+    #[test]
+    fn clamping_keeps_regions_inside() {
+        assert_eq!(
+            Region::new(-10, 200, 50, 50).clamped(SIZE),
+            Region::new(0, 160, 50, 50)
+        );
+        assert_eq!(
+            Region::new(10, 10, 500, 2).clamped(SIZE),
+            Region::new(0, 10, 280, MIN_SIZE)
+        );
+        // Buffers smaller than the minimum size are covered.
+        assert_eq!(
+            Region::new(3, 3, 10, 10).clamped([4, 4]),
+            Region::new(0, 0, 4, 4)
+        );
+    }
+
+    #[test]
+    fn scaling_follows_the_drawing_buffer() {
+        let region = Region::new(40, 20, 60, 130);
+        assert_eq!(
+            region.scaled(SIZE, [560, 420]),
+            Region::new(80, 40, 120, 260)
+        );
+        assert_eq!(
+            region.scaled(SIZE, [560, 420]).scaled([560, 420], SIZE),
+            region
+        );
+    }
+
+    #[test]
+    fn topmost_region_is_dragged() {
+        let mut regions = regions();
+        regions.select(None);
+        // Inside both, the second one is drawn over the first.
+        assert!(regions.pointer_down([90, 120], 8));
+        assert_eq!(regions.selected(), Some(1));
+        assert!(regions.pointer_move([100, 110]));
+        assert_eq!(regions.regions()[1].0, Region::new(90, 90, 100, 80));
+        regions.pointer_up();
+        assert!(!regions.pointer_move([0, 0]));
+    }
+
+    #[test]
+    fn dragging_stops_at_the_edges() {
+        let mut regions = regions();
+        regions.pointer_down([50, 30], 8);
+        assert_eq!(regions.selected(), Some(0));
+        regions.pointer_move([-1000, 30]);
+        assert_eq!(regions.regions()[0].0, Region::new(0, 20, 60, 130));
+    }
+
+    #[test]
+    fn handle_of_selected_region_resizes() {
+        let mut regions = regions();
+        // The bottom left handle of the selected second region.
+        regions.pointer_down([78, 98], 8);
+        regions.pointer_move([68, 88]);
+        assert_eq!(regions.regions()[1].0, Region::new(70, 90, 110, 90));
+    }
+
+    #[test]
+    fn clicking_outside_deselects() {
+        let mut regions = regions();
+        assert!(regions.pointer_down([270, 5], 8));
+        assert_eq!(regions.selected(), None);
+        assert!(!regions.is_dragging());
+        assert!(!regions.pointer_down([270, 5], 8));
+    }
+
+    #[test]
+    fn selected_region_is_replaced_and_removed() {
+        let mut regions = regions();
+        let color = Color::rgb(1.0, 0.0, 0.0);
+        regions.set_selected(Region::new(250, 0, 60, 60), color);
+        assert_eq!(
+            regions.selected_region(),
+            Some((Region::new(220, 0, 60, 60), color))
+        );
+        regions.remove_selected();
+        assert_eq!(regions.regions().len(), 1);
+        assert_eq!(regions.selected(), None);
+    }
+
+    #[test]
+    fn insets_never_get_negative() {
+        let handle = Region::new(10, 20, 10, 10);
+        assert_eq!(handle.inset(2), Region::new(12, 22, 6, 6));
+        assert_eq!(handle.inset(5), Region::new(15, 25, 0, 0));
+        assert_eq!(Region::new(0, 0, 1, 1).inset(1), Region::new(1, 1, 0, 0));
+    }
+}
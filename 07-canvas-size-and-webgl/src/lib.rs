@@ -1,36 +1,282 @@
-use js_sys::Object;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod sizing;
+
+pub use sizing::{Diagnostics, Sizing};
+
+use js_sys::{Int32Array, Object};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
-use web_sys::{HtmlCanvasElement, HtmlCollection, WebGl2RenderingContext, Window};
+use web_sys::{
+    console, Document, Element, Event, EventTarget, HtmlCanvasElement, HtmlCollection,
+    HtmlSelectElement, WebGl2RenderingContext, Window,
+};
+use webgl_common::{ClearContext, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample};
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let canvases: HtmlCollection = document.get_elements_by_tag_name("canvas");
-    let first_canvas = canvases
-        .item(0)
-        .unwrap()
-        .unchecked_into::<HtmlCanvasElement>();
-    let second_canvas = canvases
-        .item(1)
-        .unwrap()
-        .unchecked_into::<HtmlCanvasElement>();
-
-    first_canvas.set_width(first_canvas.client_width() as u32);
-    first_canvas.set_height(first_canvas.client_height() as u32);
-
-    vec![first_canvas, second_canvas].iter().for_each(|canvas| {
-        let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
-        let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
-
-        gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+webgl_common::example_info!();
+
+/// What the example draws, into WebGL contexts or software ones.
+pub struct Scene<C> {
+    // One context for each canvas, only the first canvas has its drawing
+    // buffer sized to match the page.
+    gls: Vec<C>,
+}
+
+impl<C: ClearContext> Scene<C> {
+    pub fn new(gls: Vec<C>) -> Self {
+        let mut scene = Scene { gls: Vec::new() };
+        for gl in gls {
+            scene.push(gl);
+        }
+        scene
+    }
+
+    /// Adds the context of another canvas.
+    pub fn push(&mut self, mut gl: C) {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
         gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
         gl.scissor(30, 10, 60, 60);
         gl.clear_color(1.0, 1.0, 0.0, 1.0);
-        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-    });
+        self.gls.push(gl);
+    }
+
+    /// Covers the drawing buffer of the context at `index` again after
+    /// its canvas was resized.
+    pub fn resize(&mut self, index: usize) {
+        let gl = &mut self.gls[index];
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+    }
+
+    pub fn render(&mut self) {
+        for gl in &mut self.gls {
+            gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        }
+    }
+}
+
+impl SoftwareExample for Scene<SoftwareContext> {
+    // The first drawing buffer is sized like the canvas on the page, the
+    // second keeps the default size.
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[120, 80], [300, 150]];
+
+    fn new(contexts: Vec<SoftwareContext>, _rng: Rng) -> Self {
+        Scene::new(contexts)
+    }
+
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        self.gls.iter_mut().collect()
+    }
+}
+
+// A canvas with the panel showing its diagnostics.
+struct CanvasPanel {
+    canvas: HtmlCanvasElement,
+    sizing: Sizing,
+    max_viewport_dims: [i32; 2],
+    // Cells showing the values of `Diagnostics::rows`.
+    values: Vec<Element>,
+}
+
+impl CanvasPanel {
+    // Wraps `canvas` in a figure with a caption naming `sizing` and a
+    // table for the diagnostics.
+    fn new(
+        document: &Document,
+        canvas: HtmlCanvasElement,
+        gl: &Gl,
+        sizing: Sizing,
+    ) -> Result<Self, JsValue> {
+        let figure = document.create_element("figure")?;
+        canvas.replace_with_with_node_1(&figure)?;
+        figure.append_child(&canvas)?;
+
+        let caption = document.create_element("figcaption")?;
+        caption.set_text_content(Some(&sizing.to_string()));
+        figure.append_child(&caption)?;
+
+        let table = document.create_element("table")?;
+        table.set_class_name("diagnostics");
+        figure.append_child(&table)?;
+
+        let max_viewport_dims = gl
+            .get_parameter(WebGl2RenderingContext::MAX_VIEWPORT_DIMS)?
+            .dyn_into::<Int32Array>()
+            .map(|dims| [dims.get_index(0), dims.get_index(1)])
+            .unwrap_or_default();
+
+        let mut panel = CanvasPanel {
+            canvas,
+            sizing,
+            max_viewport_dims,
+            values: Vec::new(),
+        };
+        for (label, _) in panel.diagnostics(gl).rows() {
+            let row = document.create_element("tr")?;
+            let header = document.create_element("th")?;
+            header.set_text_content(Some(label));
+            row.append_child(&header)?;
+            let value = document.create_element("td")?;
+            row.append_child(&value)?;
+            table.append_child(&row)?;
+            panel.values.push(value);
+        }
+        Ok(panel)
+    }
+
+    fn diagnostics(&self, gl: &Gl) -> Diagnostics {
+        let rect = self.canvas.get_bounding_client_rect();
+        Diagnostics {
+            css_size: [rect.width(), rect.height()],
+            attributes: [self.canvas.width(), self.canvas.height()],
+            drawing_buffer: [gl.drawing_buffer_width(), gl.drawing_buffer_height()],
+            device_pixel_ratio: web_sys::window().unwrap().device_pixel_ratio(),
+            max_viewport_dims: self.max_viewport_dims,
+        }
+    }
 
+    // Sets the attributes of the canvas for its sizing, returns whether
+    // they changed.
+    fn apply_sizing(&self) -> bool {
+        let rect = self.canvas.get_bounding_client_rect();
+        let ratio = web_sys::window().unwrap().device_pixel_ratio();
+        let [width, height] = self.sizing.attributes([rect.width(), rect.height()], ratio);
+        if [width, height] == [self.canvas.width(), self.canvas.height()] {
+            return false;
+        }
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        true
+    }
+
+    fn show(&self, gl: &Gl) {
+        for (cell, (_, value)) in self.values.iter().zip(self.diagnostics(gl).rows()) {
+            cell.set_text_content(Some(&value));
+        }
+    }
+}
+
+pub struct CanvasSizeAndWebgl {
+    scene: Scene<Gl>,
+    document: Document,
+    window: Window,
+    // Holds the figures of all canvases.
+    canvases: Element,
+    panels: Vec<CanvasPanel>,
+    sizing: HtmlSelectElement,
+    add: Element,
+}
+
+impl CanvasSizeAndWebgl {
+    fn add_canvas(&mut self, sizing: Sizing) -> Result<(), JsValue> {
+        let canvas: HtmlCanvasElement = self.document.create_element("canvas")?.unchecked_into();
+        canvas.append_with_str_1("Your browser does not seem to support HTML5 canvas.")?;
+        self.canvases.append_child(&canvas)?;
+        let context: Object = canvas
+            .get_context("webgl2")?
+            .ok_or("WebGL 2 is not available")?;
+        let gl: Gl = context.unchecked_into::<Gl>();
+
+        let panel = CanvasPanel::new(&self.document, canvas, &gl, sizing)?;
+        panel.apply_sizing();
+        self.scene.push(gl);
+        self.panels.push(panel);
+        Ok(())
+    }
+}
+
+impl Example for CanvasSizeAndWebgl {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        let element = |selector: &str| document.query_selector(selector).unwrap().unwrap();
+
+        let canvases: HtmlCollection = document.get_elements_by_tag_name("canvas");
+        let first_canvas = canvases
+            .item(0)
+            .unwrap()
+            .unchecked_into::<HtmlCanvasElement>();
+        let second_canvas = canvases
+            .item(1)
+            .unwrap()
+            .unchecked_into::<HtmlCanvasElement>();
+
+        first_canvas.set_width(first_canvas.client_width() as u32);
+        first_canvas.set_height(first_canvas.client_height() as u32);
+
+        let gls: Vec<Gl> = [&first_canvas, &second_canvas]
+            .iter()
+            .enumerate()
+            .map(|(index, canvas)| {
+                let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
+                let gl: Gl = context.unchecked_into::<Gl>();
+
+                let file_name = format!("{}-{}.png", env!("CARGO_PKG_NAME"), index + 1);
+                setup.bind_snapshot(&gl, &file_name);
+
+                gl
+            })
+            .collect();
+        setup.observe_resize(&first_canvas, &gls[0]);
+
+        // The first canvas is kept in device pixels by the host, the
+        // second keeps the default size.
+        let panels = vec![
+            CanvasPanel::new(&document, first_canvas, &gls[0], Sizing::DevicePixels)?,
+            CanvasPanel::new(&document, second_canvas, &gls[1], Sizing::Default)?,
+        ];
+
+        let sizing: HtmlSelectElement = element("#sizing").unchecked_into();
+        let add = element("#add-canvas");
+        setup.listen(&add, "click");
+        // Zooming changes the devicePixelRatio and resizes the window.
+        setup.listen(&window, "resize");
+
+        Ok(CanvasSizeAndWebgl {
+            scene: Scene::new(gls),
+            canvases: element("#canvases"),
+            document,
+            window,
+            panels,
+            sizing,
+            add,
+        })
+    }
+
+    fn render(&mut self) {
+        self.scene.render();
+        for (panel, gl) in self.panels.iter().zip(&self.scene.gls) {
+            panel.show(gl);
+        }
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let event_target: EventTarget = event.current_target().unwrap();
+
+        if AsRef::<EventTarget>::as_ref(&self.add) == &event_target {
+            let sizing = Sizing::from_name(&self.sizing.value()).unwrap_or(Sizing::Default);
+            if let Err(error) = self.add_canvas(sizing) {
+                console::error_1(&error);
+            }
+            return;
+        }
+
+        if AsRef::<EventTarget>::as_ref(&self.window) == &event_target {
+            // The first canvas is resized by the host.
+            for (index, panel) in self.panels.iter().enumerate().skip(1) {
+                if panel.apply_sizing() {
+                    self.scene.resize(index);
+                }
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<CanvasSizeAndWebgl>()?.keep_until_unload();
     Ok(())
 }
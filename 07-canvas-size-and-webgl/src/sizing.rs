@@ -0,0 +1,209 @@
+use std::fmt;
+
+/// Size of the drawing buffer when the `width` and `height` attributes of
+/// a canvas are not set.
+pub const DEFAULT_SIZE: [u32; 2] = [300, 150];
+
+/// How the `width` and `height` attributes of a canvas are chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sizing {
+    /// Attributes left alone, the drawing buffer is 300x150.
+    Default,
+    /// The size of the canvas on the page in CSS pixels.
+    CssPixels,
+    /// The size of the canvas on the page in device pixels.
+    DevicePixels,
+    /// A fixed size, regardless of the page.
+    Fixed([u32; 2]),
+}
+
+impl Sizing {
+    /// Every choice offered on the page, with a fixed size small enough to
+    /// see its pixels.
+    pub const ALL: [Sizing; 4] = [
+        Sizing::Default,
+        Sizing::CssPixels,
+        Sizing::DevicePixels,
+        Sizing::Fixed([40, 30]),
+    ];
+
+    /// Value of the option choosing `self`, see [`from_name`](Self::from_name).
+    pub fn name(self) -> &'static str {
+        match self {
+            Sizing::Default => "default",
+            Sizing::CssPixels => "css-pixels",
+            Sizing::DevicePixels => "device-pixels",
+            Sizing::Fixed(_) => "fixed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Sizing> {
+        Sizing::ALL.into_iter().find(|sizing| sizing.name() == name)
+    }
+
+    /// The `width` and `height` attributes for a canvas measuring
+    /// `css_size` CSS pixels on a page with `device_pixel_ratio`.
+    pub fn attributes(self, css_size: [f64; 2], device_pixel_ratio: f64) -> [u32; 2] {
+        let pixels = |size: f64| size.round().max(1.0) as u32;
+        match self {
+            Sizing::Default => DEFAULT_SIZE,
+            Sizing::CssPixels => css_size.map(pixels),
+            Sizing::DevicePixels => css_size.map(|size| pixels(size * device_pixel_ratio)),
+            Sizing::Fixed(size) => size,
+        }
+    }
+}
+
+impl fmt::Display for Sizing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sizing::Default => f.write_str("default size"),
+            Sizing::CssPixels => f.write_str("CSS pixels"),
+            Sizing::DevicePixels => f.write_str("device pixels"),
+            Sizing::Fixed([width, height]) => write!(f, "fixed {width}x{height}"),
+        }
+    }
+}
+
+/// Everything deciding how sharp a canvas looks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Diagnostics {
+    /// Size of the canvas on the page.
+    pub css_size: [f64; 2],
+    /// The `width` and `height` attributes.
+    pub attributes: [u32; 2],
+    /// Size of the drawing buffer, smaller than the attributes when they
+    /// exceed what the implementation supports.
+    pub drawing_buffer: [i32; 2],
+    pub device_pixel_ratio: f64,
+    /// The `MAX_VIEWPORT_DIMS` limit of the context.
+    pub max_viewport_dims: [i32; 2],
+}
+
+impl Diagnostics {
+    /// Drawing buffer pixels for each CSS pixel.
+    pub fn scale(&self) -> [f64; 2] {
+        [0, 1].map(|axis| self.drawing_buffer[axis] as f64 / self.css_size[axis].max(1.0))
+    }
+
+    /// Device pixels each drawing buffer pixel is stretched over, one when
+    /// the canvas is sharp.
+    pub fn device_pixels_per_buffer_pixel(&self) -> [f64; 2] {
+        self.scale().map(|scale| self.device_pixel_ratio / scale)
+    }
+
+    /// Whether the drawing buffer is smaller than the attributes ask for.
+    pub fn is_limited(&self) -> bool {
+        self.drawing_buffer[0] < self.attributes[0] as i32
+            || self.drawing_buffer[1] < self.attributes[1] as i32
+    }
+
+    /// What the browser has to do to show the drawing buffer on screen.
+    pub fn verdict(&self) -> &'static str {
+        // Allow for the CSS size being rounded to whole device pixels.
+        const TOLERANCE: f64 = 0.02;
+        let ratios = self.device_pixels_per_buffer_pixel();
+        if ratios.iter().all(|ratio| (ratio - 1.0).abs() <= TOLERANCE) {
+            "sharp, one buffer pixel per device pixel"
+        } else if ratios.iter().all(|&ratio| ratio >= 1.0 - TOLERANCE) {
+            "stretched, blurry"
+        } else if ratios.iter().all(|&ratio| ratio <= 1.0 + TOLERANCE) {
+            "shrunk, wasted pixels"
+        } else {
+            "distorted"
+        }
+    }
+
+    /// Labels and values of the panel shown next to the canvas.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let [css_width, css_height] = self.css_size;
+        let [width, height] = self.attributes;
+        let [buffer_width, buffer_height] = self.drawing_buffer;
+        let [max_width, max_height] = self.max_viewport_dims;
+        let [scale_x, scale_y] = self.scale();
+        let limited = if self.is_limited() { " (limited)" } else { "" };
+        vec![
+            ("CSS size", format!("{css_width:.1} x {css_height:.1}")),
+            ("width, height", format!("{width} x {height}")),
+            (
+                "drawing buffer",
+                format!("{buffer_width} x {buffer_height}{limited}"),
+            ),
+            (
+                "devicePixelRatio",
+                format!("{:.2}", self.device_pixel_ratio),
+            ),
+            ("MAX_VIEWPORT_DIMS", format!("{max_width} x {max_height}")),
+            ("scale", format!("{scale_x:.2} x {scale_y:.2}")),
+            ("result", self.verdict().to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(css_size: [f64; 2], drawing_buffer: [i32; 2], ratio: f64) -> Diagnostics {
+        Diagnostics {
+            css_size,
+            attributes: drawing_buffer.map(|size| size as u32),
+            drawing_buffer,
+            device_pixel_ratio: ratio,
+            max_viewport_dims: [16384, 16384],
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for sizing in Sizing::ALL {
+            assert_eq!(Sizing::from_name(sizing.name()), Some(sizing));
+        }
+        assert_eq!(Sizing::from_name("huge"), None);
+    }
+
+    #[test]
+    fn attributes_follow_the_sizing() {
+        let css_size = [120.0, 80.4];
+        assert_eq!(Sizing::Default.attributes(css_size, 2.0), [300, 150]);
+        assert_eq!(Sizing::CssPixels.attributes(css_size, 2.0), [120, 80]);
+        assert_eq!(Sizing::DevicePixels.attributes(css_size, 2.0), [240, 161]);
+        assert_eq!(Sizing::Fixed([40, 30]).attributes(css_size, 2.0), [40, 30]);
+        // Hidden canvases keep a pixel.
+        assert_eq!(Sizing::CssPixels.attributes([0.0, 0.0], 1.0), [1, 1]);
+    }
+
+    #[test]
+    fn device_pixel_buffer_is_sharp() {
+        let sharp = diagnostics([120.0, 80.0], [240, 160], 2.0);
+        assert_eq!(sharp.scale(), [2.0, 2.0]);
+        assert_eq!(sharp.device_pixels_per_buffer_pixel(), [1.0, 1.0]);
+        assert_eq!(sharp.verdict(), "sharp, one buffer pixel per device pixel");
+    }
+
+    #[test]
+    fn verdict_tells_how_the_buffer_is_scaled() {
+        // CSS pixels on a high density screen.
+        assert_eq!(
+            diagnostics([120.0, 80.0], [120, 80], 2.0).verdict(),
+            "stretched, blurry"
+        );
+        // The default size shown smaller than it is.
+        assert_eq!(
+            diagnostics([120.0, 80.0], [300, 150], 1.0).verdict(),
+            "shrunk, wasted pixels"
+        );
+        assert_eq!(
+            diagnostics([120.0, 80.0], [300, 40], 1.0).verdict(),
+            "distorted"
+        );
+    }
+
+    #[test]
+    fn limited_drawing_buffer_is_reported() {
+        let mut limited = diagnostics([8000.0, 100.0], [16384, 200], 4.0);
+        limited.attributes = [32000, 400];
+        assert!(limited.is_limited());
+        assert_eq!(limited.rows()[2].1, "16384 x 200 (limited)");
+    }
+}
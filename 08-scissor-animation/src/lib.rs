@@ -1,22 +1,23 @@
-use js_sys::{Function, Math, Object};
-use std::cell::{Cell, RefCell, RefMut};
-use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod physics;
+
+pub use physics::{Body, World};
+
+use js_sys::Object;
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
-use web_sys::{
-    Document, Element, EventListener, HtmlCanvasElement, MouseEvent, PointerEvent,
-    WebGl2RenderingContext, Window,
+use web_sys::{Document, Element, Event, HtmlCanvasElement, WebGl2RenderingContext, Window};
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Rng, Setup, SoftwareContext, SoftwareExample,
+    StateCache,
 };
 
-fn get_random_color() -> [f32; 3] {
-    let r = Math::random() as f32;
-    let g = Math::random() as f32;
-    let b = Math::random() as f32;
-    [r, g, b]
-}
+webgl_common::example_info!();
 
-fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
+// The physics world advances in fixed steps of 17ms, roughly 60 times
+// per second, however often frames are rendered.
+const STEP_DURATION: f64 = 0.017;
+
+fn get_rendering_context(document: &Document) -> (HtmlCanvasElement, Gl) {
     let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
     let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
     let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
@@ -27,179 +28,182 @@ fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
     canvas.set_width(canvas.client_width() as u32);
     canvas.set_height(canvas.client_height() as u32);
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    let gl: Gl = context.unchecked_into::<Gl>();
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
-    gl.clear_color(0.0, 0.0, 0.0, 1.0);
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    (canvas, gl)
+}
 
-    gl
+// The squares thrown into the drawing buffer, between 20 and 50 pixels
+// large.
+const SQUARE_COUNT: usize = 6;
+const SQUARE_SIZES: [f32; 2] = [20.0, 50.0];
+
+// Pixels per second squared, and the fraction of speed kept by bounces.
+const GRAVITY: f32 = 400.0;
+const RESTITUTION: f32 = 0.9;
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    // Only the scissor box and the clear color change between frames.
+    gl: C,
+    running: bool,
+    world: World,
+    // The color of each square of the world.
+    colors: Vec<Color>,
+    // Time not yet used up by a step of the animation.
+    elapsed: f64,
 }
 
-fn draw_animation(
-    gl: &WebGl2RenderingContext,
-    size: &[i32; 2],
-    velocity: &Rc<Cell<f32>>,
-    mut position: RefMut<[i32; 2]>,
-    mut color: RefMut<[f32; 3]>,
-) {
-    gl.scissor(position[0], position[1], size[0], size[1]);
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-
-    // Every frame the vertical position of the square is
-    // decreased, to create the illusion of movement.
-    position[1] -= velocity.get() as i32;
-    // When the square hits the bottom of the drawing buffer,
-    // we override it with new square of different color and
-    // velocity.
-    if position[1] < 0 {
-        // Horizontal position chosen randomly, and vertical
-        // position at the top of the drawing buffer.
-        position[0] = (Math::random() as f32 * (gl.drawing_buffer_width() - size[0]) as f32) as i32;
-        position[1] = gl.drawing_buffer_height();
-        // Random velocity between 1.0 and 7.0
-        velocity.set(1.0 + 6.0 * Math::random() as f32);
-        let new_color = get_random_color();
-        color[0] = new_color[0];
-        color[1] = new_color[1];
-        color[2] = new_color[2];
-
-        gl.clear_color(color[0], color[1], color[2], 1.0);
+impl<C: ClearContext> Scene<C> {
+    /// Squares placed, thrown and colored by `rng`.
+    pub fn new(mut gl: C, mut rng: Rng) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+
+        // Unlike the browser window, vertical position in WebGL is
+        // measured from bottom to top, and so is the physics.
+        let mut world = World::new([width as f32, height as f32], GRAVITY, RESTITUTION);
+        let [min_size, max_size] = SQUARE_SIZES;
+        world.scatter(&mut rng, SQUARE_COUNT, min_size, max_size);
+        // Colors apart from each other, so that squares stay told apart when
+        // they touch.
+        let colors = Color::palette(&mut rng, SQUARE_COUNT);
+
+        Scene {
+            gl,
+            running: false,
+            world,
+            colors,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
     }
-}
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let document_start_closure = Rc::new(document);
-    let document_stop_closure = Rc::clone(&document_start_closure);
-
-    let gl = Rc::new(get_rendering_context(&document_start_closure));
-    let gl2 = Rc::clone(&gl);
-
-    let color: Rc<RefCell<[f32; 3]>> = Rc::new(RefCell::new(get_random_color()));
-    let color2: Rc<RefCell<[f32; 3]>> = Rc::clone(&color);
-
-    gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
-    gl.clear_color(color.borrow()[0], color.borrow()[1], color.borrow()[2], 1.0);
-
-    // Unlike the browser window, vertical position in WebGL is
-    // measured from bottom to top. In here we set the initial
-    // position of the square to be at the top left corner of the
-    // drawing buffer.
-    let position = [0, gl.drawing_buffer_height()];
-    let position: Rc<RefCell<[i32; 2]>> = Rc::new(RefCell::new(position));
-    let position2: Rc<RefCell<[i32; 2]>> = Rc::clone(&position);
-
-    let size: [i32; 2] = [60, 60];
-
-    let button = document_start_closure
-        .query_selector("button")
-        .unwrap()
-        .unwrap();
-
-    let button1 = Rc::new(button);
-    let button2 = Rc::clone(&button1);
-
-    let start_animation_event_handler = Rc::new(RefCell::new(EventListener::new()));
-    let stop_animation_event_handler = Rc::new(RefCell::new(EventListener::new()));
-
-    let start_animation_event_handler1 = Rc::clone(&start_animation_event_handler);
-    let start_animation_event_handler2 = Rc::clone(&start_animation_event_handler);
-    let stop_animation_event_handler1 = Rc::clone(&stop_animation_event_handler);
-    let stop_animation_event_handler2 = Rc::clone(&stop_animation_event_handler);
-
-    let timer1: Rc<Cell<Option<i32>>> = Rc::new(Cell::new(None));
-    let timer2: Rc<Cell<Option<i32>>> = Rc::clone(&timer1);
-
-    let velocity: Rc<Cell<f32>> = Rc::new(Cell::new(3.0));
-    let velocity2: Rc<Cell<f32>> = Rc::clone(&velocity);
-
-    let draw_animation_closure = Closure::<dyn Fn()>::new(move || {
-        draw_animation(
-            &gl,
-            &size,
-            &velocity,
-            position.borrow_mut(),
-            color.borrow_mut(),
-        )
-    });
-
-    let start_animation_closure = Closure::<dyn Fn(MouseEvent)>::new(move |event: MouseEvent| {
-        document_start_closure
-            .query_selector("strong")
-            .unwrap()
-            .unwrap()
-            .set_inner_html("stop");
-        drop(button1.remove_event_listener_with_event_listener(
-            event.type_().as_str(),
-            &start_animation_event_handler1.borrow(),
-        ));
-        drop(button1.add_event_listener_with_event_listener(
-            "click",
-            &stop_animation_event_handler1.borrow(),
-        ));
-
-        let interval = web_sys::window()
-            .unwrap()
-            .set_interval_with_callback_and_timeout_and_arguments_0(
-                draw_animation_closure.as_ref().unchecked_ref(),
-                17,
-            );
+    fn step(&mut self) {
+        self.world.step(STEP_DURATION as f32);
+    }
 
-        timer1.set(Some(interval.unwrap()));
-
-        draw_animation(
-            &gl2,
-            &size,
-            &velocity2,
-            position2.borrow_mut(),
-            color2.borrow_mut(),
-        )
-    });
-
-    let stop_animation_closure = Closure::<dyn Fn(MouseEvent)>::new(move |event: MouseEvent| {
-        document_stop_closure
-            .query_selector("strong")
-            .unwrap()
-            .unwrap()
-            .set_inner_html("start");
-        drop(button2.remove_event_listener_with_event_listener(
-            event.type_().as_str(),
-            &stop_animation_event_handler2.borrow(),
-        ));
-        drop(button2.add_event_listener_with_event_listener(
-            "click",
-            &start_animation_event_handler2.borrow(),
-        ));
-
-        if let Some(handle) = timer2.take() {
-            web_sys::window()
-                .unwrap()
-                .clear_interval_with_handle(handle)
+    /// Starts or stops the animation, returns whether it runs.
+    pub fn toggle(&mut self) -> bool {
+        self.running = !self.running;
+        if self.running {
+            self.elapsed = 0.0;
+            self.step();
         }
-    });
+        self.running
+    }
 
-    let start_animation_function: &Function = start_animation_closure.as_ref().unchecked_ref();
+    pub fn update(&mut self, dt: f64) {
+        if !self.running {
+            return;
+        }
+        self.elapsed += dt;
+        while self.elapsed >= STEP_DURATION {
+            self.elapsed -= STEP_DURATION;
+            self.step();
+        }
+    }
 
-    start_animation_event_handler
-        .borrow_mut()
-        .handle_event(start_animation_function);
+    pub fn render(&mut self) {
+        let gl = &mut self.gl;
+        for (body, color) in self.world.bodies.iter().zip(&self.colors) {
+            let size = body.size.round() as i32;
+            gl.scissor(
+                body.position[0].round() as i32,
+                body.position[1].round() as i32,
+                size,
+                size,
+            );
+            gl.clear_color(color.r, color.g, color.b, color.a);
+            gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        }
+    }
 
-    let stop_animation_function: &Function = stop_animation_closure.as_ref().unchecked_ref();
+    pub fn resize(&mut self, width: u32, height: u32) {
+        // Keeps the squares inside a smaller drawing buffer.
+        self.world.set_bounds([width as f32, height as f32]);
+    }
+}
 
-    stop_animation_event_handler
-        .borrow_mut()
-        .handle_event(stop_animation_function);
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[280, 210]];
 
-    let pointer_event: PointerEvent = PointerEvent::new("click").unwrap();
+    // The animation is started.
+    fn new(mut contexts: Vec<SoftwareContext>, rng: Rng) -> Self {
+        let mut scene = Scene::new(contexts.remove(0), rng);
+        scene.toggle();
+        scene
+    }
 
-    drop(stop_animation_function.call1(&JsValue::UNDEFINED, pointer_event.as_ref()));
+    fn update(&mut self, dt: f64) {
+        Scene::update(self, dt);
+    }
 
-    start_animation_closure.forget();
-    stop_animation_closure.forget();
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+pub struct ScissorAnimation {
+    scene: Scene<StateCache>,
+    verb: Element,
+}
+
+impl Example for ScissorAnimation {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+
+        let (canvas, gl) = get_rendering_context(&document);
+
+        let button = document.query_selector("button").unwrap().unwrap();
+        let verb = document.query_selector("strong").unwrap().unwrap();
+        verb.set_inner_html("start");
+
+        setup.listen(&button, "click");
+        setup.observe_resize(&canvas, &gl);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+        setup.animate();
+
+        Ok(ScissorAnimation {
+            scene: Scene::new(StateCache::new(&gl), Rng::from_entropy()),
+            verb,
+        })
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.scene.update(dt);
+    }
 
+    fn render(&mut self) {
+        self.scene.render();
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        // The host has set the viewport behind the back of the cache.
+        self.scene.gl.invalidate();
+        self.scene.resize(width, height);
+    }
+
+    fn on_event(&mut self, _event: &Event) {
+        let running = self.scene.toggle();
+        self.verb
+            .set_inner_html(if running { "stop" } else { "start" });
+    }
+}
+
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<ScissorAnimation>()?.keep_until_unload();
     Ok(())
 }
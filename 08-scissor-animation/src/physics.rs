@@ -0,0 +1,333 @@
+use webgl_common::Rng;
+
+/// Speed below which a bounce leaves a square resting, in pixels per
+/// second, so that squares settle instead of jittering on the floor.
+const REST_SPEED: f32 = 1.0;
+
+/// A square moving in the drawing buffer, in pixels from the bottom left
+/// corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Body {
+    /// Bottom left corner.
+    pub position: [f32; 2],
+    /// Pixels per second.
+    pub velocity: [f32; 2],
+    pub size: f32,
+}
+
+impl Body {
+    pub fn new(position: [f32; 2], velocity: [f32; 2], size: f32) -> Self {
+        Self {
+            position,
+            velocity,
+            size,
+        }
+    }
+
+    /// How far `self` and `other` overlap along each axis, negative or zero
+    /// where they are apart.
+    fn overlap(&self, other: &Body) -> [f32; 2] {
+        [0, 1].map(|axis| {
+            let start = self.position[axis].max(other.position[axis]);
+            let end = (self.position[axis] + self.size).min(other.position[axis] + other.size);
+            end - start
+        })
+    }
+}
+
+/// Squares bouncing off the walls of the drawing buffer and each other,
+/// all of the same mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct World {
+    pub bodies: Vec<Body>,
+    /// Width and height of the walls.
+    pub bounds: [f32; 2],
+    /// Downward acceleration in pixels per second squared.
+    pub gravity: f32,
+    /// Fraction of the speed kept by a bounce, between 0 and 1.
+    pub restitution: f32,
+}
+
+impl World {
+    pub fn new(bounds: [f32; 2], gravity: f32, restitution: f32) -> Self {
+        Self {
+            bodies: Vec::new(),
+            bounds,
+            gravity,
+            restitution,
+        }
+    }
+
+    /// Adds `count` squares between `min_size` and `max_size` pixels large,
+    /// thrown in random directions from random places in the upper half,
+    /// the same ones for the same state of `rng`.
+    pub fn scatter(&mut self, rng: &mut Rng, count: usize, min_size: f32, max_size: f32) {
+        for _ in 0..count {
+            let size = min_size + (max_size - min_size) * rng.next_f32();
+            let room = [
+                (self.bounds[0] - size).max(0.0),
+                (self.bounds[1] / 2.0 - size).max(0.0),
+            ];
+            let position = [
+                room[0] * rng.next_f32(),
+                self.bounds[1] / 2.0 + room[1] * rng.next_f32(),
+            ];
+            let velocity = [
+                300.0 * (rng.next_f32() - 0.5),
+                200.0 * (rng.next_f32() - 0.5),
+            ];
+            self.bodies.push(Body::new(position, velocity, size));
+        }
+    }
+
+    /// Changes the walls, moving squares left outside back in.
+    pub fn set_bounds(&mut self, bounds: [f32; 2]) {
+        self.bounds = bounds;
+        for body in &mut self.bodies {
+            for (position, bound) in body.position.iter_mut().zip(bounds) {
+                *position = position.clamp(0.0, (bound - body.size).max(0.0));
+            }
+        }
+    }
+
+    /// Advances the squares by `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        for body in &mut self.bodies {
+            // Semi-implicit Euler, the new velocity moves the square.
+            body.velocity[1] -= self.gravity * dt;
+            for axis in 0..2 {
+                body.position[axis] += body.velocity[axis] * dt;
+            }
+        }
+        self.collide();
+        for index in 0..self.bodies.len() {
+            self.bounce_off_walls(index);
+        }
+    }
+
+    fn bounce(&self, speed: f32) -> f32 {
+        let speed = speed * self.restitution;
+        if speed.abs() < REST_SPEED {
+            0.0
+        } else {
+            speed
+        }
+    }
+
+    fn bounce_off_walls(&mut self, index: usize) {
+        let mut body = self.bodies[index];
+        for axis in 0..2 {
+            let max = (self.bounds[axis] - body.size).max(0.0);
+            if body.position[axis] < 0.0 {
+                body.position[axis] = 0.0;
+                body.velocity[axis] = self.bounce(body.velocity[axis].abs());
+            } else if body.position[axis] > max {
+                body.position[axis] = max;
+                body.velocity[axis] = -self.bounce(body.velocity[axis].abs());
+            }
+        }
+        self.bodies[index] = body;
+    }
+
+    // Separates overlapping squares along the axis they overlap least on
+    // and bounces them apart if they approach each other.
+    fn collide(&mut self) {
+        for first in 0..self.bodies.len() {
+            for second in first + 1..self.bodies.len() {
+                let (a, b) = (self.bodies[first], self.bodies[second]);
+                let overlap = a.overlap(&b);
+                if overlap[0] <= 0.0 || overlap[1] <= 0.0 {
+                    continue;
+                }
+                let axis = if overlap[0] < overlap[1] { 0 } else { 1 };
+                // Which way pushes `b` away from `a`.
+                let direction =
+                    if a.position[axis] + a.size / 2.0 <= b.position[axis] + b.size / 2.0 {
+                        1.0
+                    } else {
+                        -1.0
+                    };
+                self.bodies[first].position[axis] -= direction * overlap[axis] / 2.0;
+                self.bodies[second].position[axis] += direction * overlap[axis] / 2.0;
+
+                let approach = (a.velocity[axis] - b.velocity[axis]) * direction;
+                if approach > 0.0 {
+                    // Equal masses keep their total momentum.
+                    let change = (1.0 + self.restitution) * approach / 2.0;
+                    self.bodies[first].velocity[axis] -= direction * change;
+                    self.bodies[second].velocity[axis] += direction * change;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: [f32; 2] = [280.0, 210.0];
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    fn world(gravity: f32, restitution: f32, bodies: &[Body]) -> World {
+        let mut world = World::new(BOUNDS, gravity, restitution);
+        world.bodies.extend_from_slice(bodies);
+        world
+    }
+
+    #[test]
+    fn gravity_accelerates_downward() {
+        let mut world = world(100.0, 1.0, &[Body::new([0.0, 200.0], [10.0, 0.0], 10.0)]);
+        world.step(0.5);
+        world.step(0.5);
+        let body = world.bodies[0];
+        assert_eq!(body.velocity, [10.0, -100.0]);
+        // Semi-implicit Euler falls 25 + 50 pixels.
+        assert_eq!(body.position, [10.0, 125.0]);
+    }
+
+    #[test]
+    fn floor_bounce_keeps_restitution() {
+        let mut world = world(0.0, 0.5, &[Body::new([100.0, 5.0], [0.0, -100.0], 10.0)]);
+        world.step(0.1);
+        let body = world.bodies[0];
+        assert_eq!(body.position[1], 0.0);
+        assert_eq!(body.velocity[1], 50.0);
+    }
+
+    #[test]
+    fn walls_on_all_sides_keep_squares_inside() {
+        let mut world = world(
+            0.0,
+            1.0,
+            &[
+                Body::new([2.0, 100.0], [-100.0, 0.0], 20.0),
+                Body::new([255.0, 100.0], [100.0, 0.0], 20.0),
+                Body::new([100.0, 185.0], [0.0, 100.0], 20.0),
+                Body::new([200.0, 2.0], [0.0, -100.0], 20.0),
+            ],
+        );
+        world.step(0.1);
+        let positions: Vec<[f32; 2]> = world.bodies.iter().map(|body| body.position).collect();
+        assert_eq!(
+            positions,
+            [[0.0, 100.0], [260.0, 100.0], [100.0, 190.0], [200.0, 0.0]]
+        );
+        let velocities: Vec<[f32; 2]> = world.bodies.iter().map(|body| body.velocity).collect();
+        assert_eq!(
+            velocities,
+            [[100.0, 0.0], [-100.0, 0.0], [0.0, -100.0], [0.0, 100.0]]
+        );
+    }
+
+    #[test]
+    fn slow_bounces_come_to_rest() {
+        let mut world = world(0.0, 0.5, &[Body::new([0.0, 0.5], [0.0, -1.0], 10.0)]);
+        world.step(1.0);
+        assert_eq!(world.bodies[0].velocity, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn elastic_head_on_collision_swaps_velocities() {
+        let mut world = world(
+            0.0,
+            1.0,
+            &[
+                Body::new([100.0, 50.0], [50.0, 0.0], 20.0),
+                Body::new([125.0, 50.0], [-30.0, 0.0], 20.0),
+            ],
+        );
+        world.step(0.1);
+        assert_close(world.bodies[0].velocity[0], -30.0);
+        assert_close(world.bodies[1].velocity[0], 50.0);
+        // Separated where they met.
+        assert_close(
+            world.bodies[1].position[0] - world.bodies[0].position[0],
+            20.0,
+        );
+    }
+
+    #[test]
+    fn inelastic_collision_moves_squares_together() {
+        let mut world = world(
+            0.0,
+            0.0,
+            &[
+                Body::new([100.0, 50.0], [60.0, 0.0], 20.0),
+                Body::new([125.0, 50.0], [0.0, 0.0], 20.0),
+            ],
+        );
+        world.step(0.1);
+        assert_close(world.bodies[0].velocity[0], 30.0);
+        assert_close(world.bodies[1].velocity[0], 30.0);
+    }
+
+    #[test]
+    fn squares_moving_apart_do_not_bounce() {
+        let mut world = world(
+            0.0,
+            1.0,
+            &[
+                Body::new([100.0, 50.0], [-10.0, 0.0], 20.0),
+                Body::new([110.0, 50.0], [10.0, 0.0], 20.0),
+            ],
+        );
+        world.step(0.1);
+        assert_eq!(world.bodies[0].velocity, [-10.0, 0.0]);
+        assert_eq!(world.bodies[1].velocity, [10.0, 0.0]);
+    }
+
+    #[test]
+    fn energy_never_grows_without_gravity() {
+        let mut world = World::new(BOUNDS, 0.0, 0.8);
+        world.scatter(&mut Rng::new(11), 8, 20.0, 40.0);
+        let energy = |world: &World| -> f32 {
+            world
+                .bodies
+                .iter()
+                .map(|body| body.velocity[0].powi(2) + body.velocity[1].powi(2))
+                .sum()
+        };
+        let mut last = energy(&world);
+        for _ in 0..600 {
+            world.step(0.017);
+            let current = energy(&world);
+            assert!(current <= last * (1.0 + 1e-4), "{current} > {last}");
+            last = current;
+        }
+    }
+
+    #[test]
+    fn same_seed_same_motion() {
+        let run = |seed| {
+            let mut world = World::new(BOUNDS, 400.0, 0.9);
+            world.scatter(&mut Rng::new(seed), 6, 20.0, 50.0);
+            for _ in 0..300 {
+                world.step(0.017);
+            }
+            world
+        };
+        assert_eq!(run(3), run(3));
+        assert_ne!(run(3), run(4));
+    }
+
+    #[test]
+    fn squares_stay_inside_after_resizing() {
+        let mut world = World::new(BOUNDS, 400.0, 0.9);
+        world.scatter(&mut Rng::new(5), 6, 20.0, 50.0);
+        world.set_bounds([100.0, 80.0]);
+        for _ in 0..300 {
+            world.step(0.017);
+            for body in &world.bodies {
+                assert!(body.position[0] >= 0.0 && body.position[0] + body.size <= 100.0);
+                assert!(body.position[1] >= 0.0 && body.position[1] + body.size <= 80.0);
+            }
+        }
+    }
+}
@@ -1,23 +1,178 @@
-use js_sys::{Math, Object};
-use std::cell::{RefCell, RefMut};
-use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use js_sys::Object;
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Document, Element, HtmlCanvasElement, HtmlElement, MouseEvent, Node, WebGl2RenderingContext,
-    Window,
+    Document, Element, Event, HtmlCanvasElement, MouseEvent, Node, WebGl2RenderingContext, Window,
 };
+use webgl_common::{
+    ClearContext, Color, Example, Gl, Host, Mat3, Rng, Setup, SoftwareContext, SoftwareExample,
+    StateCache, Vec2,
+};
+
+webgl_common::example_info!();
+
+// The rectangle falls by its velocity once every 17ms.
+const STEP_DURATION: f64 = 0.017;
 
 struct Rectangle {
     size: [i32; 2],
     position: [i32; 2],
     velocity: i32,
+    color: Color,
 }
 
-struct GameState {
+impl Rectangle {
+    fn new(rng: &mut Rng, width: i32, height: i32) -> Self {
+        let size = [
+            (5.0 + 120.0 * rng.next_f32()) as i32,
+            (5.0 + 120.0 * rng.next_f32()) as i32,
+        ];
+        let position = [(rng.next_f32() * (width - size[0]) as f32) as i32, height];
+        let velocity = (1.0 + 6.0 * rng.next_f32()) as i32;
+        let color = Color::random(rng);
+        Self {
+            size,
+            position,
+            velocity,
+            color,
+        }
+    }
+
+    fn contains(&self, position: [i32; 2]) -> bool {
+        let diff_pos = [
+            position[0] - self.position[0],
+            position[1] - self.position[1],
+        ];
+        diff_pos[0] >= 0
+            && diff_pos[0] < self.size[0]
+            && diff_pos[1] >= 0
+            && diff_pos[1] < self.size[1]
+    }
+}
+
+/// What the example draws, into a WebGL context or a software one.
+pub struct Scene<C> {
+    // In the browser a `StateCache`, which skips setting the scissor box
+    // and the clear color while the rectangle stays in place, e.g. when
+    // the page is re-rendered for a snapshot.
+    gl: C,
+    rng: Rng,
+    raining_rect: Rectangle,
     score: i32,
     misses: i32,
+    // Time not yet used up by a step of the animation.
+    elapsed: f64,
+}
+
+impl<C: ClearContext> Scene<C> {
+    pub fn new(mut gl: C, mut rng: Rng) -> Self {
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        gl.viewport(0, 0, width, height);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        let raining_rect = Rectangle::new(&mut rng, width, height);
+        Scene {
+            gl,
+            rng,
+            raining_rect,
+            score: 0,
+            misses: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn misses(&self) -> i32 {
+        self.misses
+    }
+
+    fn regenerate(&mut self) {
+        let (width, height) = (
+            self.gl.drawing_buffer_width(),
+            self.gl.drawing_buffer_height(),
+        );
+        self.raining_rect = Rectangle::new(&mut self.rng, width, height);
+    }
+
+    fn step(&mut self) {
+        // Every frame the vertical position of the square is
+        // decreased, to create the illusion of movement.
+        self.raining_rect.position[1] -= self.raining_rect.velocity;
+
+        // When the square hits the bottom of the drawing buffer,
+        // we override it with new square of different color and
+        // velocity.
+        if self.raining_rect.position[1] < 0 {
+            self.misses += 1;
+            self.regenerate();
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
+        while self.elapsed >= STEP_DURATION {
+            self.elapsed -= STEP_DURATION;
+            self.step();
+        }
+    }
+
+    /// Catches the rectangle if it is at `position` in the drawing
+    /// buffer, counted from the bottom left.
+    pub fn click(&mut self, position: [i32; 2]) {
+        // If the click falls inside the rectangle, we caught it.
+        // Increment score and create a new rectangle.
+        if self.raining_rect.contains(position) {
+            self.score += 1;
+            self.regenerate();
+        }
+    }
+
+    pub fn render(&mut self) {
+        let rect = &self.raining_rect;
+        self.gl.scissor(
+            rect.position[0],
+            rect.position[1],
+            rect.size[0],
+            rect.size[1],
+        );
+        let color = rect.color;
+        self.gl.clear_color(color.r, color.g, color.b, color.a);
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    }
+
+    pub fn resize(&mut self, width: u32) {
+        // Keeps the rectangle inside a narrower drawing buffer.
+        let rect = &mut self.raining_rect;
+        rect.position[0] = rect.position[0].min(width as i32 - rect.size[0]).max(0);
+    }
+}
+
+impl SoftwareExample for Scene<SoftwareContext> {
+    const CANVAS_SIZES: &'static [[u32; 2]] = &[[280, 210]];
+
+    // Nobody catches the rectangles.
+    fn new(mut contexts: Vec<SoftwareContext>, rng: Rng) -> Self {
+        Scene::new(contexts.remove(0), rng)
+    }
+
+    fn update(&mut self, dt: f64) {
+        Scene::update(self, dt);
+    }
+
+    fn render(&mut self) {
+        Scene::render(self);
+    }
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext> {
+        vec![&mut self.gl]
+    }
+}
+
+struct GameState {
     score_display: Node,
     misses_display: Node,
 }
@@ -25,74 +180,23 @@ struct GameState {
 impl GameState {
     fn new(document: &Document) -> GameState {
         let strongs = document.query_selector_all("strong").unwrap();
-        let score = 0;
-        let misses = 0;
         let score_display = strongs.get(0).unwrap();
         let misses_display = strongs.get(1).unwrap();
         GameState {
-            score,
-            misses,
             score_display,
             misses_display,
         }
     }
 
-    fn hit(&mut self) {
-        self.score += 1;
+    fn show(&self, scene: &Scene<StateCache>) {
         self.score_display
-            .set_text_content(Some(&self.score.to_string()));
-    }
-    fn miss(&mut self) {
-        self.misses += 1;
+            .set_text_content(Some(&scene.score().to_string()));
         self.misses_display
-            .set_text_content(Some(&self.misses.to_string()));
-    }
-}
-
-impl Rectangle {
-    fn initiate(gl: &WebGl2RenderingContext) -> ([i32; 2], [i32; 2], i32, [f32; 3]) {
-        let rand_nums = get_random_vector();
-        let size = [
-            (5.0 + 120.0 * rand_nums[0]) as i32,
-            (5.0 + 120.0 * rand_nums[1]) as i32,
-        ];
-        let position = [
-            (rand_nums[2] * (gl.drawing_buffer_width() - size[0]) as f32) as i32,
-            gl.drawing_buffer_height(),
-        ];
-        let velocity = (1.0 + 6.0 * Math::random() as f32) as i32;
-        let color = get_random_vector();
-
-        (size, position, velocity, color)
-    }
-
-    fn new(gl: &WebGl2RenderingContext) -> Self {
-        let (size, position, velocity, color) = Self::initiate(gl);
-        gl.clear_color(color[0], color[1], color[2], 1.0);
-        Self {
-            size,
-            position,
-            velocity,
-        }
-    }
-
-    fn regenerate(&mut self, gl: &WebGl2RenderingContext) {
-        let (size, position, velocity, color) = Self::initiate(gl);
-        self.size = size;
-        self.position = position;
-        self.velocity = velocity;
-        gl.clear_color(color[0], color[1], color[2], 1.0);
+            .set_text_content(Some(&scene.misses().to_string()));
     }
 }
 
-fn get_random_vector() -> [f32; 3] {
-    let r = Math::random() as f32;
-    let g = Math::random() as f32;
-    let b = Math::random() as f32;
-    [r, g, b]
-}
-
-fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
+fn get_rendering_context(document: &Document) -> (HtmlCanvasElement, Gl) {
     let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
     let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
     let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
@@ -103,128 +207,85 @@ fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
     canvas.set_width(canvas.client_width() as u32);
     canvas.set_height(canvas.client_height() as u32);
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    let gl: Gl = context.unchecked_into::<Gl>();
 
-    gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
-    gl.clear_color(0.0, 0.0, 0.0, 1.0);
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-
-    gl
+    (canvas, gl)
 }
 
-fn draw_animation(
-    gl: &WebGl2RenderingContext,
-    mut raining_rect: RefMut<Rectangle>,
-    mut game_state: RefMut<GameState>,
-) {
-    gl.scissor(
-        raining_rect.position[0],
-        raining_rect.position[1],
-        raining_rect.size[0],
-        raining_rect.size[1],
-    );
-    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-
-    // Every frame the vertical position of the square is
-    // decreased, to create the illusion of movement.
-    raining_rect.position[1] -= raining_rect.velocity;
-
-    // When the square hits the bottom of the drawing buffer,
-    // we override it with new square of different color and
-    // velocity.
-    if raining_rect.position[1] < 0 {
-        game_state.miss();
-        raining_rect.regenerate(gl);
-    }
-    // We are using setTimeout for animation. So we reschedule
-    // the timeout to call drawAnimation again in 17ms.
-    // Otherwise we won't get any animation.
-    // timer = setTimeout(drawAnimation, 17);
-}
-
-fn player_click(
-    gl: &WebGl2RenderingContext,
-    evt: MouseEvent,
-    mut raining_rect: RefMut<Rectangle>,
-    mut game_state: RefMut<GameState>,
-) {
+fn click_position(gl: &Gl, evt: &MouseEvent) -> [i32; 2] {
     // We need to transform the position of the click event from
     // window coordinates to relative position inside the canvas.
-    // In addition we need to remember that vertical position in
-    // WebGL increases from bottom to top, unlike in the browser
-    // window.
-    let target: HtmlElement = evt.target().unwrap().unchecked_into::<HtmlElement>();
-    let position = [
-        evt.page_x() - target.offset_left(),
-        gl.drawing_buffer_height() - (evt.page_y() - target.offset_top()),
-    ];
-    // If the click falls inside the rectangle, we caught it.
-
-    // Increment score and create a new rectangle.
-    let diff_pos = [
-        position[0] - raining_rect.position[0],
-        position[1] - raining_rect.position[1],
-    ];
-    if diff_pos[0] >= 0
-        && diff_pos[0] < raining_rect.size[0]
-        && diff_pos[1] >= 0
-        && diff_pos[1] < raining_rect.size[1]
-    {
-        game_state.hit();
-        raining_rect.regenerate(gl);
-    }
+    // The click is in CSS pixels from the top left corner, the
+    // drawing buffer in device pixels from the bottom left corner.
+    let target: HtmlCanvasElement = evt.target().unwrap().unchecked_into::<HtmlCanvasElement>();
+    let to_buffer = Mat3::css_to_buffer(
+        Vec2::new(target.client_width() as f32, target.client_height() as f32),
+        Vec2::new(
+            gl.drawing_buffer_width() as f32,
+            gl.drawing_buffer_height() as f32,
+        ),
+    );
+    let position = to_buffer.transform_point(Vec2::new(
+        (evt.page_x() - target.offset_left()) as f32,
+        (evt.page_y() - target.offset_top()) as f32,
+    ));
+    [position.x.floor() as i32, position.y.floor() as i32]
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let game_state = Rc::new(RefCell::new(GameState::new(&document)));
-    let game_state2 = Rc::clone(&game_state);
+pub struct RainingRectangles {
+    scene: Scene<StateCache>,
+    game_state: GameState,
+}
 
-    let document_start_closure = Rc::new(&document);
-    //let document_stop_closure = Rc::clone(&document_start_closure);
+impl Example for RainingRectangles {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
 
-    let gl = Rc::new(get_rendering_context(&document_start_closure));
-    let gl2 = Rc::clone(&gl);
+        let game_state = GameState::new(&document);
 
-    gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
-    let raining_rect = Rc::new(RefCell::new(Rectangle::new(&gl)));
-    let raining_rect2 = Rc::clone(&raining_rect);
+        let (canvas, gl) = get_rendering_context(&document);
 
-    let draw_animation_closure = Closure::<dyn Fn()>::new(move || {
-        draw_animation(&gl, raining_rect.borrow_mut(), game_state.borrow_mut())
-    });
+        setup.listen(&canvas, "click");
+        setup.observe_resize(&canvas, &gl);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+        setup.animate();
 
-    drop(
-        window.set_interval_with_callback_and_timeout_and_arguments_0(
-            draw_animation_closure.as_ref().unchecked_ref(),
-            17,
-        ),
-    );
+        let scene = Scene::new(StateCache::new(&gl), Rng::from_entropy());
+        game_state.show(&scene);
+        Ok(RainingRectangles { scene, game_state })
+    }
 
-    let player_click_closure = Closure::<dyn FnMut(MouseEvent)>::new(move |event: MouseEvent| {
-        player_click(
-            &gl2,
-            event,
-            raining_rect2.borrow_mut(),
-            game_state2.borrow_mut(),
-        );
-    });
+    fn update(&mut self, dt: f64) {
+        let misses = self.scene.misses();
+        self.scene.update(dt);
+        if self.scene.misses() != misses {
+            self.game_state.show(&self.scene);
+        }
+    }
 
-    let player_click_function = player_click_closure.as_ref().unchecked_ref();
+    fn render(&mut self) {
+        self.scene.render();
+    }
 
-    drop(
-        document
-            .query_selector("canvas")
-            .unwrap()
-            .unwrap()
-            .add_event_listener_with_callback("click", player_click_function),
-    );
+    fn resize(&mut self, width: u32, _height: u32) {
+        // The host has set the viewport behind the back of the cache.
+        self.scene.gl.invalidate();
+        self.scene.resize(width);
+    }
 
-    draw_animation_closure.forget();
-    player_click_closure.forget();
+    fn on_event(&mut self, event: &Event) {
+        let position = click_position(self.scene.gl.gl(), event.unchecked_ref::<MouseEvent>());
+        let score = self.scene.score();
+        self.scene.click(position);
+        if self.scene.score() != score {
+            self.game_state.show(&self.scene);
+        }
+    }
+}
 
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<RainingRectangles>()?.keep_until_unload();
     Ok(())
 }
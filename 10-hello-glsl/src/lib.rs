@@ -1,11 +1,21 @@
-use js_sys::Object;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod point_size;
+
+pub use point_size::{PointDrawing, PointSizeRange};
+
+use js_sys::{Float32Array, Object};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Document, Element, HtmlCanvasElement, WebGl2RenderingContext, WebGlBuffer, WebGlProgram, Window,
+    Document, Element, Event, HtmlCanvasElement, HtmlElement, HtmlInputElement,
+    WebGl2RenderingContext, WebGlProgram, WebGlVertexArrayObject, Window,
+};
+use webgl_common::{
+    Example, Gl, Host, QuadCorner, ResourceRegistry, Setup, VertexBuffer, QUAD_CORNERS,
 };
 
-fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
+webgl_common::example_info!();
+
+fn get_rendering_context(document: &Document) -> (HtmlCanvasElement, Gl) {
     let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
     let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
     let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
@@ -16,71 +26,182 @@ fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
     canvas.set_width(canvas.client_width() as u32);
     canvas.set_height(canvas.client_height() as u32);
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    let gl: Gl = context.unchecked_into::<Gl>();
 
     gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
     gl.clear_color(0.0, 0.0, 0.0, 1.0);
     gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
 
-    gl
+    (canvas, gl)
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let gl = get_rendering_context(&document);
-
-    let vertex_shader = gl
-        .create_shader(WebGl2RenderingContext::VERTEX_SHADER)
-        .unwrap();
-    gl.shader_source(&vertex_shader, include_str!("shader.vert"));
-    gl.compile_shader(&vertex_shader);
-
-    let fragment_shader = gl
-        .create_shader(WebGl2RenderingContext::FRAGMENT_SHADER)
-        .unwrap();
-    gl.shader_source(&fragment_shader, include_str!("shader.frag"));
-    gl.compile_shader(&fragment_shader);
-    let program = gl.create_program().unwrap();
-
-    gl.attach_shader(&program, &vertex_shader);
-    gl.attach_shader(&program, &fragment_shader);
-
-    gl.link_program(&program);
-    gl.detach_shader(&program, &vertex_shader);
-    gl.detach_shader(&program, &fragment_shader);
-    gl.delete_shader(Some(&vertex_shader));
-    gl.delete_shader(Some(&fragment_shader));
-
-    if gl
-        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        gl.enable_vertex_attrib_array(0);
-        let buffer = gl.create_buffer().unwrap();
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        gl.vertex_attrib_pointer_with_i32(0, 1, WebGl2RenderingContext::FLOAT, false, 0, 0);
-
-        gl.use_program(Some(&program));
-        gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, 1);
-
-        clean_up(&gl, Some(&program), Some(&buffer))
-    } else {
-        clean_up(&gl, Some(&program), None)
+// The size the point is drawn at unless the page asks for another one.
+const DEFAULT_POINT_SIZE: f32 = 64.0;
+
+// The range of sizes the point can be drawn at by this context.
+fn point_size_range(gl: &Gl) -> PointSizeRange {
+    let values = gl
+        .get_parameter(WebGl2RenderingContext::ALIASED_POINT_SIZE_RANGE)
+        .ok()
+        .and_then(|range| range.dyn_into::<Float32Array>().ok())
+        .map(|range| range.to_vec())
+        .unwrap_or_default();
+    PointSizeRange::from_parameter(&values)
+}
+
+pub struct HelloGlsl {
+    gl: Gl,
+    // The programs and the vertex array of the quad, the buffer of its
+    // corners manages its own object.
+    resources: ResourceRegistry,
+    point_program: WebGlProgram,
+    quad_program: WebGlProgram,
+    quad_vertex_array: WebGlVertexArrayObject,
+    corner_buffer: VertexBuffer<QuadCorner>,
+    range: PointSizeRange,
+    drawing: PointDrawing,
+    size_input: HtmlInputElement,
+    size_text: Element,
+    quad_fallback: HtmlInputElement,
+    warning: HtmlElement,
+}
+
+impl HelloGlsl {
+    // Plans the drawing for the controls and shows the requested size and
+    // any warning.
+    fn apply_controls(&mut self) {
+        let requested = self.size_input.value_as_number() as f32;
+        let requested = if requested.is_finite() {
+            requested
+        } else {
+            DEFAULT_POINT_SIZE
+        };
+        self.drawing = PointDrawing::plan(requested, self.range, self.quad_fallback.checked());
+        self.size_text
+            .set_text_content(Some(&format!("{requested} px")));
+
+        let warning = self.drawing.warning();
+        self.warning.set_text_content(warning.as_deref());
+        self.warning.set_hidden(warning.is_none());
+
+        let program = match self.drawing {
+            PointDrawing::Quad(_) => &self.quad_program,
+            _ => &self.point_program,
+        };
+        self.gl.use_program(Some(program));
+        self.gl.uniform1f(
+            self.gl.get_uniform_location(program, "pointSize").as_ref(),
+            self.drawing.size(),
+        );
     }
 
-    Ok(())
+    // The quad is sized in pixels, so its shader needs the size of the
+    // drawing buffer.
+    fn set_resolution(&self) {
+        self.gl.use_program(Some(&self.quad_program));
+        self.gl.uniform2f(
+            self.gl
+                .get_uniform_location(&self.quad_program, "resolution")
+                .as_ref(),
+            self.gl.drawing_buffer_width() as f32,
+            self.gl.drawing_buffer_height() as f32,
+        );
+    }
+}
+
+impl Example for HelloGlsl {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        let element = |selector: &str| document.query_selector(selector).unwrap().unwrap();
+
+        let (canvas, gl) = get_rendering_context(&document);
+
+        let mut resources = ResourceRegistry::new(&gl);
+        let point_program = resources.link_program(
+            "point",
+            include_str!("shader.vert"),
+            include_str!("shader.frag"),
+        )?;
+        let quad_program = resources.link_program(
+            "quad",
+            include_str!("quad.vert"),
+            include_str!("shader.frag"),
+        )?;
+
+        let quad_vertex_array = resources.create_vertex_array("quad")?;
+        gl.bind_vertex_array(Some(&quad_vertex_array));
+        let corner_buffer =
+            VertexBuffer::new(&gl, &QUAD_CORNERS, WebGl2RenderingContext::STATIC_DRAW)?;
+        corner_buffer.bind_attributes(&gl, &quad_program);
+        gl.bind_vertex_array(None);
+
+        let range = point_size_range(&gl);
+        element("#point-size-range")
+            .set_text_content(Some(&format!("{} to {} pixels", range.min, range.max)));
+
+        let size_input: HtmlInputElement = element("#point-size").unchecked_into();
+        let quad_fallback: HtmlInputElement = element("#quad-fallback").unchecked_into();
+        setup.listen(&size_input, "input");
+        setup.listen(&quad_fallback, "change");
+        setup.observe_resize(&canvas, &gl);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+
+        let mut example = HelloGlsl {
+            gl,
+            resources,
+            point_program,
+            quad_program,
+            quad_vertex_array,
+            corner_buffer,
+            range,
+            drawing: PointDrawing::Point(DEFAULT_POINT_SIZE),
+            size_input,
+            size_text: element("#point-size-text"),
+            quad_fallback,
+            warning: element("#point-size-warning").unchecked_into(),
+        };
+        example.set_resolution();
+        example.apply_controls();
+        Ok(example)
+    }
+
+    fn render(&mut self) {
+        // The program chosen by the controls stays in use, drawing a
+        // single point, or the quad in its place, is all there is to do.
+        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        match self.drawing {
+            PointDrawing::Quad(_) => {
+                self.gl.bind_vertex_array(Some(&self.quad_vertex_array));
+                self.gl.draw_arrays(
+                    WebGl2RenderingContext::TRIANGLE_STRIP,
+                    0,
+                    QUAD_CORNERS.len() as i32,
+                );
+                self.gl.bind_vertex_array(None);
+            }
+            _ => self.gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, 1),
+        }
+    }
+
+    fn resize(&mut self, _width: u32, _height: u32) {
+        self.set_resolution();
+        self.apply_controls();
+    }
+
+    fn on_event(&mut self, _event: &Event) {
+        self.apply_controls();
+    }
+
+    fn destroy(&mut self) {
+        self.gl.use_program(None);
+        self.corner_buffer.delete(&self.gl);
+        self.resources.clear();
+    }
 }
 
-fn clean_up(
-    gl: &WebGl2RenderingContext,
-    program: Option<&WebGlProgram>,
-    buffer: Option<&WebGlBuffer>,
-) {
-    gl.use_program(None);
-    gl.delete_buffer(buffer);
-    gl.delete_program(program);
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<HelloGlsl>()?.keep_until_unload();
+    Ok(())
 }
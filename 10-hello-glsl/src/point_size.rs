@@ -0,0 +1,152 @@
+/// Sizes a point can be drawn at, as reported by `ALIASED_POINT_SIZE_RANGE`.
+/// WebGL only guarantees sizes up to 1.0, many GPUs stop at 64 or 256.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointSizeRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl PointSizeRange {
+    /// The range from the values of the parameter, assuming the minimum
+    /// guaranteed by WebGL for missing or invalid ones.
+    pub fn from_parameter(values: &[f32]) -> Self {
+        match values {
+            &[min, max, ..] if min.is_finite() && max.is_finite() && min <= max && max >= 1.0 => {
+                Self { min, max }
+            }
+            _ => Self { min: 1.0, max: 1.0 },
+        }
+    }
+
+    pub fn contains(self, size: f32) -> bool {
+        (self.min..=self.max).contains(&size)
+    }
+}
+
+/// How a square of the requested size ends up being drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointDrawing {
+    /// A point of the requested size.
+    Point(f32),
+    /// A point clamped into the range, smaller or larger than requested.
+    Clamped { requested: f32, size: f32 },
+    /// Two triangles covering a square of the requested size, as points
+    /// that large are not supported.
+    Quad(f32),
+}
+
+impl PointDrawing {
+    /// How to draw a square of `requested` pixels with points of `range`,
+    /// falling back to a quad for large sizes if `allow_quad` is set.
+    pub fn plan(requested: f32, range: PointSizeRange, allow_quad: bool) -> Self {
+        if range.contains(requested) {
+            PointDrawing::Point(requested)
+        } else if allow_quad && requested > range.max {
+            PointDrawing::Quad(requested)
+        } else {
+            PointDrawing::Clamped {
+                requested,
+                size: requested.clamp(range.min, range.max),
+            }
+        }
+    }
+
+    /// Side of the square drawn, in pixels.
+    pub fn size(self) -> f32 {
+        match self {
+            PointDrawing::Point(size) | PointDrawing::Quad(size) => size,
+            PointDrawing::Clamped { size, .. } => size,
+        }
+    }
+
+    /// Warning to show next to the controls, none if the square is drawn
+    /// as requested with a point.
+    pub fn warning(self) -> Option<String> {
+        match self {
+            PointDrawing::Point(_) => None,
+            PointDrawing::Clamped { requested, size } => Some(format!(
+                "Points of {requested} pixels are not supported, drawn at {size} pixels instead."
+            )),
+            PointDrawing::Quad(size) => Some(format!(
+                "Points of {size} pixels are not supported, drawn as a quad instead."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: PointSizeRange = PointSizeRange {
+        min: 1.0,
+        max: 64.0,
+    };
+
+    #[test]
+    fn range_falls_back_to_the_guaranteed_minimum() {
+        assert_eq!(
+            PointSizeRange::from_parameter(&[1.0, 1024.0]),
+            PointSizeRange {
+                min: 1.0,
+                max: 1024.0
+            }
+        );
+        let guaranteed = PointSizeRange { min: 1.0, max: 1.0 };
+        assert_eq!(PointSizeRange::from_parameter(&[]), guaranteed);
+        assert_eq!(PointSizeRange::from_parameter(&[8.0, 2.0]), guaranteed);
+        assert_eq!(
+            PointSizeRange::from_parameter(&[f32::NAN, 64.0]),
+            guaranteed
+        );
+    }
+
+    #[test]
+    fn supported_sizes_are_drawn_as_points() {
+        for size in [1.0, 16.5, 64.0] {
+            let drawing = PointDrawing::plan(size, RANGE, true);
+            assert_eq!(drawing, PointDrawing::Point(size));
+            assert_eq!(drawing.warning(), None);
+        }
+    }
+
+    #[test]
+    fn large_sizes_are_clamped_with_a_warning() {
+        let drawing = PointDrawing::plan(128.0, RANGE, false);
+        assert_eq!(
+            drawing,
+            PointDrawing::Clamped {
+                requested: 128.0,
+                size: 64.0
+            }
+        );
+        assert_eq!(drawing.size(), 64.0);
+        assert_eq!(
+            drawing.warning().unwrap(),
+            "Points of 128 pixels are not supported, drawn at 64 pixels instead."
+        );
+    }
+
+    #[test]
+    fn large_sizes_fall_back_to_a_quad() {
+        let drawing = PointDrawing::plan(128.0, RANGE, true);
+        assert_eq!(drawing, PointDrawing::Quad(128.0));
+        assert_eq!(drawing.size(), 128.0);
+        assert!(drawing.warning().unwrap().contains("quad"));
+    }
+
+    #[test]
+    fn small_sizes_are_clamped_up() {
+        let range = PointSizeRange {
+            min: 2.0,
+            max: 64.0,
+        };
+        assert_eq!(
+            PointDrawing::plan(0.5, range, true),
+            PointDrawing::Clamped {
+                requested: 0.5,
+                size: 2.0
+            }
+        );
+    }
+}
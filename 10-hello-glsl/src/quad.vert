@@ -0,0 +1,12 @@
+#version 100
+precision highp float;
+
+attribute vec2 corner;
+
+uniform float pointSize;
+uniform vec2 resolution;
+
+// The same square as the point, in pixels around the center.
+void main() {
+  gl_Position = vec4(corner * pointSize / resolution, 0.0, 1.0);
+}
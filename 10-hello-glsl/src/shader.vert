@@ -1,5 +1,7 @@
 #version 100
+uniform float pointSize;
+
 void main() {
   gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
-  gl_PointSize = 64.0;
+  gl_PointSize = pointSize;
 }
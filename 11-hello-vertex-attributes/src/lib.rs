@@ -1,14 +1,39 @@
 use js_sys::Object;
-use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Document, Element, HtmlCanvasElement, HtmlElement, MouseEvent, WebGl2RenderingContext,
-    WebGlBuffer, WebGlProgram, Window,
+    Document, Element, Event, EventTarget, HtmlCanvasElement, HtmlElement, MouseEvent,
+    WebGl2RenderingContext, WebGlUniformLocation, Window,
 };
+use webgl_common::{
+    Example, Gl, GrowableVertexBuffer, Host, Mat3, Mat4, ResourceRegistry, Setup, Vec2, Vertex,
+};
+
+webgl_common::example_info!();
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct PointVertex {
+    position: Vec2,
+}
+
+struct PointsDisplay {
+    count_display: Element,
+}
+
+impl PointsDisplay {
+    fn new(document: &Document) -> PointsDisplay {
+        let count_display = document.query_selector("strong").unwrap().unwrap();
+        PointsDisplay { count_display }
+    }
+
+    fn update(&self, count: usize) {
+        self.count_display
+            .set_text_content(Some(&count.to_string()));
+    }
+}
 
-fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
+fn get_rendering_context(document: &Document) -> (HtmlCanvasElement, Gl) {
     let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
     let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
     let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
@@ -19,115 +44,164 @@ fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
     canvas.set_width(canvas.client_width() as u32);
     canvas.set_height(canvas.client_height() as u32);
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    let gl: Gl = context.unchecked_into::<Gl>();
 
     gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
     gl.clear_color(0.0, 0.0, 0.0, 1.0);
     gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
 
-    gl
+    (canvas, gl)
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let gl = Rc::new(get_rendering_context(&document));
-    let gl2 = Rc::clone(&gl);
-
-    let vertex_shader = gl
-        .create_shader(WebGl2RenderingContext::VERTEX_SHADER)
-        .unwrap();
-    gl.shader_source(&vertex_shader, include_str!("shader.vert"));
-    gl.compile_shader(&vertex_shader);
-
-    let fragment_shader = gl
-        .create_shader(WebGl2RenderingContext::FRAGMENT_SHADER)
-        .unwrap();
-    gl.shader_source(&fragment_shader, include_str!("shader.frag"));
-    gl.compile_shader(&fragment_shader);
-    let program = gl.create_program().unwrap();
-
-    gl.attach_shader(&program, &vertex_shader);
-    gl.attach_shader(&program, &fragment_shader);
-
-    gl.link_program(&program);
-    gl.detach_shader(&program, &vertex_shader);
-    gl.detach_shader(&program, &fragment_shader);
-    gl.delete_shader(Some(&vertex_shader));
-    gl.delete_shader(Some(&fragment_shader));
-
-    if gl
-        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        gl.enable_vertex_attrib_array(0);
-        let buffer = gl.create_buffer().unwrap();
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        unsafe {
-            let positions_array_buf_view = js_sys::Float32Array::view(&[0.0]);
-
-            gl2.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &positions_array_buf_view,
-                WebGl2RenderingContext::STATIC_DRAW,
+fn draw_points(
+    gl: &Gl,
+    buffer: &GrowableVertexBuffer<PointVertex>,
+    points_display: &PointsDisplay,
+) {
+    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+    gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, buffer.len() as i32);
+    points_display.update(buffer.len());
+}
+
+fn drawing_buffer_size(gl: &Gl) -> Vec2 {
+    Vec2::new(
+        gl.drawing_buffer_width() as f32,
+        gl.drawing_buffer_height() as f32,
+    )
+}
+
+fn add_point(gl: &Gl, evt: &MouseEvent, buffer: &mut GrowableVertexBuffer<PointVertex>) {
+    // Transform the position of the click from window coordinates
+    // to pixels of the drawing buffer, where the vertical axis points
+    // up. The click is in CSS pixels, so it is scaled by the size of
+    // the drawing buffer over the size of the canvas on the page.
+    let target: HtmlElement = evt.target().unwrap().unchecked_into::<HtmlElement>();
+    let to_buffer = Mat3::css_to_buffer(
+        Vec2::new(target.client_width() as f32, target.client_height() as f32),
+        drawing_buffer_size(gl),
+    );
+    let position = to_buffer.transform_point(Vec2::new(
+        (evt.page_x() - target.offset_left()) as f32,
+        (evt.page_y() - target.offset_top()) as f32,
+    ));
+
+    buffer.push(gl, PointVertex { position });
+}
+
+pub struct HelloVertexAttributes {
+    gl: Gl,
+    // Owns the program, the buffer grows and manages its own object.
+    resources: ResourceRegistry,
+    buffer: GrowableVertexBuffer<PointVertex>,
+    // The projection maps drawing buffer pixels to clip space, `size` is
+    // the drawing buffer it was last set up for.
+    projection: Option<WebGlUniformLocation>,
+    size: Vec2,
+    points_display: PointsDisplay,
+    canvas: HtmlCanvasElement,
+    undo: Element,
+    clear: Element,
+}
+
+impl Example for HelloVertexAttributes {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+
+        let (canvas, gl) = get_rendering_context(&document);
+
+        let mut resources = ResourceRegistry::new(&gl);
+        let program = resources.link_program(
+            "points",
+            include_str!("shader.vert"),
+            include_str!("shader.frag"),
+        )?;
+
+        let buffer: GrowableVertexBuffer<PointVertex> =
+            GrowableVertexBuffer::with_capacity(&gl, 4)?;
+        buffer.bind_attributes(&gl, &program);
+        gl.use_program(Some(&program));
+        let projection = gl.get_uniform_location(&program, "projection");
+        let size = drawing_buffer_size(&gl);
+        Mat4::pixel_ortho(size.x, size.y).upload(&gl, projection.as_ref());
+
+        let points_display = PointsDisplay::new(&document);
+        let undo = document.query_selector("#undo").unwrap().unwrap();
+        let clear = document.query_selector("#clear").unwrap().unwrap();
+
+        // Every click appends a new point to the buffer, all points
+        // collected so far are drawn again.
+        setup.listen(&canvas, "click");
+        setup.listen(&undo, "click");
+        setup.listen(&clear, "click");
+        setup.observe_resize(&canvas, &gl);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+
+        Ok(HelloVertexAttributes {
+            gl,
+            resources,
+            buffer,
+            projection,
+            size,
+            points_display,
+            canvas,
+            undo,
+            clear,
+        })
+    }
+
+    fn render(&mut self) {
+        draw_points(&self.gl, &self.buffer, &self.points_display);
+    }
+
+    // Points stay at the same place relative to the canvas, their pixel
+    // positions are scaled to the new drawing buffer.
+    fn resize(&mut self, width: u32, height: u32) {
+        let size = Vec2::new(width as f32, height as f32);
+        let scale = Mat3::scale(Vec2::new(
+            size.x / self.size.x.max(1.0),
+            size.y / self.size.y.max(1.0),
+        ));
+        let points = self.buffer.vertices().to_vec();
+        self.buffer.clear();
+        for point in points {
+            self.buffer.push(
+                &self.gl,
+                PointVertex {
+                    position: scale.transform_point(point.position),
+                },
             );
         }
-        gl.vertex_attrib_pointer_with_i32(0, 1, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        Mat4::pixel_ortho(size.x, size.y).upload(&self.gl, self.projection.as_ref());
+        self.size = size;
+    }
 
-        gl.use_program(Some(&program));
-        gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, 1);
-
-        let click_closure = Closure::<dyn FnMut(MouseEvent)>::new(move |evt: MouseEvent| {
-            let target: HtmlElement = evt.target().unwrap().unchecked_into::<HtmlElement>();
-            let click_x_relative_to_canvas = evt.page_x() - target.offset_left();
-            let click_x_in_webgl_coords = 2.0
-                * (click_x_relative_to_canvas as f32 - (gl2.drawing_buffer_width() as f32 / 2.0))
-                / gl2.drawing_buffer_width() as f32;
-            unsafe {
-                let positions_array_buf_view =
-                    js_sys::Float32Array::view(&[click_x_in_webgl_coords]);
-
-                gl2.buffer_data_with_array_buffer_view(
-                    WebGl2RenderingContext::ARRAY_BUFFER,
-                    &positions_array_buf_view,
-                    WebGl2RenderingContext::STATIC_DRAW,
-                );
-            }
-            gl2.draw_arrays(WebGl2RenderingContext::POINTS, 0, 1);
-        });
-
-        let unload_closure =
-            Closure::<dyn FnMut()>::new(move || clean_up(&gl, Some(&program), Some(&buffer)));
-
-        let click_function = click_closure.as_ref().unchecked_ref();
-        let unload_function = unload_closure.as_ref().unchecked_ref();
-
-        drop(
-            document
-                .query_selector("canvas")
-                .unwrap()
-                .unwrap()
-                .add_event_listener_with_callback("click", click_function),
-        );
-        drop(window.add_event_listener_with_callback("beforeunload", unload_function));
-        click_closure.forget();
-        unload_closure.forget();
-    } else {
-        clean_up(&gl, Some(&program), None)
+    fn on_event(&mut self, event: &Event) {
+        let target = event.current_target();
+        let is_target = |element: &EventTarget| target.as_ref() == Some(element);
+
+        if is_target(&self.canvas) {
+            add_point(
+                &self.gl,
+                event.unchecked_ref::<MouseEvent>(),
+                &mut self.buffer,
+            );
+        } else if is_target(&self.undo) {
+            self.buffer.pop();
+        } else if is_target(&self.clear) {
+            self.buffer.clear();
+        }
     }
 
-    Ok(())
+    fn destroy(&mut self) {
+        self.gl.use_program(None);
+        self.buffer.delete(&self.gl);
+        self.resources.clear();
+    }
 }
 
-fn clean_up(
-    gl: &WebGl2RenderingContext,
-    program: Option<&WebGlProgram>,
-    buffer: Option<&WebGlBuffer>,
-) {
-    gl.use_program(None);
-    gl.delete_buffer(buffer);
-    gl.delete_program(program);
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<HelloVertexAttributes>()?.keep_until_unload();
+    Ok(())
 }
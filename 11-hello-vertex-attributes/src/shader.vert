@@ -1,9 +1,11 @@
 #version 100
 precision highp float;
 
-attribute float position;
+// Position in pixels of the drawing buffer, from its bottom left corner.
+attribute vec2 position;
+uniform mat4 projection;
 
 void main() {
-  gl_Position = vec4(position, 0.0, 0.0, 1.0);
+  gl_Position = projection * vec4(position, 0.0, 1.0);
   gl_PointSize = 64.0;
 }
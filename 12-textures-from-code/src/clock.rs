@@ -0,0 +1,100 @@
+/// Time a single step advances the animation by, one frame at 60 frames
+/// per second.
+pub const FRAME_DURATION: f64 = 1.0 / 60.0;
+
+/// Time of the animation, which can be paused and stepped frame by frame
+/// to inspect it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Clock {
+    time: f64,
+    frame: u64,
+    paused: bool,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds since the animation started, not counting pauses.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Frames shown since the animation started, including steps.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Moves on by `dt` seconds unless paused, returns whether it did.
+    pub fn advance(&mut self, dt: f64) -> bool {
+        if self.paused {
+            return false;
+        }
+        // Timestamps may go backwards when the page changes its clock.
+        self.time += dt.max(0.0);
+        self.frame += 1;
+        true
+    }
+
+    /// Pauses or resumes, returns whether it is paused.
+    pub fn toggle(&mut self) -> bool {
+        self.paused = !self.paused;
+        self.paused
+    }
+
+    /// Moves on by a single frame while paused, returns whether it did.
+    pub fn step(&mut self) -> bool {
+        if !self.paused {
+            return false;
+        }
+        self.time += FRAME_DURATION;
+        self.frame += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_until_paused() {
+        let mut clock = Clock::new();
+        assert!(clock.advance(0.5));
+        assert!(clock.advance(0.25));
+        assert_eq!((clock.time(), clock.frame()), (0.75, 2));
+
+        assert!(clock.toggle());
+        assert!(!clock.advance(1.0));
+        assert_eq!((clock.time(), clock.frame()), (0.75, 2));
+
+        assert!(!clock.toggle());
+        assert!(clock.advance(0.25));
+        assert_eq!(clock.time(), 1.0);
+    }
+
+    #[test]
+    fn steps_only_while_paused() {
+        let mut clock = Clock::new();
+        assert!(!clock.step());
+        clock.toggle();
+        for _ in 0..60 {
+            assert!(clock.step());
+        }
+        assert_eq!(clock.frame(), 60);
+        assert!((clock.time() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn time_never_goes_backwards() {
+        let mut clock = Clock::new();
+        clock.advance(0.5);
+        clock.advance(-0.25);
+        assert_eq!(clock.time(), 0.5);
+    }
+}
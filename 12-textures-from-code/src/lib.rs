@@ -1,12 +1,248 @@
-use js_sys::Object;
-use std::rc::Rc;
-use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+mod clock;
+
+pub use clock::{Clock, FRAME_DURATION};
+
+use js_sys::{Math, Object};
+use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    Document, Element, HtmlCanvasElement, WebGl2RenderingContext, WebGlBuffer, WebGlProgram, Window,
+    Document, Element, Event, EventTarget, HtmlButtonElement, HtmlCanvasElement, HtmlInputElement,
+    WebGl2RenderingContext, WebGlProgram, WebGlVertexArrayObject, Window,
+};
+use webgl_common::instance::draw_arrays_instanced;
+use webgl_common::post_process::{Effect, PostProcessChain};
+use webgl_common::{
+    Example, Gl, Host, InstanceBuffer, QuadCorner, ResourceRegistry, Setup, Vertex, VertexBuffer,
+    QUAD_CORNERS,
 };
 
-fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
+webgl_common::example_info!();
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct PointVertex {
+    position: [f32; 2],
+}
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct SpriteInstance {
+    offset: [f32; 2],
+    size: f32,
+    color: [f32; 3],
+}
+
+impl SpriteInstance {
+    fn random() -> Self {
+        let offset = [
+            2.0 * Math::random() as f32 - 1.0,
+            2.0 * Math::random() as f32 - 1.0,
+        ];
+        let size = 8.0 + 56.0 * Math::random() as f32;
+        let color = [
+            2.0 * Math::random() as f32,
+            2.0 * Math::random() as f32,
+            2.0 * Math::random() as f32,
+        ];
+        Self {
+            offset,
+            size,
+            color,
+        }
+    }
+}
+
+// Every pixel goes through the post-processing passes, so the drawing
+// buffer stays below two megapixels even on large high density screens.
+const MAX_PIXELS: u32 = 2_000_000;
+
+// The sprites are sized in pixels and the color of the glow changes
+// across the canvas, so the shaders need the size of the drawing buffer.
+fn set_resolution(gl: &Gl, program: &WebGlProgram) {
+    gl.use_program(Some(program));
+    gl.uniform2f(
+        gl.get_uniform_location(program, "resolution").as_ref(),
+        gl.drawing_buffer_width() as f32,
+        gl.drawing_buffer_height() as f32,
+    );
+}
+
+// Everything needed to draw either the single glowing point of the
+// original example, or the stress test with many instanced sprites.
+struct Scene {
+    // Programs and vertex arrays, the buffers and the post-processing
+    // chain manage their own objects.
+    resources: ResourceRegistry,
+    glow_program: WebGlProgram,
+    glow_vertex_array: WebGlVertexArrayObject,
+    point_buffer: VertexBuffer<PointVertex>,
+    sprite_program: WebGlProgram,
+    sprite_vertex_array: WebGlVertexArrayObject,
+    corner_buffer: VertexBuffer<QuadCorner>,
+    sprites: Vec<SpriteInstance>,
+    instance_buffer: InstanceBuffer<SpriteInstance>,
+    stress_test: bool,
+    post_process: PostProcessChain,
+    // Whether grayscale, blur and vignette are on, in the order they
+    // are applied.
+    effects: [bool; 3],
+}
+
+impl Scene {
+    fn new(gl: &Gl, sprite_count: usize) -> Result<Self, JsValue> {
+        let mut resources = ResourceRegistry::new(gl);
+        let glow_program = resources.link_program(
+            "glow",
+            include_str!("shader.vert"),
+            include_str!("shader.frag"),
+        )?;
+        let sprite_program = resources.link_program(
+            "sprites",
+            include_str!("sprite.vert"),
+            include_str!("sprite.frag"),
+        )?;
+
+        let glow_vertex_array = resources.create_vertex_array("glow")?;
+        gl.bind_vertex_array(Some(&glow_vertex_array));
+        let point_buffer = VertexBuffer::new(
+            gl,
+            &[PointVertex {
+                position: [0.0, 0.0],
+            }],
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+        point_buffer.bind_attributes(gl, &glow_program);
+
+        let sprites: Vec<SpriteInstance> = (0..sprite_count)
+            .map(|_| SpriteInstance::random())
+            .collect();
+
+        let sprite_vertex_array = resources.create_vertex_array("sprites")?;
+        gl.bind_vertex_array(Some(&sprite_vertex_array));
+        let corner_buffer =
+            VertexBuffer::new(gl, &QUAD_CORNERS, WebGl2RenderingContext::STATIC_DRAW)?;
+        corner_buffer.bind_attributes(gl, &sprite_program);
+        let instance_buffer =
+            InstanceBuffer::new(gl, &sprites, WebGl2RenderingContext::DYNAMIC_DRAW)?;
+        instance_buffer.bind_attributes(gl, &sprite_program);
+        gl.bind_vertex_array(None);
+
+        set_resolution(gl, &glow_program);
+        set_resolution(gl, &sprite_program);
+
+        let post_process =
+            PostProcessChain::new(gl, gl.drawing_buffer_width(), gl.drawing_buffer_height())?;
+
+        Ok(Self {
+            resources,
+            glow_program,
+            glow_vertex_array,
+            point_buffer,
+            sprite_program,
+            sprite_vertex_array,
+            corner_buffer,
+            sprites,
+            instance_buffer,
+            stress_test: false,
+            post_process,
+            effects: [false; 3],
+        })
+    }
+
+    fn resize(&mut self, gl: &Gl) {
+        set_resolution(gl, &self.glow_program);
+        set_resolution(gl, &self.sprite_program);
+        // The targets only have a color texture, which is always
+        // complete unless the context is lost and nothing is drawn anyway.
+        self.post_process
+            .resize(gl, gl.drawing_buffer_width(), gl.drawing_buffer_height())
+            .ok();
+    }
+
+    fn toggle_effect(&mut self, index: usize) -> bool {
+        self.effects[index] = !self.effects[index];
+
+        let effects = [
+            Effect::Grayscale,
+            Effect::Blur { radius: 6.0 },
+            Effect::Vignette { strength: 0.8 },
+        ]
+        .into_iter()
+        .zip(self.effects)
+        .filter_map(|(effect, enabled)| enabled.then_some(effect))
+        .collect();
+        self.post_process.set_effects(effects);
+
+        self.effects[index]
+    }
+
+    fn set_sprite_count(&mut self, gl: &Gl, sprite_count: usize) {
+        // Keep the sprites which are already on screen, so that moving
+        // the slider only adds or removes sprites.
+        self.sprites.truncate(sprite_count);
+        while self.sprites.len() < sprite_count {
+            self.sprites.push(SpriteInstance::random());
+        }
+        self.instance_buffer.set_data(gl, &self.sprites);
+    }
+
+    fn draw(&self, gl: &Gl, time: f64) {
+        // Without any effect the scene is drawn straight into the canvas.
+        let post_processing = !self.post_process.effects().is_empty();
+        if post_processing {
+            self.post_process.begin(gl);
+        }
+
+        self.draw_scene(gl, time);
+
+        if post_processing {
+            self.post_process.finish(gl);
+        }
+    }
+
+    fn draw_scene(&self, gl: &Gl, time: f64) {
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+
+        if self.stress_test {
+            gl.use_program(Some(&self.sprite_program));
+            gl.bind_vertex_array(Some(&self.sprite_vertex_array));
+            gl.enable(WebGl2RenderingContext::BLEND);
+            gl.blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ONE);
+            draw_arrays_instanced(
+                gl,
+                WebGl2RenderingContext::TRIANGLE_STRIP,
+                QUAD_CORNERS.len() as i32,
+                &self.instance_buffer,
+            );
+            gl.disable(WebGl2RenderingContext::BLEND);
+        } else {
+            gl.use_program(Some(&self.glow_program));
+            gl.uniform1f(
+                gl.get_uniform_location(&self.glow_program, "time").as_ref(),
+                time as f32,
+            );
+            gl.bind_vertex_array(Some(&self.glow_vertex_array));
+            gl.draw_arrays(
+                WebGl2RenderingContext::POINTS,
+                0,
+                self.point_buffer.len() as i32,
+            );
+        }
+
+        gl.bind_vertex_array(None);
+    }
+
+    fn clean_up(&mut self, gl: &Gl) {
+        gl.use_program(None);
+        self.point_buffer.delete(gl);
+        self.corner_buffer.delete(gl);
+        self.instance_buffer.delete(gl);
+        self.post_process.delete(gl);
+        self.resources.clear();
+    }
+}
+
+fn get_rendering_context(document: &Document) -> (HtmlCanvasElement, Gl) {
     let canvas: Element = document.query_selector("canvas").unwrap().unwrap();
     let canvas: HtmlCanvasElement = canvas.unchecked_into::<HtmlCanvasElement>();
     let context: Object = canvas.get_context("webgl2").unwrap().unwrap();
@@ -17,80 +253,146 @@ fn get_rendering_context(document: &Document) -> WebGl2RenderingContext {
     canvas.set_width(canvas.client_width() as u32);
     canvas.set_height(canvas.client_height() as u32);
 
-    let gl: WebGl2RenderingContext = context.unchecked_into::<WebGl2RenderingContext>();
+    let gl: Gl = context.unchecked_into::<Gl>();
 
     gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
     gl.clear_color(0.0, 0.0, 0.0, 1.0);
     gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
 
-    gl
+    (canvas, gl)
 }
 
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-
-    let gl = Rc::new(get_rendering_context(&document));
-    let gl2 = Rc::clone(&gl);
-
-    let vertex_shader = gl
-        .create_shader(WebGl2RenderingContext::VERTEX_SHADER)
-        .unwrap();
-    gl.shader_source(&vertex_shader, include_str!("shader.vert"));
-    gl.compile_shader(&vertex_shader);
-
-    let fragment_shader = gl
-        .create_shader(WebGl2RenderingContext::FRAGMENT_SHADER)
-        .unwrap();
-    gl.shader_source(&fragment_shader, include_str!("shader.frag"));
-    gl.compile_shader(&fragment_shader);
-    let program = gl.create_program().unwrap();
-
-    gl.attach_shader(&program, &vertex_shader);
-    gl.attach_shader(&program, &fragment_shader);
-
-    gl.link_program(&program);
-    gl.detach_shader(&program, &vertex_shader);
-    gl.detach_shader(&program, &fragment_shader);
-    gl.delete_shader(Some(&vertex_shader));
-    gl.delete_shader(Some(&fragment_shader));
-
-    if gl
-        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        gl.enable_vertex_attrib_array(0);
-        let buffer = gl.create_buffer().unwrap();
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        unsafe {
-            let positions_array_buf_view = js_sys::Float32Array::view(&[0.0, 0.0]);
-
-            gl2.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &positions_array_buf_view,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
+pub struct TexturesFromCode {
+    gl: Gl,
+    scene: Scene,
+    stress_test_toggle: Element,
+    instance_count: HtmlInputElement,
+    instance_count_display: Element,
+    // Grayscale, blur and vignette toggles, in the order of `Scene::effects`.
+    effect_toggles: [Element; 3],
+    clock: Clock,
+    pause_toggle: Element,
+    frame_step: HtmlButtonElement,
+    frame_display: Element,
+}
+
+impl TexturesFromCode {
+    fn show_clock(&self) {
+        self.frame_display.set_text_content(Some(&format!(
+            "frame {}, {:.3} s",
+            self.clock.frame(),
+            self.clock.time()
+        )));
+    }
+}
+
+impl Example for TexturesFromCode {
+    fn init(setup: &mut Setup) -> Result<Self, JsValue> {
+        let window: Window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+
+        let (canvas, gl) = get_rendering_context(&document);
+
+        let stress_test_toggle: Element = document.query_selector("#stress-test").unwrap().unwrap();
+        let instance_count: HtmlInputElement = document
+            .query_selector("#instance-count")
+            .unwrap()
+            .unwrap()
+            .unchecked_into::<HtmlInputElement>();
+        let instance_count_display: Element = document.query_selector("strong").unwrap().unwrap();
+        let effect_toggles = ["#grayscale", "#blur", "#vignette"]
+            .map(|selector| document.query_selector(selector).unwrap().unwrap());
+
+        let pause_toggle: Element = document.query_selector("#pause").unwrap().unwrap();
+        let frame_step: HtmlButtonElement = document
+            .query_selector("#frame-step")
+            .unwrap()
+            .unwrap()
+            .unchecked_into::<HtmlButtonElement>();
+        let frame_display: Element = document.query_selector("#frame-time").unwrap().unwrap();
+
+        let sprite_count = instance_count.value_as_number() as usize;
+        let scene = Scene::new(&gl, sprite_count)?;
+
+        setup.listen(&stress_test_toggle, "click");
+        setup.listen(&instance_count, "input");
+        for effect_toggle in &effect_toggles {
+            setup.listen(effect_toggle, "click");
         }
-        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        setup.listen(&pause_toggle, "click");
+        setup.listen(&frame_step, "click");
+        setup.observe_resize(&canvas, &gl);
+        setup.limit_pixels(MAX_PIXELS);
+        setup.bind_snapshot(&gl, concat!(env!("CARGO_PKG_NAME"), ".png"));
+        setup.animate();
+
+        // Frames can only be stepped through while paused.
+        frame_step.set_disabled(true);
 
-        gl.use_program(Some(&program));
-        gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, 1);
-        clean_up(&gl, Some(&program), Some(&buffer))
-    } else {
-        clean_up(&gl, Some(&program), None)
+        Ok(TexturesFromCode {
+            gl,
+            scene,
+            stress_test_toggle,
+            instance_count,
+            instance_count_display,
+            effect_toggles,
+            clock: Clock::new(),
+            pause_toggle,
+            frame_step,
+            frame_display,
+        })
     }
 
-    Ok(())
+    fn update(&mut self, dt: f64) {
+        self.clock.advance(dt);
+    }
+
+    fn render(&mut self) {
+        self.scene.draw(&self.gl, self.clock.time());
+        self.show_clock();
+    }
+
+    fn resize(&mut self, _width: u32, _height: u32) {
+        self.scene.resize(&self.gl);
+    }
+
+    fn on_event(&mut self, event: &Event) {
+        let target = event.current_target();
+        let is_target = |element: &EventTarget| target.as_ref() == Some(element);
+
+        if is_target(&self.stress_test_toggle) {
+            self.scene.stress_test = !self.scene.stress_test;
+            self.stress_test_toggle
+                .set_text_content(Some(if self.scene.stress_test { "On" } else { "Off" }));
+        } else if is_target(&self.instance_count) {
+            let sprite_count = self.instance_count.value_as_number() as usize;
+            self.instance_count_display
+                .set_text_content(Some(&sprite_count.to_string()));
+            self.scene.set_sprite_count(&self.gl, sprite_count);
+        } else if is_target(&self.pause_toggle) {
+            let paused = self.clock.toggle();
+            self.pause_toggle
+                .set_text_content(Some(if paused { "Resume" } else { "Pause" }));
+            self.frame_step.set_disabled(!paused);
+        } else if is_target(&self.frame_step) {
+            self.clock.step();
+        } else if let Some(index) = self
+            .effect_toggles
+            .iter()
+            .position(|effect_toggle| is_target(effect_toggle))
+        {
+            let enabled = self.scene.toggle_effect(index);
+            self.effect_toggles[index].set_text_content(Some(if enabled { "On" } else { "Off" }));
+        }
+    }
+
+    fn destroy(&mut self) {
+        self.scene.clean_up(&self.gl);
+    }
 }
 
-fn clean_up(
-    gl: &WebGl2RenderingContext,
-    program: Option<&WebGlProgram>,
-    buffer: Option<&WebGlBuffer>,
-) {
-    gl.use_program(None);
-    gl.delete_buffer(buffer);
-    gl.delete_program(program);
+#[cfg_attr(feature = "standalone", wasm_bindgen::prelude::wasm_bindgen(start))]
+pub fn start() -> Result<(), JsValue> {
+    Host::start::<TexturesFromCode>()?.keep_until_unload();
+    Ok(())
 }
@@ -1,11 +1,17 @@
 #version 100
 precision mediump float;
+
+uniform highp float time;
+uniform highp vec2 resolution;
+
 void main() {
   vec2 fragmentPosition = 2.0 * gl_PointCoord - 1.0;
   float distance = length(fragmentPosition);
   float distanceSqrd = distance * distance;
-  gl_FragColor = vec4(
-                      0.2 / distanceSqrd,
-                      0.1 / distanceSqrd,
-                      0.0, 1.0 );
+  // The glow pulses once a second, and its color goes around the hue
+  // circle every six seconds, ahead on the right side of the canvas.
+  float pulse = 1.0 + 0.3 * sin(6.2831853 * time);
+  float hue = time / 6.0 + 0.25 * gl_FragCoord.x / resolution.x;
+  vec3 tint = 0.5 + 0.5 * cos(6.2831853 * (hue + vec3(0.0, 0.15, 0.5)));
+  gl_FragColor = vec4(pulse * 0.2 / distanceSqrd * tint, 1.0);
 }
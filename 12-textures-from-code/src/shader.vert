@@ -3,7 +3,12 @@ precision highp float;
 
 attribute vec2 position;
 
+uniform float time;
+
 void main() {
-  gl_Position = vec4(position, 0.0, 1.0);
+  // The glow moves along a figure eight, once every eight seconds.
+  float angle = 0.25 * 3.14159265 * time;
+  vec2 path = vec2(0.5 * sin(angle), 0.35 * sin(2.0 * angle));
+  gl_Position = vec4(position + path, 0.0, 1.0);
   gl_PointSize = 128.0;
 }
@@ -0,0 +1,14 @@
+#version 100
+precision mediump float;
+
+varying vec2 spriteCoord;
+varying vec3 spriteColor;
+
+void main() {
+  float distanceSqrd = dot(spriteCoord, spriteCoord);
+  // Same glow as in shader.frag, shifted so that it fades out
+  // completely at the edge of the quad. Sprites are blended
+  // additively, so any leftover would add up to visible squares.
+  float glow = max(0.1 / distanceSqrd - 0.1, 0.0);
+  gl_FragColor = vec4(glow * spriteColor, 1.0);
+}
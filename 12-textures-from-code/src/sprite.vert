@@ -0,0 +1,18 @@
+#version 100
+precision highp float;
+
+attribute vec2 corner;
+attribute vec2 offset;
+attribute float size;
+attribute vec3 color;
+
+uniform vec2 resolution;
+
+varying vec2 spriteCoord;
+varying vec3 spriteColor;
+
+void main() {
+  spriteCoord = corner;
+  spriteColor = color;
+  gl_Position = vec4(offset + corner * size / resolution, 0.0, 1.0);
+}
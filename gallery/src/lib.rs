@@ -0,0 +1,184 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use wasm_bindgen::JsCast;
+use web_sys::{console, Document, Element, Window};
+use webgl_common::{resources, ExampleInfo, Host};
+
+mod pages;
+
+struct Entry {
+    info: ExampleInfo,
+    // Adds the elements the example expects to find in the document.
+    build_page: fn(&Document, &Element) -> Result<(), JsValue>,
+    start: fn() -> Result<Host, JsValue>,
+}
+
+const EXAMPLES: [Entry; 12] = [
+    Entry {
+        info: detect_webgl::INFO,
+        build_page: pages::detect_webgl,
+        start: Host::start::<detect_webgl::DetectWebgl>,
+    },
+    Entry {
+        info: clearing_with_colors::INFO,
+        build_page: pages::clearing_with_colors,
+        start: Host::start::<clearing_with_colors::ClearingWithColors>,
+    },
+    Entry {
+        info: clearing_by_clicking::INFO,
+        build_page: pages::clearing_by_clicking,
+        start: Host::start::<clearing_by_clicking::ClearingByClicking>,
+    },
+    Entry {
+        info: simple_color_animation::INFO,
+        build_page: pages::simple_color_animation,
+        start: Host::start::<simple_color_animation::SimpleColorAnimation>,
+    },
+    Entry {
+        info: color_masking::INFO,
+        build_page: pages::color_masking,
+        start: Host::start::<color_masking::ColorMasking>,
+    },
+    Entry {
+        info: basic_scissoring::INFO,
+        build_page: pages::basic_scissoring,
+        start: Host::start::<basic_scissoring::BasicScissoring>,
+    },
+    Entry {
+        info: canvas_size_and_webgl::INFO,
+        build_page: pages::canvas_size_and_webgl,
+        start: Host::start::<canvas_size_and_webgl::CanvasSizeAndWebgl>,
+    },
+    Entry {
+        info: scissor_animation::INFO,
+        build_page: pages::scissor_animation,
+        start: Host::start::<scissor_animation::ScissorAnimation>,
+    },
+    Entry {
+        info: raining_rectangles::INFO,
+        build_page: pages::raining_rectangles,
+        start: Host::start::<raining_rectangles::RainingRectangles>,
+    },
+    Entry {
+        info: hello_glsl::INFO,
+        build_page: pages::hello_glsl,
+        start: Host::start::<hello_glsl::HelloGlsl>,
+    },
+    Entry {
+        info: hello_vertex_attributes::INFO,
+        build_page: pages::hello_vertex_attributes,
+        start: Host::start::<hello_vertex_attributes::HelloVertexAttributes>,
+    },
+    Entry {
+        info: textures_from_code::INFO,
+        build_page: pages::textures_from_code,
+        start: Host::start::<textures_from_code::TexturesFromCode>,
+    },
+];
+
+// The examples look up the first `p`, `button`, `strong` or `canvas` of
+// the document, so the navigation and the header only use other elements.
+fn build_navigation(document: &Document) -> Result<(), JsValue> {
+    let list: Element = document.query_selector("nav ol").unwrap().unwrap();
+
+    for entry in EXAMPLES.iter() {
+        let item = document.create_element("li")?;
+        let link = document.create_element("a")?;
+        link.set_attribute("href", &format!("#{}", entry.info.name))?;
+        link.set_text_content(Some(entry.info.description));
+        item.append_with_node_1(&link)?;
+        list.append_with_node_1(&item)?;
+    }
+
+    Ok(())
+}
+
+fn highlight_navigation(document: &Document, current: &Entry) {
+    let links = document.query_selector_all("nav a").unwrap();
+    for index in 0..links.length() {
+        let link = links.get(index).unwrap().unchecked_into::<Element>();
+        let href = link.get_attribute("href").unwrap_or_default();
+        let is_current = href.trim_start_matches('#') == current.info.name;
+        link.set_class_name(if is_current { "current" } else { "" });
+    }
+}
+
+fn build_header(document: &Document, entry: &Entry) -> Result<(), JsValue> {
+    let header: Element = document.query_selector("header").unwrap().unwrap();
+
+    let title = document.create_element("h1")?;
+    title.set_text_content(Some(entry.info.description));
+
+    let source = document.create_element("a")?;
+    source.set_attribute("href", entry.info.homepage)?;
+    source.set_text_content(Some("Original article on MDN"));
+
+    header.replace_children_with_node_2(&title, &source);
+    document.set_title(entry.info.description);
+
+    Ok(())
+}
+
+fn current_entry(window: &Window) -> Option<&'static Entry> {
+    let hash = window.location().hash().unwrap_or_default();
+    let route = hash.trim_start_matches('#');
+    EXAMPLES.iter().find(|entry| entry.info.name == route)
+}
+
+// Stops the running example, reporting anything it did not release, and
+// starts the one selected by the URL hash in its place.
+fn switch_example(window: &Window, running: &RefCell<Option<Host>>) -> Result<(), JsValue> {
+    let document = window.document().unwrap();
+
+    if let Some(host) = running.take() {
+        let leaks = host.stop();
+        if !leaks.is_empty() {
+            console::error_1(&resources::leak_report(&leaks).into());
+        }
+    }
+
+    let entry = match current_entry(window) {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+
+    highlight_navigation(&document, entry);
+    build_header(&document, entry)?;
+
+    let main: Element = document.query_selector("main").unwrap().unwrap();
+    main.replace_children_with_node_0();
+    // Lets the style sheet tell the examples apart.
+    main.set_class_name(entry.info.name);
+    (entry.build_page)(&document, &main)?;
+
+    running.replace(Some((entry.start)()?));
+    Ok(())
+}
+
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let window: Window = web_sys::window().unwrap();
+    let document: Document = window.document().unwrap();
+
+    build_navigation(&document)?;
+
+    let running: Rc<RefCell<Option<Host>>> = Rc::new(RefCell::new(None));
+    let running2 = Rc::clone(&running);
+    switch_example(&window, &running)?;
+
+    let window2 = window.clone();
+    let hash_change_closure = Closure::<dyn Fn()>::new(move || {
+        if let Err(error) = switch_example(&window2, &running2) {
+            console::error_1(&error);
+        }
+    });
+    drop(window.add_event_listener_with_callback(
+        "hashchange",
+        hash_change_closure.as_ref().unchecked_ref(),
+    ));
+    hash_change_closure.forget();
+
+    Ok(())
+}
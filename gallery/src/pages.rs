@@ -0,0 +1,482 @@
+use wasm_bindgen::prelude::JsValue;
+use web_sys::{Document, Element};
+
+// Builds the same elements as the `index.html` of each example. The
+// examples look their elements up by tag name or id, so the order and
+// the ids have to match.
+
+fn append(document: &Document, parent: &Element, tag: &str) -> Result<Element, JsValue> {
+    let element = document.create_element(tag)?;
+    parent.append_with_node_1(&element)?;
+    Ok(element)
+}
+
+fn paragraph(document: &Document, parent: &Element, text: &str) -> Result<Element, JsValue> {
+    let paragraph = append(document, parent, "p")?;
+    paragraph.append_with_str_1(text)?;
+    Ok(paragraph)
+}
+
+fn canvas(document: &Document, parent: &Element, id: Option<&str>) -> Result<(), JsValue> {
+    let canvas = append(document, parent, "canvas")?;
+    if let Some(id) = id {
+        canvas.set_id(id);
+    }
+    canvas.append_with_str_1("Your browser does not seem to support HTML5 canvas.")
+}
+
+fn button(
+    document: &Document,
+    parent: &Element,
+    id: Option<&str>,
+    label: &str,
+) -> Result<Element, JsValue> {
+    let button = append(document, parent, "button")?;
+    if let Some(id) = id {
+        button.set_id(id);
+    }
+    button.append_with_str_1(label)?;
+    Ok(button)
+}
+
+fn set_attributes(element: &Element, attributes: &[(&str, &str)]) -> Result<(), JsValue> {
+    for (name, value) in attributes {
+        element.set_attribute(name, value)?;
+    }
+    Ok(())
+}
+
+// A labelled select element with options given as value and text.
+fn select(
+    document: &Document,
+    parent: &Element,
+    text: &str,
+    id: &str,
+    options: &[(&str, &str)],
+    selected: &str,
+) -> Result<(), JsValue> {
+    let label = label(document, parent, text)?;
+    let select = append(document, &label, "select")?;
+    select.set_id(id);
+    for &(value, text) in options {
+        let option = append(document, &select, "option")?;
+        option.set_attribute("value", value)?;
+        if value == selected {
+            option.set_attribute("selected", "")?;
+        }
+        option.append_with_str_1(text)?;
+    }
+    Ok(())
+}
+
+fn label(document: &Document, parent: &Element, text: &str) -> Result<Element, JsValue> {
+    let label = append(document, parent, "label")?;
+    label.append_with_str_1(text)?;
+    label.append_with_str_1(" ")?;
+    Ok(label)
+}
+
+fn keys(document: &Document, parent: &Element, keys: &[&str]) -> Result<(), JsValue> {
+    for (index, key) in keys.iter().enumerate() {
+        if index > 0 {
+            parent.append_with_str_1("+")?;
+        }
+        append(document, parent, "kbd")?.append_with_str_1(key)?;
+    }
+    Ok(())
+}
+
+fn strong(document: &Document, parent: &Element, text: &str) -> Result<(), JsValue> {
+    append(document, parent, "strong")?.append_with_str_1(text)
+}
+
+fn snapshot_hint(document: &Document, parent: &Element) -> Result<(), JsValue> {
+    let hint = paragraph(document, parent, "Press ")?;
+    append(document, &hint, "kbd")?.append_with_str_1("S")?;
+    hint.append_with_str_1(" to save a snapshot of the canvas.")
+}
+
+pub fn detect_webgl(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "[ Here would go the result of WebGL feature detection ]",
+    )?;
+    button(
+        document,
+        root,
+        None,
+        "Press here to detect WebGLRenderingContext",
+    )?;
+    Ok(())
+}
+
+pub fn clearing_with_colors(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "A very simple WebGL program that shows some color.",
+    )?;
+    canvas(document, root, None)?;
+    snapshot_hint(document, root)
+}
+
+pub fn clearing_by_clicking(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "A very simple WebGL program that still shows some color and user interaction.",
+    )?;
+    paragraph(
+        document,
+        root,
+        "You can repeatedly click the empty canvas or the button below to change color.",
+    )?;
+    canvas(document, root, Some("canvas-view"))?;
+    button(
+        document,
+        root,
+        Some("color-switcher"),
+        "Press here to switch color",
+    )?;
+
+    let history = append(document, root, "div")?;
+    history.set_id("color-history");
+    history.set_attribute("aria-label", "Previous colors")?;
+
+    let copy = paragraph(document, root, "Copy the color as ")?;
+    copy.set_id("color-copy");
+    for format in ["hex", "rgb", "hsl"] {
+        button(document, &copy, None, format)?.set_attribute("data-format", format)?;
+        copy.append_with_str_1(" ")?;
+    }
+    append(document, &copy, "output")?.set_id("color-text");
+
+    let hint = paragraph(
+        document,
+        root,
+        "Click a previous color to clear with it again, press ",
+    )?;
+    keys(document, &hint, &["Ctrl", "Z"])?;
+    hint.append_with_str_1(" to undo and ")?;
+    keys(document, &hint, &["Ctrl", "Y"])?;
+    hint.append_with_str_1(" to redo.")?;
+    snapshot_hint(document, root)
+}
+
+pub fn simple_color_animation(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "A simple WebGL program that shows color animation.",
+    )?;
+    paragraph(
+        document,
+        root,
+        "You can click the button below to toggle the color animation on or off.",
+    )?;
+    canvas(document, root, Some("canvas-view"))?;
+    let toggle = button(document, root, Some("animation-onoff"), "Press here to ")?;
+    strong(document, &toggle, "[verb goes here]")?;
+    toggle.append_with_str_1(" the animation")?;
+
+    let settings = append(document, root, "form")?;
+    settings.set_id("transition-settings");
+    let duration = label(document, &settings, "Transition")?;
+    let range = append(document, &duration, "input")?;
+    range.set_id("duration");
+    set_attributes(
+        &range,
+        &[
+            ("type", "range"),
+            ("min", "0"),
+            ("max", "1000"),
+            ("step", "50"),
+            ("value", "500"),
+        ],
+    )?;
+    duration.append_with_str_1(" ")?;
+    append(document, &duration, "output")?.set_id("duration-text");
+    select(
+        document,
+        &settings,
+        "Easing",
+        "easing",
+        &[
+            ("linear", "linear"),
+            ("ease-in-out", "ease-in-out"),
+            ("cubic-bezier", "cubic-bezier"),
+        ],
+        "ease-in-out",
+    )?;
+    let bezier = append(document, &settings, "input")?;
+    bezier.set_id("bezier");
+    set_attributes(
+        &bezier,
+        &[
+            ("value", "0.25, 0.1, 0.25, 1"),
+            ("size", "16"),
+            ("aria-label", "Control points of the cubic Bézier curve"),
+        ],
+    )?;
+    select(
+        document,
+        &settings,
+        "Color space",
+        "color-space",
+        &[
+            ("srgb", "sRGB"),
+            ("linear-rgb", "linear RGB"),
+            ("hsl", "HSL"),
+            ("oklab", "OKLab"),
+        ],
+        "oklab",
+    )?;
+    snapshot_hint(document, root)
+}
+
+pub fn color_masking(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "Tinting the displayed colors with color masking.",
+    )?;
+    canvas(document, root, None)?;
+    for id in ["red-toggle", "green-toggle", "blue-toggle", "alpha-toggle"] {
+        button(document, root, Some(id), "On")?;
+        root.append_with_str_1(" ")?;
+    }
+
+    let clear_alpha = append(document, root, "p")?;
+    let clear_alpha = label(document, &clear_alpha, "Clear alpha")?;
+    let range = append(document, &clear_alpha, "input")?;
+    range.set_id("clear-alpha");
+    set_attributes(
+        &range,
+        &[
+            ("type", "range"),
+            ("min", "0"),
+            ("max", "1"),
+            ("step", "0.05"),
+            ("value", "0.5"),
+        ],
+    )?;
+
+    let attributes = append(document, root, "p")?;
+    let alpha = append(document, &attributes, "label")?;
+    let checkbox = append(document, &alpha, "input")?;
+    checkbox.set_id("context-alpha");
+    set_attributes(&checkbox, &[("type", "checkbox"), ("checked", "")])?;
+    alpha.append_with_str_1(" alpha")?;
+    attributes.append_with_str_1(" ")?;
+    select(
+        document,
+        &attributes,
+        "premultipliedAlpha",
+        "premultiplied-alpha",
+        &[("true", "true"), ("false", "false")],
+        "true",
+    )?;
+
+    let panel = append(document, root, "div")?;
+    panel.set_id("blend-panel");
+    let buffer = paragraph(document, &panel, "Drawing buffer ")?;
+    append(document, &buffer, "code")?.set_id("buffer-pixel");
+    buffer.append_with_str_1(", composited like ")?;
+    append(document, &buffer, "code")?.set_id("blend-func");
+    let swatches = paragraph(document, &panel, "Over the light squares ")?;
+    let light = append(document, &swatches, "span")?;
+    light.set_id("over-light");
+    light.set_class_name("swatch");
+    swatches.append_with_str_1(" and the dark ones ")?;
+    let dark = append(document, &swatches, "span")?;
+    dark.set_id("over-dark");
+    dark.set_class_name("swatch");
+    snapshot_hint(document, root)
+}
+
+pub fn basic_scissoring(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(document, root, "Result of of scissoring.")?;
+    canvas(document, root, None)?;
+    paragraph(
+        document,
+        root,
+        "Drag a region to move it, or the corners of the selected one to resize it.",
+    )?;
+
+    let settings = append(document, root, "div")?;
+    settings.set_id("region-settings");
+    for (text, id, min) in [
+        ("x", "region-x", "0"),
+        ("y", "region-y", "0"),
+        ("width", "region-width", "1"),
+        ("height", "region-height", "1"),
+    ] {
+        let input = append(document, &label(document, &settings, text)?, "input")?;
+        input.set_id(id);
+        set_attributes(&input, &[("type", "number"), ("min", min), ("step", "1")])?;
+    }
+    let color = append(document, &label(document, &settings, "color")?, "input")?;
+    color.set_id("region-color");
+    color.set_attribute("type", "color")?;
+
+    let buttons = append(document, root, "p")?;
+    button(document, &buttons, Some("add-region"), "Add a region")?;
+    buttons.append_with_str_1(" ")?;
+    button(
+        document,
+        &buttons,
+        Some("remove-region"),
+        "Remove the selected region",
+    )?;
+    snapshot_hint(document, root)
+}
+
+pub fn canvas_size_and_webgl(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(document, root, "Compare the two canvases.")?;
+    let canvases = append(document, root, "div")?;
+    canvases.set_id("canvases");
+    canvas(document, &canvases, None)?;
+    canvases.append_with_str_1(" ")?;
+    canvas(document, &canvases, None)?;
+
+    let controls = append(document, root, "p")?;
+    select(
+        document,
+        &controls,
+        "Sizing",
+        "sizing",
+        &[
+            ("default", "default size"),
+            ("css-pixels", "CSS pixels"),
+            ("device-pixels", "device pixels"),
+            ("fixed", "fixed 40x30"),
+        ],
+        "device-pixels",
+    )?;
+    controls.append_with_str_1(" ")?;
+    button(document, &controls, Some("add-canvas"), "Add a canvas")?;
+    snapshot_hint(document, root)
+}
+
+pub fn scissor_animation(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "WebGL animation by clearing the drawing buffer with solid color and applying scissor test.",
+    )?;
+    let toggle = button(document, root, Some("animation-onoff"), "Press here to ")?;
+    strong(document, &toggle, "[verb goes here]")?;
+    toggle.append_with_str_1(" the animation")?;
+    canvas(document, root, None)?;
+    snapshot_hint(document, root)
+}
+
+pub fn raining_rectangles(document: &Document, root: &Element) -> Result<(), JsValue> {
+    let score = paragraph(document, root, "You caught ")?;
+    strong(document, &score, "0")?;
+    score.append_with_str_1(". You missed ")?;
+    strong(document, &score, "0")?;
+    score.append_with_str_1(".")?;
+    canvas(document, root, None)?;
+    snapshot_hint(document, root)
+}
+
+pub fn hello_glsl(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(document, root, "Hello World! Hello GLSL!")?;
+    canvas(document, root, None)?;
+
+    let controls = append(document, root, "p")?;
+    let size = label(document, &controls, "Point size")?;
+    let range = append(document, &size, "input")?;
+    range.set_id("point-size");
+    set_attributes(
+        &range,
+        &[
+            ("type", "range"),
+            ("min", "1"),
+            ("max", "256"),
+            ("step", "1"),
+            ("value", "64"),
+        ],
+    )?;
+    size.append_with_str_1(" ")?;
+    append(document, &size, "output")?.set_id("point-size-text");
+    controls.append_with_str_1(" ")?;
+    let fallback = append(document, &controls, "label")?;
+    let checkbox = append(document, &fallback, "input")?;
+    checkbox.set_id("quad-fallback");
+    set_attributes(&checkbox, &[("type", "checkbox"), ("checked", "")])?;
+    fallback.append_with_str_1(" draw a quad when points are too limited")?;
+
+    let range = paragraph(document, root, "ALIASED_POINT_SIZE_RANGE is ")?;
+    append(document, &range, "span")?.set_id("point-size-range");
+    range.append_with_str_1(".")?;
+    let warning = append(document, root, "p")?;
+    warning.set_id("point-size-warning");
+    warning.set_attribute("hidden", "")?;
+    snapshot_hint(document, root)
+}
+
+pub fn hello_vertex_attributes(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "First encounter with attributes and sending data to GPU. Click on the canvas to add a square at the clicked position.",
+    )?;
+    canvas(document, root, None)?;
+    let count = paragraph(document, root, "Squares drawn: ")?;
+    strong(document, &count, "0")?;
+    button(document, root, Some("undo"), "Undo")?;
+    root.append_with_str_1(" ")?;
+    button(document, root, Some("clear"), "Clear")?;
+    snapshot_hint(document, root)
+}
+
+pub fn textures_from_code(document: &Document, root: &Element) -> Result<(), JsValue> {
+    paragraph(
+        document,
+        root,
+        "Texture from code. Simple demonstration of procedural texturing",
+    )?;
+    canvas(document, root, None)?;
+
+    let animation = append(document, root, "p")?;
+    button(document, &animation, Some("pause"), "Pause")?;
+    animation.append_with_str_1(" ")?;
+    button(document, &animation, Some("frame-step"), "Step one frame")?;
+    animation.append_with_str_1(" ")?;
+    append(document, &animation, "output")?.set_id("frame-time");
+
+    let stress_test = paragraph(document, root, "Stress test ")?;
+    button(document, &stress_test, Some("stress-test"), "Off")?;
+
+    let sprites = append(document, root, "p")?;
+    let instance_count = append(document, &sprites, "input")?;
+    instance_count.set_id("instance-count");
+    set_attributes(
+        &instance_count,
+        &[
+            ("type", "range"),
+            ("min", "100"),
+            ("max", "20000"),
+            ("step", "100"),
+            ("value", "1000"),
+        ],
+    )?;
+    sprites.append_with_str_1(" ")?;
+    strong(document, &sprites, "1000")?;
+    sprites.append_with_str_1(" sprites")?;
+
+    let effects = append(document, root, "p")?;
+    for (label, id) in [
+        ("Grayscale ", "grayscale"),
+        (" Blur ", "blur"),
+        (" Vignette ", "vignette"),
+    ] {
+        effects.append_with_str_1(label)?;
+        button(document, &effects, Some(id), "Off")?;
+    }
+
+    snapshot_hint(document, root)
+}
@@ -0,0 +1,70 @@
+//! Renders an example with the software rasterizer and saves every frame
+//! as PNG, or all of them as one animated PNG.
+//!
+//! ```sh
+//! cargo run -p headless --bin render -- --example raining-rectangles --frames 120 --seed 7 --out frames/
+//! ```
+
+use headless::{file_name, run_example, Args, Frame, FPS};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{env, fs};
+use webgl_common::capture::{encode_apng, encode_png};
+
+const USAGE: &str = "usage: render --example NAME [--frames N] [--seed N] [--out DIR] [--animated]";
+
+fn write(path: &Path, bytes: Result<Vec<u8>, png::EncodingError>) -> Result<(), String> {
+    let bytes = bytes.map_err(|error| format!("{}: {error}", path.display()))?;
+    fs::write(path, bytes).map_err(|error| format!("{}: {error}", path.display()))?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse(env::args().skip(1), &["animated"])?;
+    let example = args.option("example").ok_or(USAGE)?;
+    let frames = args.parsed("frames", 1)?;
+    let seed = args.parsed("seed", 0)?;
+    let animated = args.flag("animated");
+    let out = PathBuf::from(args.option("out").unwrap_or("."));
+    fs::create_dir_all(&out).map_err(|error| format!("{}: {error}", out.display()))?;
+
+    let mut result = Ok(());
+    let mut rendered: Vec<Frame> = Vec::new();
+    run_example(example, seed, frames, &mut |index, frame| {
+        if animated {
+            rendered.push(frame);
+            return;
+        }
+        for (canvas, (width, height, pixels)) in frame.iter().enumerate() {
+            let path = out.join(file_name(example, canvas, frame.len(), Some(index)));
+            if result.is_ok() {
+                result = write(&path, encode_png(*width, *height, pixels));
+            }
+        }
+    })?;
+    result?;
+
+    // Frames of every canvas go into an animation of their own.
+    let canvases = rendered.first().map_or(0, |frame| frame.len());
+    for canvas in 0..canvases {
+        let (width, height, _) = rendered[0][canvas];
+        let pixels: Vec<&[u8]> = rendered
+            .iter()
+            .map(|frame| frame[canvas].2.as_slice())
+            .collect();
+        let path = out.join(file_name(example, canvas, canvases, None));
+        write(&path, encode_apng(width, height, &pixels, FPS))?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Replays a trace with the software rasterizer and saves the drawing
+//! buffer of every traced context as PNG.
+//!
+//! ```sh
+//! cargo run -p headless --bin replay -- scissor-animation.trace --frames 30 --out shots/
+//! ```
+
+use headless::{save_png, Args};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+use webgl_common::trace::replay::replay_software;
+use webgl_common::trace::Trace;
+
+fn run() -> Result<(), String> {
+    let args = Args::parse(env::args().skip(1), &[])?;
+    let [path] = args.positional.as_slice() else {
+        return Err("usage: replay <trace> [--frames N] [--out DIR]".to_string());
+    };
+
+    let bytes = fs::read(path).map_err(|error| format!("{path}: {error}"))?;
+    let trace = Trace::decode(&bytes).map_err(|error| format!("{path}: {error}"))?;
+    let frames = args.parsed("frames", trace.frames.len())?;
+    let out = PathBuf::from(args.option("out").unwrap_or("."));
+    fs::create_dir_all(&out).map_err(|error| format!("{}: {error}", out.display()))?;
+
+    let replay = replay_software(&trace, frames);
+    for (name, count) in &replay.skipped {
+        eprintln!("skipped {count} {name} call(s), not supported by the software rasterizer");
+    }
+    for (index, gl) in replay.contexts.iter().enumerate() {
+        let path = out.join(format!("context-{}.png", index + 1));
+        save_png(gl, &path).map_err(|error| format!("{}: {error}", path.display()))?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
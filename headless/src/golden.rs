@@ -0,0 +1,206 @@
+//! Comparison of rendered frames with checked-in golden images.
+//!
+//! Setting [`BLESS_VAR`] replaces the golden images with the rendered
+//! frames instead of comparing them.
+
+use std::path::Path;
+use std::{env, fs};
+use webgl_common::capture::encode_png;
+
+/// Environment variable which blesses the rendered frames as new golden
+/// images when set to anything but `0`.
+pub const BLESS_VAR: &str = "BLESS_GOLDENS";
+
+/// A top-down RGBA image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn decode(png_bytes: &[u8]) -> Result<Image, String> {
+        let mut decoder = png::Decoder::new(png_bytes);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().map_err(|error| error.to_string())?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut pixels)
+            .map_err(|error| error.to_string())?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(format!(
+                "expected 8 bit RGBA, found {:?} {:?}",
+                info.bit_depth, info.color_type
+            ));
+        }
+        pixels.truncate(info.buffer_size());
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+}
+
+/// How far a rendered image is from the golden one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Difference {
+    /// Pixels with a channel differing by more than the tolerance.
+    pub pixels: usize,
+    /// Largest difference of a channel.
+    pub max_channel: u8,
+}
+
+/// Compares images of the same size channel by channel, differences up to
+/// `tolerance` are accepted.
+pub fn compare(expected: &Image, actual: &Image, tolerance: u8) -> Difference {
+    assert_eq!(
+        (expected.width, expected.height),
+        (actual.width, actual.height)
+    );
+    let mut difference = Difference {
+        pixels: 0,
+        max_channel: 0,
+    };
+    for (expected, actual) in expected.pixels.chunks(4).zip(actual.pixels.chunks(4)) {
+        let max_channel = channel_differences(expected, actual).max().unwrap_or(0);
+        difference.max_channel = difference.max_channel.max(max_channel);
+        if max_channel > tolerance {
+            difference.pixels += 1;
+        }
+    }
+    difference
+}
+
+fn channel_differences<'a>(expected: &'a [u8], actual: &'a [u8]) -> impl Iterator<Item = u8> + 'a {
+    expected
+        .iter()
+        .zip(actual)
+        .map(|(expected, actual)| expected.abs_diff(*actual))
+}
+
+/// An image of the same size showing differing pixels in red over a faded
+/// version of the expected image.
+pub fn diff_image(expected: &Image, actual: &Image, tolerance: u8) -> Image {
+    let pixels = expected
+        .pixels
+        .chunks(4)
+        .zip(actual.pixels.chunks(4))
+        .flat_map(|(expected, actual)| {
+            if channel_differences(expected, actual).any(|difference| difference > tolerance) {
+                [255, 0, 0, 255]
+            } else {
+                let gray = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+                let faded = (gray / 4 + 192) as u8;
+                [faded, faded, faded, 255]
+            }
+        })
+        .collect();
+    Image {
+        width: expected.width,
+        height: expected.height,
+        pixels,
+    }
+}
+
+pub fn is_blessing() -> bool {
+    env::var(BLESS_VAR).is_ok_and(|value| value != "0")
+}
+
+fn save(image: &Image, path: &Path) -> Result<(), String> {
+    let png_bytes = encode_png(image.width, image.height, &image.pixels)
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    fs::write(path, png_bytes).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// Compares `actual` with the golden image `golden_dir/name`, or replaces
+/// the golden image when blessing. On a mismatch the actual image and a
+/// diff image are written to `diff_dir`.
+pub fn check(
+    name: &str,
+    actual: &Image,
+    golden_dir: &Path,
+    diff_dir: &Path,
+    tolerance: u8,
+) -> Result<(), String> {
+    let golden_path = golden_dir.join(name);
+    if is_blessing() {
+        fs::create_dir_all(golden_dir).map_err(|error| error.to_string())?;
+        return save(actual, &golden_path);
+    }
+
+    let png_bytes = fs::read(&golden_path).map_err(|error| {
+        format!(
+            "{}: {error}, run with {BLESS_VAR}=1 to create it",
+            golden_path.display()
+        )
+    })?;
+    let expected = Image::decode(&png_bytes)?;
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!(
+            "{name}: expected {}x{} pixels, rendered {}x{}",
+            expected.width, expected.height, actual.width, actual.height
+        ));
+    }
+
+    let difference = compare(&expected, actual, tolerance);
+    if difference.pixels == 0 {
+        return Ok(());
+    }
+    fs::create_dir_all(diff_dir).map_err(|error| error.to_string())?;
+    let stem = name.trim_end_matches(".png");
+    let actual_path = diff_dir.join(format!("{stem}-actual.png"));
+    let diff_path = diff_dir.join(format!("{stem}-diff.png"));
+    save(actual, &actual_path)?;
+    save(&diff_image(&expected, actual, tolerance), &diff_path)?;
+    Err(format!(
+        "{name}: {} pixels differ by up to {}, see {} and {}",
+        difference.pixels,
+        difference.max_channel,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> Image {
+        Image {
+            width: pixels.len() as u32,
+            height: 1,
+            pixels: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn differences_within_tolerance_pass() {
+        let expected = image(&[[10, 20, 30, 255], [0, 0, 0, 0]]);
+        let actual = image(&[[12, 18, 30, 255], [0, 0, 0, 0]]);
+        assert_eq!(
+            compare(&expected, &actual, 2),
+            Difference {
+                pixels: 0,
+                max_channel: 2
+            }
+        );
+        assert_eq!(compare(&expected, &actual, 1).pixels, 1);
+    }
+
+    #[test]
+    fn diff_marks_differing_pixels_red() {
+        let expected = image(&[[0, 0, 0, 255], [255, 255, 255, 255]]);
+        let actual = image(&[[0, 0, 0, 255], [255, 255, 255, 0]]);
+        let diff = diff_image(&expected, &actual, 0);
+        assert_eq!(diff.pixels, [192, 192, 192, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_round_trips() {
+        let original = image(&[[1, 2, 3, 4], [5, 6, 7, 8]]);
+        let png_bytes = encode_png(2, 1, &original.pixels).unwrap();
+        assert_eq!(Image::decode(&png_bytes), Ok(original));
+    }
+}
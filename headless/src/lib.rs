@@ -0,0 +1,252 @@
+pub mod golden;
+
+use std::path::Path;
+use std::{fs, io};
+use webgl_common::capture::encode_png;
+use webgl_common::{ExampleInfo, Rng, SoftwareContext, SoftwareExample};
+
+/// Frames per simulated second.
+pub const FPS: u16 = 60;
+
+/// Top-down RGBA pixels of every canvas of an example after a frame.
+pub type Frame = Vec<(u32, u32, Vec<u8>)>;
+
+type Runner = fn(u64, usize, &mut dyn FnMut(usize, Frame));
+
+/// Examples which run natively, with the function running them.
+pub const EXAMPLES: &[(ExampleInfo, Runner)] = &[
+    (
+        clearing_with_colors::INFO,
+        run::<clearing_with_colors::Scene<SoftwareContext>>,
+    ),
+    (
+        clearing_by_clicking::INFO,
+        run::<clearing_by_clicking::Scene<SoftwareContext>>,
+    ),
+    (
+        simple_color_animation::INFO,
+        run::<simple_color_animation::Scene<SoftwareContext>>,
+    ),
+    (
+        color_masking::INFO,
+        run::<color_masking::Scene<SoftwareContext>>,
+    ),
+    (
+        basic_scissoring::INFO,
+        run::<basic_scissoring::Scene<SoftwareContext>>,
+    ),
+    (
+        canvas_size_and_webgl::INFO,
+        run::<canvas_size_and_webgl::Scene<SoftwareContext>>,
+    ),
+    (
+        scissor_animation::INFO,
+        run::<scissor_animation::Scene<SoftwareContext>>,
+    ),
+    (
+        raining_rectangles::INFO,
+        run::<raining_rectangles::Scene<SoftwareContext>>,
+    ),
+];
+
+/// Runs `E` seeded with `seed` for `frames` frames of `1 / FPS` seconds,
+/// passing the index and the drawing buffers of every frame to
+/// `on_frame`. Like in the browser, the first frame is rendered right after
+/// the example is set up and every frame starts from a cleared drawing
+/// buffer.
+pub fn run<E: SoftwareExample>(seed: u64, frames: usize, on_frame: &mut dyn FnMut(usize, Frame)) {
+    let contexts: Vec<SoftwareContext> = E::CANVAS_SIZES
+        .iter()
+        .map(|&[width, height]| SoftwareContext::new(width, height))
+        .collect();
+    let mut example = E::new(contexts, Rng::new(seed));
+    for index in 0..frames {
+        if index > 0 {
+            example.update(1.0 / FPS as f64);
+        }
+        example.render();
+        let frame = example
+            .contexts()
+            .into_iter()
+            .map(|gl| {
+                let pixels = gl.read_drawing_buffer();
+                gl.present();
+                pixels
+            })
+            .collect();
+        on_frame(index, frame);
+    }
+}
+
+/// Runs the example named `name`, see [`run`].
+pub fn run_example(
+    name: &str,
+    seed: u64,
+    frames: usize,
+    on_frame: &mut dyn FnMut(usize, Frame),
+) -> Result<(), String> {
+    let (_, runner) = EXAMPLES
+        .iter()
+        .find(|(info, _)| info.name == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = EXAMPLES.iter().map(|(info, _)| info.name).collect();
+            format!(
+                "unknown example {name}, expected one of {}",
+                names.join(", ")
+            )
+        })?;
+    runner(seed, frames, on_frame);
+    Ok(())
+}
+
+/// Name of the PNG file of a frame, `{example}-{frame}.png` with the
+/// 0-based index of the canvas in between when there are several, and
+/// without the frame for animations of all frames. Shared by the `render`
+/// binary and the golden images so their files can be compared.
+pub fn file_name(example: &str, canvas: usize, canvases: usize, frame: Option<usize>) -> String {
+    let mut name = example.to_string();
+    if canvases > 1 {
+        name += &format!("-{canvas}");
+    }
+    if let Some(frame) = frame {
+        name += &format!("-{frame:04}");
+    }
+    name + ".png"
+}
+
+/// Command line options of the form `--name value`, or just `--name` for
+/// flags, and positional arguments.
+pub struct Args {
+    pub positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    /// Parses `args`, where the options named in `flags` take no value.
+    pub fn parse(args: impl IntoIterator<Item = String>, flags: &[&str]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flags.contains(&name) => {
+                    options.push((name.to_string(), "true".to_string()));
+                }
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("missing value of --{name}"))?;
+                    options.push((name.to_string(), value));
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.option(name).is_some()
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of `--name` parsed as `T`, `default` if it is missing.
+    pub fn parsed<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.option(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("invalid value of --{name}: {value}")),
+            None => Ok(default),
+        }
+    }
+}
+
+/// Writes the drawing buffer of `gl` to `path` as PNG.
+pub fn save_png(gl: &SoftwareContext, path: &Path) -> io::Result<()> {
+    let (width, height, pixels) = gl.read_drawing_buffer();
+    let png_bytes = encode_png(width, height, &pixels).map_err(io::Error::other)?;
+    fs::write(path, png_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()), &["animated"])
+    }
+
+    #[test]
+    fn options_follow_their_name() {
+        let args = args(&[
+            "trace.json",
+            "--frames",
+            "3",
+            "--animated",
+            "--out",
+            "shots",
+        ])
+        .unwrap();
+        assert_eq!(args.positional, ["trace.json"]);
+        assert!(args.flag("animated"));
+        assert!(!args.flag("seed"));
+        assert_eq!(args.option("out"), Some("shots"));
+        assert_eq!(args.parsed("frames", 0), Ok(3));
+        assert_eq!(args.parsed("seed", 7), Ok(7));
+    }
+
+    #[test]
+    fn file_names_number_canvases_from_zero() {
+        assert_eq!(
+            file_name("raining-rectangles", 0, 1, Some(60)),
+            "raining-rectangles-0060.png"
+        );
+        assert_eq!(
+            file_name("canvas-size-and-webgl", 1, 2, Some(0)),
+            "canvas-size-and-webgl-1-0000.png"
+        );
+        assert_eq!(
+            file_name("canvas-size-and-webgl", 0, 2, None),
+            "canvas-size-and-webgl-0.png"
+        );
+    }
+
+    #[test]
+    fn runs_are_reproducible() {
+        let render = |seed| {
+            let mut frames = Vec::new();
+            run_example("raining-rectangles", seed, 30, &mut |_, frame| {
+                frames.push(frame)
+            })
+            .unwrap();
+            frames
+        };
+        let frames = render(7);
+        assert_eq!(frames.len(), 30);
+        assert!(frames == render(7));
+        assert!(frames != render(8));
+    }
+
+    #[test]
+    fn unknown_example_is_an_error() {
+        assert!(run_example("hello-glsl", 0, 1, &mut |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn invalid_options_are_errors() {
+        assert!(args(&["--frames"]).is_err());
+        assert!(args(&["--frames", "many"])
+            .unwrap()
+            .parsed("frames", 0)
+            .is_err());
+    }
+}
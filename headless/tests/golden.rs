@@ -0,0 +1,114 @@
+//! Renders the examples natively and compares selected frames with the
+//! images in `tests/golden`. Run with `BLESS_GOLDENS=1` to update them.
+
+use headless::golden::{self, Image};
+use std::path::Path;
+
+// Rounding of the software rasterizer may change a little between
+// releases of the examples, actual regressions differ by far more.
+const TOLERANCE: u8 = 2;
+
+struct Case {
+    example: &'static str,
+    seed: u64,
+    frames: &'static [usize],
+}
+
+const CASES: &[Case] = &[
+    Case {
+        example: "clearing-with-colors",
+        seed: 0,
+        frames: &[0],
+    },
+    Case {
+        example: "clearing-by-clicking",
+        seed: 1,
+        frames: &[0],
+    },
+    Case {
+        example: "simple-color-animation",
+        seed: 2,
+        // Halfway through the transition to the second color, and after it.
+        frames: &[0, 75, 90],
+    },
+    Case {
+        example: "color-masking",
+        seed: 3,
+        frames: &[0, 90],
+    },
+    Case {
+        example: "basic-scissoring",
+        seed: 0,
+        frames: &[0],
+    },
+    Case {
+        example: "canvas-size-and-webgl",
+        seed: 0,
+        frames: &[0],
+    },
+    Case {
+        example: "scissor-animation",
+        seed: 4,
+        frames: &[0, 60, 119],
+    },
+    Case {
+        example: "raining-rectangles",
+        seed: 7,
+        frames: &[0, 60, 119],
+    },
+];
+
+fn check_case(case: &Case) -> Vec<String> {
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let diff_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden-diffs");
+    let last_frame = case.frames.iter().max().unwrap();
+
+    let mut failures = Vec::new();
+    headless::run_example(
+        case.example,
+        case.seed,
+        last_frame + 1,
+        &mut |index, frame| {
+            if !case.frames.contains(&index) {
+                return;
+            }
+            let canvases = frame.len();
+            for (canvas, (width, height, pixels)) in frame.into_iter().enumerate() {
+                let name = headless::file_name(case.example, canvas, canvases, Some(index));
+                let actual = Image {
+                    width,
+                    height,
+                    pixels,
+                };
+                if let Err(error) = golden::check(&name, &actual, &golden_dir, &diff_dir, TOLERANCE)
+                {
+                    failures.push(error);
+                }
+            }
+        },
+    )
+    .unwrap();
+    failures
+}
+
+#[test]
+fn examples_match_golden_images() {
+    let failures: Vec<String> = CASES.iter().flat_map(check_case).collect();
+    assert!(
+        failures.is_empty(),
+        "{} images differ:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn every_native_example_has_a_case() {
+    for (info, _) in headless::EXAMPLES {
+        assert!(
+            CASES.iter().any(|case| case.example == info.name),
+            "no golden images for {}",
+            info.name
+        );
+    }
+}
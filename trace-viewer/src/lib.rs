@@ -0,0 +1,154 @@
+use js_sys::Uint8Array;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+use wasm_bindgen::JsCast;
+use web_sys::{
+    console, Document, Element, FileReader, HtmlCanvasElement, HtmlElement, HtmlInputElement,
+    WebGl2RenderingContext, Window,
+};
+use webgl_common::trace::replay::replay_webgl;
+use webgl_common::trace::Trace;
+
+fn element(document: &Document, selector: &str) -> Element {
+    document.query_selector(selector).unwrap().unwrap()
+}
+
+// Lists the calls of every frame, each frame collapsed into a `details`
+// element.
+fn show_trace(document: &Document, trace: &Trace) -> Result<(), JsValue> {
+    let call_count: usize = trace.frames.iter().map(|frame| frame.calls.len()).sum();
+    element(document, "#summary").set_text_content(Some(&format!(
+        "{} frames, {} calls, {} contexts",
+        trace.frames.len(),
+        call_count,
+        trace.contexts.len()
+    )));
+    element(document, "#replay-frame").set_attribute("max", &trace.frames.len().to_string())?;
+
+    let main = element(document, "main");
+    main.replace_children_with_node_0();
+    for (index, frame) in trace.frames.iter().enumerate() {
+        let details = document.create_element("details")?;
+        let summary = document.create_element("summary")?;
+        summary.set_text_content(Some(&format!(
+            "Frame {} ({} calls)",
+            index + 1,
+            frame.calls.len()
+        )));
+        details.append_with_node_1(&summary)?;
+
+        let list = document.create_element("ol")?;
+        for call in &frame.calls {
+            let item = document.create_element("li")?;
+            // The context only matters when there are several.
+            let text = if trace.contexts.len() > 1 {
+                format!("[{}] {call}", call.context)
+            } else {
+                call.to_string()
+            };
+            item.set_text_content(Some(&text));
+            list.append_with_node_1(&item)?;
+        }
+        details.append_with_node_1(&list)?;
+        main.append_with_node_1(&details)?;
+    }
+
+    Ok(())
+}
+
+// Replays the first `frames` frames on fresh canvases of the traced sizes.
+fn replay(document: &Document, trace: &Trace, frames: usize) -> Result<(), JsValue> {
+    let canvases = element(document, "#canvases");
+    canvases.replace_children_with_node_0();
+
+    let mut contexts = Vec::new();
+    for &[width, height] in &trace.contexts {
+        let canvas = document
+            .create_element("canvas")?
+            .unchecked_into::<HtmlCanvasElement>();
+        canvas.set_width(width);
+        canvas.set_height(height);
+        canvases.append_with_node_1(&canvas)?;
+        let gl = canvas
+            .get_context("webgl2")?
+            .ok_or("WebGL2 is not available")?
+            .unchecked_into::<WebGl2RenderingContext>();
+        contexts.push(gl);
+    }
+
+    let skipped = replay_webgl(trace, &contexts, frames);
+    let text = if skipped.is_empty() {
+        "Every call was replayed.".to_string()
+    } else {
+        let calls: Vec<String> = skipped
+            .iter()
+            .map(|(name, count)| format!("{name} ×{count}"))
+            .collect();
+        format!(
+            "Skipped calls, which failed or use objects created before the trace: {}",
+            calls.join(", ")
+        )
+    };
+    element(document, "#skipped").set_text_content(Some(&text));
+
+    Ok(())
+}
+
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let window: Window = web_sys::window().unwrap();
+    let document: Document = window.document().unwrap();
+
+    let trace: Rc<RefCell<Option<Trace>>> = Rc::new(RefCell::new(None));
+
+    let reader = FileReader::new()?;
+    let document2 = document.clone();
+    let reader2 = reader.clone();
+    let trace2 = Rc::clone(&trace);
+    let load_closure = Closure::<dyn Fn()>::new(move || {
+        let bytes = Uint8Array::new(&reader2.result().unwrap()).to_vec();
+        match Trace::decode(&bytes) {
+            Ok(decoded) => {
+                if let Err(error) = show_trace(&document2, &decoded) {
+                    console::error_1(&error);
+                }
+                trace2.replace(Some(decoded));
+            }
+            Err(error) => {
+                element(&document2, "#summary").set_text_content(Some(&error.to_string()));
+                trace2.take();
+            }
+        }
+    });
+    reader.set_onload(Some(load_closure.as_ref().unchecked_ref()));
+    load_closure.forget();
+
+    let input = element(&document, "#trace-file").unchecked_into::<HtmlInputElement>();
+    let input2 = input.clone();
+    let change_closure = Closure::<dyn Fn()>::new(move || {
+        if let Some(file) = input2.files().and_then(|files| files.get(0)) {
+            drop(reader.read_as_array_buffer(&file));
+        }
+    });
+    input.set_onchange(Some(change_closure.as_ref().unchecked_ref()));
+    change_closure.forget();
+
+    let frame_input = element(&document, "#replay-frame").unchecked_into::<HtmlInputElement>();
+    let document2 = document.clone();
+    let replay_closure = Closure::<dyn Fn()>::new(move || {
+        if let Some(trace) = trace.borrow().as_ref() {
+            let frames = frame_input.value_as_number().max(1.0) as usize;
+            if let Err(error) = replay(&document2, trace, frames) {
+                console::error_1(&error);
+            }
+        }
+    });
+    element(&document, "#replay")
+        .unchecked_into::<HtmlElement>()
+        .set_onclick(Some(replay_closure.as_ref().unchecked_ref()));
+    replay_closure.forget();
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Derives `webgl_common::vertex::Vertex` for a `#[repr(C)]` struct with
+/// named fields. Every field becomes one vertex attribute, bound by the
+/// field name unless overridden with `#[vertex(name = "...")]`. Integer
+/// fields can be marked `#[vertex(normalized)]`.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !has_repr_c(&input) {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "#[derive(Vertex)] requires #[repr(C)] so that field offsets are stable",
+        ));
+    }
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "#[derive(Vertex)] does not support generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "#[derive(Vertex)] requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "#[derive(Vertex)] can only be used on structs",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let mut attributes = Vec::new();
+    let mut field_types = Vec::new();
+
+    for field in fields {
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let mut name = field_ident.to_string();
+        let mut normalized = false;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("vertex"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("normalized") {
+                    normalized = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `name = \"...\"` or `normalized`"))
+                }
+            })?;
+        }
+
+        attributes.push(quote! {
+            ::webgl_common::vertex::VertexAttribute {
+                name: #name,
+                components: <#ty as ::webgl_common::vertex::AttributeFormat>::COMPONENTS,
+                data_type: <#ty as ::webgl_common::vertex::AttributeFormat>::DATA_TYPE,
+                normalized: #normalized,
+                offset: ::core::mem::offset_of!(#ident, #field_ident) as i32,
+            }
+        });
+        field_types.push(ty);
+    }
+
+    Ok(quote! {
+        // Byte views of the vertex data are only sound when the struct has no
+        // padding, so refuse to compile layouts where the fields do not add
+        // up to the size of the struct.
+        const _: () = ::core::assert!(
+            0 #(+ ::core::mem::size_of::<#field_types>())* == ::core::mem::size_of::<#ident>(),
+            "#[derive(Vertex)] structs must not contain padding",
+        );
+
+        unsafe impl ::webgl_common::vertex::Vertex for #ident {
+            const LAYOUT: ::webgl_common::vertex::VertexLayout =
+                ::webgl_common::vertex::VertexLayout {
+                    attributes: &[#(#attributes),*],
+                    stride: ::core::mem::size_of::<#ident>() as i32,
+                };
+        }
+    })
+}
+
+fn has_repr_c(input: &DeriveInput) -> bool {
+    input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .any(|attr| {
+            let mut repr_c = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("C") {
+                    repr_c = true;
+                }
+                Ok(())
+            });
+            repr_c
+        })
+}
@@ -0,0 +1,238 @@
+use crate::gl::Gl;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Blob, BlobPropertyBag, HtmlAnchorElement, KeyboardEvent, Url, WebGl2RenderingContext,
+};
+
+/// Key which saves a snapshot of the canvas, see
+/// [`Setup::bind_snapshot`](crate::example::Setup::bind_snapshot).
+pub const SNAPSHOT_KEY: &str = "s";
+
+// Time the browser gets to start a download before its object URL is
+// revoked. Revoking right after the click cancels it in some browsers.
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+/// Reverses the order of the rows of a tightly packed RGBA image in place.
+///
+/// `readPixels` returns rows bottom to top, image files store them top to
+/// bottom.
+pub fn flip_rows(pixels: &mut [u8], width: usize, height: usize) {
+    let row_length = width * 4;
+    assert_eq!(
+        pixels.len(),
+        row_length * height,
+        "pixel data does not match a {width}x{height} RGBA image"
+    );
+    for row in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - 1 - row) * row_length);
+        top[row * row_length..(row + 1) * row_length].swap_with_slice(&mut bottom[..row_length]);
+    }
+}
+
+/// Encodes a top-down, tightly packed RGBA image as PNG.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(png_bytes)
+}
+
+/// Encodes top-down RGBA `frames` of the same size as an animated PNG
+/// playing `fps` frames per second in a loop.
+pub fn encode_apng<F: AsRef<[u8]>>(
+    width: u32,
+    height: u32,
+    frames: &[F],
+    fps: u16,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(1, fps)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.as_ref())?;
+    }
+    writer.finish()?;
+    Ok(png_bytes)
+}
+
+/// Reads the whole drawing buffer as a top-down RGBA image.
+///
+/// Unless the context was created with `preserveDrawingBuffer`, this has
+/// to be called in the same task which rendered the frame, as the drawing
+/// buffer is cleared once it is presented.
+pub fn read_drawing_buffer(gl: &WebGl2RenderingContext) -> Result<(u32, u32, Vec<u8>), JsValue> {
+    let gl = Gl::from_ref(gl);
+    let width = gl.drawing_buffer_width();
+    let height = gl.drawing_buffer_height();
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+    gl.read_pixels_with_opt_u8_array(
+        0,
+        0,
+        width,
+        height,
+        WebGl2RenderingContext::RGBA,
+        WebGl2RenderingContext::UNSIGNED_BYTE,
+        Some(&mut pixels),
+    )?;
+    flip_rows(&mut pixels, width as usize, height as usize);
+    Ok((width as u32, height as u32, pixels))
+}
+
+/// Reads the drawing buffer and offers it as a PNG file download.
+pub fn save_png(gl: &WebGl2RenderingContext, file_name: &str) -> Result<(), JsValue> {
+    let (width, height, pixels) = read_drawing_buffer(gl)?;
+    let png_bytes = encode_png(width, height, &pixels)
+        .map_err(|error| JsValue::from_str(&error.to_string()))?;
+    download(&png_bytes, "image/png", file_name)
+}
+
+/// Offers `bytes` as a file download.
+pub fn download(bytes: &[u8], mime_type: &str, file_name: &str) -> Result<(), JsValue> {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: HtmlAnchorElement = document
+        .create_element("a")?
+        .unchecked_into::<HtmlAnchorElement>();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    let revoke = Closure::once_into_js(move || drop(Url::revoke_object_url(&url)));
+    window.set_timeout_with_callback_and_timeout_and_arguments_0(
+        revoke.unchecked_ref(),
+        REVOKE_DELAY_MS,
+    )?;
+    Ok(())
+}
+
+/// Whether `event` is a plain, non-repeated press of [`SNAPSHOT_KEY`].
+pub fn is_snapshot_key(event: &KeyboardEvent) -> bool {
+    event.key() == SNAPSHOT_KEY
+        && !event.repeat()
+        && !event.ctrl_key()
+        && !event.meta_key()
+        && !event.alt_key()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic_image(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|pixel| {
+                let (x, y) = (pixel % width, pixel / width);
+                [x as u8 * 10, y as u8 * 20, (x + y) as u8, 255 - pixel as u8]
+            })
+            .collect()
+    }
+
+    fn pixel(pixels: &[u8], width: usize, x: usize, y: usize) -> &[u8] {
+        let start = (y * width + x) * 4;
+        &pixels[start..start + 4]
+    }
+
+    #[test]
+    fn flip_rows_reverses_row_order() {
+        let (width, height) = (3, 4);
+        let original = synthetic_image(width, height);
+        let mut flipped = original.clone();
+        flip_rows(&mut flipped, width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(
+                    pixel(&flipped, width, x, y),
+                    pixel(&original, width, x, height - 1 - y)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn flip_rows_keeps_middle_row_of_odd_height() {
+        let (width, height) = (2, 5);
+        let original = synthetic_image(width, height);
+        let mut flipped = original.clone();
+        flip_rows(&mut flipped, width, height);
+
+        assert_eq!(pixel(&flipped, width, 0, 2), pixel(&original, width, 0, 2));
+        assert_eq!(pixel(&flipped, width, 1, 2), pixel(&original, width, 1, 2));
+    }
+
+    #[test]
+    fn flip_rows_twice_is_identity() {
+        let (width, height) = (5, 6);
+        let original = synthetic_image(width, height);
+        let mut pixels = original.clone();
+        flip_rows(&mut pixels, width, height);
+        flip_rows(&mut pixels, width, height);
+        assert_eq!(pixels, original);
+    }
+
+    #[test]
+    #[should_panic]
+    fn flip_rows_rejects_mismatched_size() {
+        let mut pixels = vec![0; 10];
+        flip_rows(&mut pixels, 2, 2);
+    }
+
+    #[test]
+    fn encode_png_round_trips() {
+        let (width, height) = (7, 3);
+        let pixels = synthetic_image(width, height);
+        let png_bytes = encode_png(width as u32, height as u32, &pixels).unwrap();
+
+        assert_eq!(&png_bytes[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoder = png::Decoder::new(png_bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+
+        assert_eq!((info.width, info.height), (width as u32, height as u32));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(info.bit_depth, png::BitDepth::Eight);
+        assert_eq!(&decoded[..info.buffer_size()], pixels.as_slice());
+    }
+
+    #[test]
+    fn encode_png_rejects_short_data() {
+        assert!(encode_png(4, 4, &[0; 12]).is_err());
+    }
+
+    #[test]
+    fn encode_apng_keeps_every_frame() {
+        let (width, height) = (4, 2);
+        let frames = [synthetic_image(width, height), vec![7; width * height * 4]];
+        let png_bytes = encode_apng(width as u32, height as u32, &frames, 60).unwrap();
+
+        let decoder = png::Decoder::new(png_bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control().unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (2, 0));
+
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        for frame in &frames {
+            let info = reader.next_frame(&mut decoded).unwrap();
+            assert_eq!(&decoded[..info.buffer_size()], frame.as_slice());
+        }
+    }
+}
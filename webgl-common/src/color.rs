@@ -0,0 +1,454 @@
+use crate::random::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// An sRGB color with straight, not premultiplied, alpha, the way colors
+/// are passed to `clearColor` and written in CSS. Channels are in `[0, 1]`
+/// unless a conversion leaves the gamut, see [`Color::clamped`].
+///
+/// Hues of the cylindrical forms are in degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// Failure to parse a hex color.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid hex color: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Color = Color::rgba(0.0, 0.0, 0.0, 0.0);
+
+    /// An opaque color.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Color {
+        Color::rgba(r, g, b, 1.0)
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn with_alpha(self, a: f32) -> Color {
+        Color { a, ..self }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    pub fn rgb_array(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    /// The color with every channel clamped to `[0, 1]`.
+    pub fn clamped(self) -> Color {
+        let [r, g, b, a] = self.to_array().map(|channel| channel.clamp(0.0, 1.0));
+        Color { r, g, b, a }
+    }
+
+    /// Whether the color channels are within `[0, 1]`, allowing for
+    /// rounding errors of the conversions.
+    pub fn in_gamut(self) -> bool {
+        self.rgb_array()
+            .iter()
+            .all(|channel| (-1e-4..=1.0 + 1e-4).contains(channel))
+    }
+
+    /// Interpolates every channel, alpha included, in sRGB.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let [r, g, b, a] = lerp(self.to_array(), other.to_array(), t);
+        Color { r, g, b, a }
+    }
+
+    /// Linear RGB, proportional to the emitted light.
+    pub fn to_linear(self) -> [f32; 3] {
+        self.rgb_array().map(|c| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    }
+
+    pub fn from_linear(linear: [f32; 3]) -> Color {
+        let [r, g, b] = linear.map(|c| {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        });
+        Color::rgb(r, g, b)
+    }
+
+    // Hue in degrees, the largest and the smallest channel.
+    fn hue_and_range(self) -> (f32, f32, f32) {
+        let [r, g, b] = self.rgb_array();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let sector = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        };
+        (sector * 60.0, max, min)
+    }
+
+    // The color of `hue` with the given chroma, raised by `m` on every
+    // channel.
+    fn from_hue(hue: f32, chroma: f32, m: f32) -> Color {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let [r, g, b] = match sector as u32 {
+            0 => [chroma, x, 0.0],
+            1 => [x, chroma, 0.0],
+            2 => [0.0, chroma, x],
+            3 => [0.0, x, chroma],
+            4 => [x, 0.0, chroma],
+            _ => [chroma, 0.0, x],
+        };
+        Color::rgb(r + m, g + m, b + m)
+    }
+
+    /// Hue, saturation and lightness, grays have a hue of 0.
+    pub fn to_hsl(self) -> [f32; 3] {
+        let (hue, max, min) = self.hue_and_range();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        [hue, saturation, lightness]
+    }
+
+    pub fn from_hsl(hsl: [f32; 3]) -> Color {
+        let [hue, saturation, lightness] = hsl;
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Hue, saturation and value, grays have a hue of 0.
+    pub fn to_hsv(self) -> [f32; 3] {
+        let (hue, max, min) = self.hue_and_range();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        [hue, saturation, max]
+    }
+
+    pub fn from_hsv(hsv: [f32; 3]) -> Color {
+        let [hue, saturation, value] = hsv;
+        let chroma = value * saturation;
+        Color::from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Perceptual lightness and the green-red and blue-yellow axes, see
+    /// <https://bottosson.github.io/posts/oklab/>.
+    pub fn to_oklab(self) -> [f32; 3] {
+        let [r, g, b] = self.to_linear();
+        let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+        let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+        let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+        let [l, m, s] = [l.cbrt(), m.cbrt(), s.cbrt()];
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+
+    pub fn from_oklab(lab: [f32; 3]) -> Color {
+        let [lightness, a, b] = lab;
+        let l = lightness + 0.396_337_78 * a + 0.215_803_76 * b;
+        let m = lightness - 0.105_561_346 * a - 0.063_854_17 * b;
+        let s = lightness - 0.089_484_18 * a - 1.291_485_5 * b;
+        let [l, m, s] = [l * l * l, m * m * m, s * s * s];
+        Color::from_linear([
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        ])
+    }
+
+    /// OKLab as lightness, chroma and hue.
+    pub fn to_oklch(self) -> [f32; 3] {
+        let [lightness, a, b] = self.to_oklab();
+        let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+        [lightness, a.hypot(b), hue]
+    }
+
+    pub fn from_oklch(lch: [f32; 3]) -> Color {
+        let [lightness, chroma, hue] = lch;
+        let (sin, cos) = hue.to_radians().sin_cos();
+        Color::from_oklab([lightness, chroma * cos, chroma * sin])
+    }
+
+    /// Color and alpha as blended with `ONE, ONE_MINUS_SRC_ALPHA`, the form
+    /// a canvas with `premultipliedAlpha` expects in its drawing buffer.
+    pub fn premultiplied(self) -> [f32; 4] {
+        [self.r * self.a, self.g * self.a, self.b * self.a, self.a]
+    }
+
+    /// Undoes [`premultiplied`](Self::premultiplied), fully transparent
+    /// colors become transparent black.
+    pub fn from_premultiplied(rgba: [f32; 4]) -> Color {
+        let [r, g, b, a] = rgba;
+        if a == 0.0 {
+            return Color::TRANSPARENT;
+        }
+        Color::rgba(r / a, g / a, b / a, a)
+    }
+
+    /// Relative luminance as defined by WCAG, ignoring alpha.
+    pub fn relative_luminance(self) -> f32 {
+        let [r, g, b] = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG contrast ratio of the two colors, from 1 for equal luminance
+    /// to 21 for black on white.
+    pub fn contrast_ratio(self, other: Color) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` unless the color is opaque. Channels are
+    /// clamped.
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self.clamped().to_array().map(to_byte);
+        if a == 255 {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is
+    /// optional.
+    pub fn from_hex(text: &str) -> Result<Color, ParseColorError> {
+        let error = || ParseColorError(text.to_string());
+        let digits = text.strip_prefix('#').unwrap_or(text);
+        let nibbles: Vec<u8> = digits
+            .chars()
+            .map(|digit| digit.to_digit(16).map(|value| value as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(error)?;
+        let channels: Vec<u8> = match nibbles.len() {
+            3 | 4 => nibbles.iter().map(|nibble| nibble * 17).collect(),
+            6 | 8 => nibbles
+                .chunks(2)
+                .map(|pair| pair[0] * 16 + pair[1])
+                .collect(),
+            _ => return Err(error()),
+        };
+        let [r, g, b] = [0, 1, 2].map(|index| channels[index] as f32 / 255.0);
+        let a = channels.get(3).map_or(1.0, |&a| a as f32 / 255.0);
+        Ok(Color::rgba(r, g, b, a))
+    }
+
+    /// A random opaque color, uniformly distributed in sRGB.
+    pub fn random(rng: &mut Rng) -> Color {
+        Color::rgb(rng.next_f32(), rng.next_f32(), rng.next_f32())
+    }
+
+    /// `count` opaque colors of similar lightness and chroma, with hues
+    /// spread evenly in OKLab from a random start, so that they are about
+    /// equally easy to tell apart.
+    pub fn palette(rng: &mut Rng, count: usize) -> Vec<Color> {
+        // Successive hues a golden angle apart stay spread for any count.
+        const GOLDEN_ANGLE: f32 = 137.507_77;
+        let start = rng.next_f32() * 360.0;
+        (0..count)
+            .map(|index| {
+                let lightness = 0.65 + 0.1 * rng.next_f32();
+                let hue = start + GOLDEN_ANGLE * index as f32;
+                // Some hues do not reach the chroma at this lightness.
+                let mut chroma = 0.15;
+                let mut color = Color::from_oklch([lightness, chroma, hue]);
+                while !color.in_gamut() && chroma > 0.0 {
+                    chroma -= 0.01;
+                    color = Color::from_oklch([lightness, chroma.max(0.0), hue]);
+                }
+                color.clamped()
+            })
+            .collect()
+    }
+}
+
+fn to_byte(channel: f32) -> u8 {
+    (channel * 255.0).round() as u8
+}
+
+fn lerp<const N: usize>(from: [f32; N], to: [f32; N], t: f32) -> [f32; N] {
+    std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t)
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(text: &str) -> Result<Color, ParseColorError> {
+        Color::from_hex(text)
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from([r, g, b]: [f32; 3]) -> Color {
+        Color::rgb(r, g, b)
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from([r, g, b, a]: [f32; 4]) -> Color {
+        Color::rgba(r, g, b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Properties are checked for this many random colors.
+    const SAMPLES: usize = 1000;
+
+    fn random_colors() -> impl Iterator<Item = Color> {
+        let mut rng = Rng::new(43);
+        (0..SAMPLES).map(move |_| Color::random(&mut rng).with_alpha(rng.next_f32()))
+    }
+
+    fn assert_close(actual: Color, expected: Color, tolerance: f32) {
+        let close = actual
+            .to_array()
+            .iter()
+            .zip(expected.to_array())
+            .all(|(actual, expected)| (actual - expected).abs() <= tolerance);
+        assert!(close, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn linear_round_trips() {
+        for color in random_colors() {
+            let round_trip = Color::from_linear(color.to_linear()).with_alpha(color.a);
+            assert_close(round_trip, color, 1e-5);
+        }
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        for color in random_colors().chain([Color::BLACK, Color::WHITE]) {
+            let hsl = Color::from_hsl(color.to_hsl()).with_alpha(color.a);
+            assert_close(hsl, color, 1e-5);
+            let hsv = Color::from_hsv(color.to_hsv()).with_alpha(color.a);
+            assert_close(hsv, color, 1e-5);
+        }
+    }
+
+    #[test]
+    fn oklab_round_trips() {
+        for color in random_colors().chain([Color::BLACK, Color::WHITE]) {
+            let lab = Color::from_oklab(color.to_oklab()).with_alpha(color.a);
+            assert_close(lab, color, 1e-3);
+            let lch = Color::from_oklch(color.to_oklch()).with_alpha(color.a);
+            assert_close(lch, color, 1e-3);
+        }
+    }
+
+    #[test]
+    fn hex_round_trips_within_a_step() {
+        for color in random_colors() {
+            let parsed: Color = color.to_hex().parse().unwrap();
+            assert_close(parsed, color, 0.5 / 255.0 + 1e-6);
+            assert_eq!(parsed.to_hex(), color.to_hex());
+        }
+    }
+
+    #[test]
+    fn premultiplied_round_trips() {
+        for color in random_colors().filter(|color| color.a > 0.01) {
+            assert_close(
+                Color::from_premultiplied(color.premultiplied()),
+                color,
+                1e-4,
+            );
+        }
+        assert_eq!(Color::from_premultiplied([0.0; 4]), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn known_conversions() {
+        let orange = Color::rgb(1.0, 0.5, 0.0);
+        assert_eq!(orange.to_hex(), "#ff8000");
+        assert_eq!(orange.with_alpha(0.5).to_hex(), "#ff800080");
+        assert_eq!(orange.to_hsl(), [30.0, 1.0, 0.5]);
+        assert_eq!(orange.to_hsv(), [30.0, 1.0, 1.0]);
+        assert_close(
+            Color::from_hsl([240.0, 1.0, 0.5]),
+            Color::rgb(0.0, 0.0, 1.0),
+            1e-6,
+        );
+        let [lightness, a, b] = Color::WHITE.to_oklab();
+        assert!((lightness - 1.0).abs() < 1e-4 && a.abs() < 1e-4 && b.abs() < 1e-4);
+    }
+
+    #[test]
+    fn parses_hex_forms() {
+        assert_eq!(Color::from_hex("#fff"), Ok(Color::WHITE));
+        assert_eq!(Color::from_hex("000"), Ok(Color::BLACK));
+        assert_eq!(Color::from_hex("#0000"), Ok(Color::TRANSPARENT));
+        assert_eq!(Color::from_hex("#ff000080").unwrap().to_hex(), "#ff000080");
+        for invalid in ["", "#ff", "#12345", "#gggggg", "#ffé", "#+f+f+f"] {
+            assert!(Color::from_hex(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn contrast_ratio() {
+        assert!((Color::BLACK.contrast_ratio(Color::WHITE) - 21.0).abs() < 1e-4);
+        for color in random_colors() {
+            let ratio = color.contrast_ratio(Color::WHITE);
+            assert!((1.0..=21.0).contains(&ratio));
+            assert_eq!(ratio, Color::WHITE.contrast_ratio(color));
+            assert_eq!(color.contrast_ratio(color), 1.0);
+        }
+    }
+
+    #[test]
+    fn palette_colors_are_distinct_and_in_gamut() {
+        let mut rng = Rng::new(1);
+        let palette = Color::palette(&mut rng, 8);
+        assert_eq!(palette.len(), 8);
+        for (index, color) in palette.iter().enumerate() {
+            assert!(color.in_gamut() && color.a == 1.0);
+            let [lightness, ..] = color.to_oklab();
+            assert!((0.6..0.8).contains(&lightness), "{color:?}");
+            for other in &palette[index + 1..] {
+                let distance = color
+                    .to_oklab()
+                    .iter()
+                    .zip(other.to_oklab())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f32>()
+                    .sqrt();
+                assert!(distance > 0.05, "{color:?} and {other:?} are too close");
+            }
+        }
+        assert_eq!(palette, Color::palette(&mut Rng::new(1), 8));
+    }
+}
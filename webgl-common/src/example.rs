@@ -0,0 +1,396 @@
+use crate::capture;
+use crate::gl::Gl;
+use crate::hud::{FrameHud, HudKind};
+use crate::resize::CanvasResizer;
+use crate::resources::{self, ResourceCounts, ResourceKind};
+use crate::trace::{self, Trace};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    console, Event, EventTarget, HtmlCanvasElement, KeyboardEvent, WebGl2RenderingContext,
+};
+
+/// Metadata of an example crate, taken from its `Cargo.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExampleInfo {
+    /// Package name, also used as the route of the example in the gallery.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Article of the MDN "WebGL by example" series the example follows.
+    pub homepage: &'static str,
+}
+
+/// Declares `pub const INFO: ExampleInfo` filled in from the manifest of
+/// the crate invoking the macro.
+#[macro_export]
+macro_rules! example_info {
+    () => {
+        pub const INFO: $crate::example::ExampleInfo = $crate::example::ExampleInfo {
+            name: env!("CARGO_PKG_NAME"),
+            description: env!("CARGO_PKG_DESCRIPTION"),
+            homepage: env!("CARGO_PKG_HOMEPAGE"),
+        };
+    };
+}
+
+/// An example run by a [`Host`], which owns all event listeners and the
+/// animation loop, so that the example can be stopped at any time.
+pub trait Example {
+    /// Sets the example up, its elements are already in the document.
+    /// Events and other host services are requested through `setup`.
+    fn init(setup: &mut Setup) -> Result<Self, JsValue>
+    where
+        Self: Sized;
+
+    /// Advances the example by `dt` seconds. Only called for examples
+    /// which requested [`Setup::animate`].
+    fn update(&mut self, _dt: f64) {}
+
+    /// Draws the current state. Called after `init`, every event, every
+    /// resize and every animation frame.
+    fn render(&mut self);
+
+    /// The canvas passed to [`Setup::observe_resize`] got a drawing buffer
+    /// of `width` x `height` pixels. The viewport already covers it, a
+    /// [`StateCache`](crate::StateCache) has to be invalidated.
+    fn resize(&mut self, _width: u32, _height: u32) {}
+
+    /// Handles an event requested with [`Setup::listen`].
+    fn on_event(&mut self, _event: &Event) {}
+
+    /// Deletes every GL object the example created.
+    fn destroy(&mut self) {}
+}
+
+/// Services an [`Example`] asks its [`Host`] for while initializing.
+#[derive(Default)]
+pub struct Setup {
+    events: Vec<(EventTarget, &'static str)>,
+    snapshots: Vec<(WebGl2RenderingContext, String)>,
+    resize_canvas: Option<(HtmlCanvasElement, WebGl2RenderingContext)>,
+    max_pixels: Option<u32>,
+    animate: bool,
+}
+
+impl Setup {
+    /// Passes `event_type` events dispatched to `target` to
+    /// [`Example::on_event`].
+    pub fn listen(&mut self, target: &EventTarget, event_type: &'static str) {
+        self.events.push((target.clone(), event_type));
+    }
+
+    /// Saves the drawing buffer of `gl` as `file_name` when the snapshot
+    /// key is pressed, right after rendering the example again.
+    pub fn bind_snapshot(&mut self, gl: &WebGl2RenderingContext, file_name: &str) {
+        self.snapshots.push((gl.clone(), file_name.to_string()));
+    }
+
+    /// Keeps the drawing buffer of `canvas` the same size as the canvas
+    /// on the page in device pixels, and the viewport of `gl` covering
+    /// it, see [`Example::resize`].
+    pub fn observe_resize(&mut self, canvas: &HtmlCanvasElement, gl: &WebGl2RenderingContext) {
+        self.resize_canvas = Some((canvas.clone(), gl.clone()));
+    }
+
+    /// Scales the drawing buffer of the canvas passed to
+    /// [`observe_resize`](Self::observe_resize) down to at most
+    /// `max_pixels` pixels, trading sharpness for speed on large screens.
+    pub fn limit_pixels(&mut self, max_pixels: u32) {
+        self.max_pixels = Some(max_pixels);
+    }
+
+    /// Calls [`Example::update`] and [`Example::render`] on every
+    /// animation frame.
+    pub fn animate(&mut self) {
+        self.animate = true;
+    }
+}
+
+type SharedExample = Rc<RefCell<Box<dyn Example>>>;
+type FrameCallback = Closure<dyn FnMut(f64)>;
+
+// Every render ends a frame of the trace being recorded, if any.
+fn render_frame(example: &mut dyn Example) {
+    example.render();
+    trace::end_frame();
+}
+
+// Starts recording a trace, downloaded as `{name}.trace.json` when it is a
+// single frame and in the more compact binary form otherwise.
+fn start_trace(frames: usize, name: Rc<RefCell<String>>) {
+    trace::start(frames, move |trace: Trace| {
+        let name = name.borrow();
+        drop(if trace.frames.len() <= 1 {
+            capture::download(
+                trace.to_json().as_bytes(),
+                "application/json",
+                &format!("{name}.trace.json"),
+            )
+        } else {
+            capture::download(
+                &trace.to_binary(),
+                "application/octet-stream",
+                &format!("{name}.trace"),
+            )
+        });
+    });
+}
+
+struct Listener {
+    target: EventTarget,
+    event_type: &'static str,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+// The frame callback schedules itself again, so it has to be reachable
+// from within. Taking it out of the cell ends the loop.
+struct AnimationLoop {
+    callback: RefCell<Option<FrameCallback>>,
+    request_id: Cell<Option<i32>>,
+}
+
+impl AnimationLoop {
+    fn request_frame(&self) {
+        if let Some(callback) = self.callback.borrow().as_ref() {
+            let request_id = web_sys::window()
+                .unwrap()
+                .request_animation_frame(callback.as_ref().unchecked_ref());
+            self.request_id.set(request_id.ok());
+        }
+    }
+}
+
+/// Runs an [`Example`] until [`stop`](Self::stop) is called or the host is
+/// dropped.
+pub struct Host {
+    example: SharedExample,
+    listeners: Vec<Listener>,
+    animation: Option<Rc<AnimationLoop>>,
+    resizer: Option<CanvasResizer>,
+    // Live objects before the example was initialized.
+    resources_before: ResourceCounts,
+    stopped: bool,
+}
+
+impl Host {
+    pub fn start<E: Example + 'static>() -> Result<Host, JsValue> {
+        let resources_before = resources::live();
+
+        // Traces requested by the URL include the initialization, so that
+        // they can be replayed on their own.
+        let trace_name = Rc::new(RefCell::new(String::from("webgl")));
+        if let Some(frames) = trace::requested_frames() {
+            start_trace(frames, Rc::clone(&trace_name));
+        }
+
+        let mut setup = Setup::default();
+        let example: SharedExample = match E::init(&mut setup) {
+            Ok(example) => Rc::new(RefCell::new(Box::new(example))),
+            Err(error) => {
+                trace::finish();
+                return Err(error);
+            }
+        };
+        if let Some((_, file_name)) = setup.snapshots.first() {
+            let stem = file_name.trim_end_matches(".png").to_string();
+            trace_name.replace(stem);
+        }
+        render_frame(&mut **example.borrow_mut());
+
+        let mut host = Host {
+            example,
+            listeners: Vec::new(),
+            animation: None,
+            resizer: None,
+            resources_before,
+            stopped: false,
+        };
+
+        let window = web_sys::window().unwrap();
+
+        // The graphs go into the main canvas of the example.
+        let hud_gl = setup
+            .resize_canvas
+            .as_ref()
+            .map(|(_, gl)| gl)
+            .or(setup.snapshots.first().map(|(gl, _)| gl));
+        let hud = HudKind::requested()
+            .filter(|_| setup.animate)
+            .map(|kind| match (kind, hud_gl) {
+                (HudKind::Gl, Some(gl)) => FrameHud::gl(gl),
+                _ => FrameHud::dom(&window.document().unwrap()),
+            });
+
+        for (target, event_type) in setup.events {
+            let example = Rc::clone(&host.example);
+            host.add_listener(target, event_type, move |event: Event| {
+                let mut example = example.borrow_mut();
+                example.on_event(&event);
+                render_frame(&mut **example);
+            });
+        }
+
+        if !setup.snapshots.is_empty() {
+            let example = Rc::clone(&host.example);
+            let snapshots = setup.snapshots;
+            host.add_listener(window.into(), "keydown", move |event: Event| {
+                let event = event.unchecked_ref::<KeyboardEvent>();
+                if let Some(frames) = trace::trace_key_frames(event) {
+                    // The frame rendered right away is the first traced.
+                    start_trace(frames, Rc::clone(&trace_name));
+                    render_frame(&mut **example.borrow_mut());
+                    return;
+                }
+                if !capture::is_snapshot_key(event) {
+                    return;
+                }
+                // The drawing buffer is only valid in the task which
+                // rendered it.
+                render_frame(&mut **example.borrow_mut());
+                for (gl, file_name) in &snapshots {
+                    if let Err(error) = capture::save_png(gl, file_name) {
+                        console::error_1(&error);
+                    }
+                }
+            });
+        }
+
+        if let Some((canvas, gl)) = setup.resize_canvas {
+            let example = Rc::clone(&host.example);
+            let resizer = CanvasResizer::new(&canvas, setup.max_pixels, move |width, height| {
+                Gl::from_ref(&gl).viewport(0, 0, width as i32, height as i32);
+                let mut example = example.borrow_mut();
+                example.resize(width, height);
+                render_frame(&mut **example);
+            })?;
+            resources::created(ResourceKind::Listener);
+            host.resizer = Some(resizer);
+        }
+
+        if setup.animate {
+            host.start_animation(hud);
+        }
+
+        Ok(host)
+    }
+
+    fn add_listener<F>(&mut self, target: EventTarget, event_type: &'static str, handler: F)
+    where
+        F: FnMut(Event) + 'static,
+    {
+        let closure = Closure::<dyn FnMut(Event)>::new(handler);
+        drop(target.add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref()));
+        resources::created(ResourceKind::Listener);
+
+        self.listeners.push(Listener {
+            target,
+            event_type,
+            closure,
+        });
+    }
+
+    fn start_animation(&mut self, mut hud: Option<FrameHud>) {
+        let animation = Rc::new(AnimationLoop {
+            callback: RefCell::new(None),
+            request_id: Cell::new(None),
+        });
+
+        let example = Rc::clone(&self.example);
+        let animation2 = Rc::clone(&animation);
+        let mut last_timestamp: Option<f64> = None;
+        let performance = web_sys::window().unwrap().performance().unwrap();
+        let callback = FrameCallback::new(move |timestamp: f64| {
+            // The timestamp is in milliseconds, the first frame does not
+            // advance the example.
+            let dt = last_timestamp.map_or(0.0, |last| (timestamp - last) / 1000.0);
+            last_timestamp = Some(timestamp);
+            let start = performance.now();
+            {
+                let mut example = example.borrow_mut();
+                example.update(dt);
+                render_frame(&mut **example);
+            }
+            // Traces only contain the calls of the example.
+            if let Some(hud) = hud.as_mut().filter(|_| !trace::is_recording()) {
+                hud.record(timestamp, performance.now() - start);
+            }
+            animation2.request_frame();
+        });
+
+        animation.callback.replace(Some(callback));
+        resources::created(ResourceKind::Listener);
+        animation.request_frame();
+        self.animation = Some(animation);
+    }
+
+    /// Removes all listeners, ends the animation loop and destroys the
+    /// example. Returns the objects created while the example ran which
+    /// are still alive, empty if everything was released.
+    pub fn stop(mut self) -> Vec<(ResourceKind, usize)> {
+        self.tear_down();
+        resources::live().leaked_since(&self.resources_before)
+    }
+
+    /// Keeps the example running for the lifetime of the page, destroying
+    /// it when the page is unloaded.
+    pub fn keep_until_unload(self) {
+        let host = RefCell::new(Some(self));
+        let unload_closure = Closure::<dyn FnMut()>::new(move || {
+            if let Some(host) = host.take() {
+                let leaks = host.stop();
+                if cfg!(debug_assertions) && !leaks.is_empty() {
+                    console::warn_1(&resources::leak_report(&leaks).into());
+                }
+            }
+        });
+        drop(web_sys::window().unwrap().add_event_listener_with_callback(
+            "beforeunload",
+            unload_closure.as_ref().unchecked_ref(),
+        ));
+        unload_closure.forget();
+    }
+
+    fn tear_down(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.stopped = true;
+
+        for listener in self.listeners.drain(..) {
+            drop(listener.target.remove_event_listener_with_callback(
+                listener.event_type,
+                listener.closure.as_ref().unchecked_ref(),
+            ));
+            resources::released(ResourceKind::Listener);
+        }
+
+        if self.resizer.take().is_some() {
+            resources::released(ResourceKind::Listener);
+        }
+
+        if let Some(animation) = self.animation.take() {
+            if let Some(request_id) = animation.request_id.take() {
+                drop(
+                    web_sys::window()
+                        .unwrap()
+                        .cancel_animation_frame(request_id),
+                );
+            }
+            animation.callback.take();
+            resources::released(ResourceKind::Listener);
+        }
+
+        self.example.borrow_mut().destroy();
+        // Hands out what was recorded of a trace, the frames it still
+        // waits for would never come.
+        trace::finish();
+    }
+}
+
+impl Drop for Host {
+    fn drop(&mut self) {
+        self.tear_down();
+    }
+}
@@ -0,0 +1,289 @@
+// With the `debug-gl` feature every call made through a `Gl` is followed by
+// `getError`. Without it the methods forward to the context and compile to
+// the same code as calling it directly.
+
+use std::ops::Deref;
+#[cfg(feature = "debug-gl")]
+use std::{fmt, panic::Location};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer,
+    WebGlShader, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+/// A WebGL2 context which, with the `debug-gl` feature, reports every error
+/// raised by a call to the console, with the name and arguments of the
+/// call and the location of the Rust code making it.
+///
+/// The helpers of this crate taking a `WebGl2RenderingContext` make their
+/// calls through [`Gl::from_ref`], so they are checked as well and errors
+/// point at the helper. Methods not wrapped here, i.e. queries which raise
+/// no errors, are reached through `Deref`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Gl(WebGl2RenderingContext);
+
+impl Gl {
+    pub fn new(gl: WebGl2RenderingContext) -> Self {
+        Gl(gl)
+    }
+
+    /// Views a context as a `Gl` without cloning it.
+    pub fn from_ref(gl: &WebGl2RenderingContext) -> &Self {
+        gl.unchecked_ref()
+    }
+
+    #[cfg(feature = "debug-gl")]
+    #[track_caller]
+    fn check(&self, name: &str, args: &[&dyn fmt::Debug]) {
+        loop {
+            let error = self.0.get_error();
+            if error == WebGl2RenderingContext::NO_ERROR {
+                break;
+            }
+            let message = error_message(error, name, args, Location::caller());
+            web_sys::console::error_1(&message.into());
+            // A lost context keeps reporting the loss.
+            if error == WebGl2RenderingContext::CONTEXT_LOST_WEBGL {
+                break;
+            }
+        }
+    }
+}
+
+impl Deref for Gl {
+    type Target = WebGl2RenderingContext;
+
+    fn deref(&self) -> &WebGl2RenderingContext {
+        &self.0
+    }
+}
+
+impl From<WebGl2RenderingContext> for Gl {
+    fn from(gl: WebGl2RenderingContext) -> Self {
+        Gl(gl)
+    }
+}
+
+impl AsRef<JsValue> for Gl {
+    fn as_ref(&self) -> &JsValue {
+        self.0.as_ref()
+    }
+}
+
+impl AsRef<WebGl2RenderingContext> for Gl {
+    fn as_ref(&self) -> &WebGl2RenderingContext {
+        &self.0
+    }
+}
+
+impl From<Gl> for JsValue {
+    fn from(gl: Gl) -> Self {
+        gl.0.into()
+    }
+}
+
+impl JsCast for Gl {
+    fn instanceof(value: &JsValue) -> bool {
+        WebGl2RenderingContext::instanceof(value)
+    }
+
+    fn unchecked_from_js(value: JsValue) -> Self {
+        Gl(value.unchecked_into())
+    }
+
+    fn unchecked_from_js_ref(value: &JsValue) -> &Self {
+        // `Gl` and `WebGl2RenderingContext` are transparent wrappers of a
+        // `JsValue`.
+        unsafe { &*(value as *const JsValue as *const Gl) }
+    }
+}
+
+/// Name of an error returned by `getError`.
+pub fn error_name(error: u32) -> &'static str {
+    match error {
+        WebGl2RenderingContext::NO_ERROR => "NO_ERROR",
+        WebGl2RenderingContext::INVALID_ENUM => "INVALID_ENUM",
+        WebGl2RenderingContext::INVALID_VALUE => "INVALID_VALUE",
+        WebGl2RenderingContext::INVALID_OPERATION => "INVALID_OPERATION",
+        WebGl2RenderingContext::INVALID_FRAMEBUFFER_OPERATION => "INVALID_FRAMEBUFFER_OPERATION",
+        WebGl2RenderingContext::OUT_OF_MEMORY => "OUT_OF_MEMORY",
+        WebGl2RenderingContext::CONTEXT_LOST_WEBGL => "CONTEXT_LOST_WEBGL",
+        _ => "unknown error",
+    }
+}
+
+// Arguments such as vertex data are cut short.
+#[cfg(any(feature = "debug-gl", test))]
+fn error_message(
+    error: u32,
+    name: &str,
+    args: &[&dyn std::fmt::Debug],
+    location: &std::panic::Location,
+) -> String {
+    const MAX_ARG_LENGTH: usize = 40;
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| {
+            let arg = format!("{arg:?}");
+            match arg.char_indices().nth(MAX_ARG_LENGTH) {
+                Some((end, _)) => format!("{}…", &arg[..end]),
+                None => arg,
+            }
+        })
+        .collect();
+    format!(
+        "{} in {name}({}) at {location}",
+        error_name(error),
+        args.join(", ")
+    )
+}
+
+macro_rules! checked {
+    ($(fn $name:ident($($arg:ident: $type:ty),*) $(-> $result:ty)?;)*) => {
+        impl Gl {
+            $(
+                #[cfg_attr(feature = "debug-gl", track_caller)]
+                #[inline]
+                #[allow(clippy::too_many_arguments)]
+                pub fn $name(&self, $($arg: $type),*) $(-> $result)? {
+                    let result = self.0.$name($($arg),*);
+                    #[cfg(feature = "debug-gl")]
+                    self.check(stringify!($name), &[$(&$arg),*]);
+                    result
+                }
+            )*
+        }
+    };
+}
+
+checked! {
+    fn active_texture(texture: u32);
+    fn attach_shader(program: &WebGlProgram, shader: &WebGlShader);
+    fn bind_buffer(target: u32, buffer: Option<&WebGlBuffer>);
+    fn bind_framebuffer(target: u32, framebuffer: Option<&WebGlFramebuffer>);
+    fn bind_renderbuffer(target: u32, renderbuffer: Option<&WebGlRenderbuffer>);
+    fn bind_texture(target: u32, texture: Option<&WebGlTexture>);
+    fn bind_vertex_array(array: Option<&WebGlVertexArrayObject>);
+    fn blend_func(source: u32, destination: u32);
+    fn blend_func_separate(source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32);
+    fn buffer_data_with_i32(target: u32, size: i32, usage: u32);
+    fn buffer_data_with_u8_array(target: u32, data: &[u8], usage: u32);
+    fn buffer_sub_data_with_i32_and_u8_array(target: u32, offset: i32, data: &[u8]);
+    fn check_framebuffer_status(target: u32) -> u32;
+    fn clear(mask: u32);
+    fn clear_color(red: f32, green: f32, blue: f32, alpha: f32);
+    fn color_mask(red: bool, green: bool, blue: bool, alpha: bool);
+    fn compile_shader(shader: &WebGlShader);
+    fn create_buffer() -> Option<WebGlBuffer>;
+    fn create_framebuffer() -> Option<WebGlFramebuffer>;
+    fn create_program() -> Option<WebGlProgram>;
+    fn create_renderbuffer() -> Option<WebGlRenderbuffer>;
+    fn create_shader(shader_type: u32) -> Option<WebGlShader>;
+    fn create_texture() -> Option<WebGlTexture>;
+    fn create_vertex_array() -> Option<WebGlVertexArrayObject>;
+    fn delete_buffer(buffer: Option<&WebGlBuffer>);
+    fn delete_framebuffer(framebuffer: Option<&WebGlFramebuffer>);
+    fn delete_program(program: Option<&WebGlProgram>);
+    fn delete_renderbuffer(renderbuffer: Option<&WebGlRenderbuffer>);
+    fn delete_shader(shader: Option<&WebGlShader>);
+    fn delete_texture(texture: Option<&WebGlTexture>);
+    fn delete_vertex_array(array: Option<&WebGlVertexArrayObject>);
+    fn detach_shader(program: &WebGlProgram, shader: &WebGlShader);
+    fn disable(capability: u32);
+    fn draw_arrays(mode: u32, first: i32, count: i32);
+    fn draw_arrays_instanced(mode: u32, first: i32, count: i32, instance_count: i32);
+    fn enable(capability: u32);
+    fn enable_vertex_attrib_array(index: u32);
+    fn framebuffer_renderbuffer(target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<&WebGlRenderbuffer>);
+    fn framebuffer_texture_2d(target: u32, attachment: u32, texture_target: u32, texture: Option<&WebGlTexture>, level: i32);
+    fn generate_mipmap(target: u32);
+    fn get_attrib_location(program: &WebGlProgram, name: &str) -> i32;
+    fn get_uniform_location(program: &WebGlProgram, name: &str) -> Option<WebGlUniformLocation>;
+    fn get_parameter(name: u32) -> Result<JsValue, JsValue>;
+    fn get_program_info_log(program: &WebGlProgram) -> Option<String>;
+    fn get_program_parameter(program: &WebGlProgram, name: u32) -> JsValue;
+    fn get_shader_info_log(shader: &WebGlShader) -> Option<String>;
+    fn get_shader_parameter(shader: &WebGlShader, name: u32) -> JsValue;
+    fn is_enabled(capability: u32) -> bool;
+    fn link_program(program: &WebGlProgram);
+    fn pixel_storei(name: u32, value: i32);
+    fn renderbuffer_storage(target: u32, internal_format: u32, width: i32, height: i32);
+    fn scissor(x: i32, y: i32, width: i32, height: i32);
+    fn shader_source(shader: &WebGlShader, source: &str);
+    fn tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(target: u32, level: i32, internal_format: i32, width: i32, height: i32, border: i32, format: u32, type_: u32, pixels: Option<&[u8]>) -> Result<(), JsValue>;
+    fn tex_parameteri(target: u32, name: u32, value: i32);
+    fn uniform1f(location: Option<&WebGlUniformLocation>, x: f32);
+    fn uniform1i(location: Option<&WebGlUniformLocation>, x: i32);
+    fn uniform2f(location: Option<&WebGlUniformLocation>, x: f32, y: f32);
+    fn uniform3f(location: Option<&WebGlUniformLocation>, x: f32, y: f32, z: f32);
+    fn uniform4f(location: Option<&WebGlUniformLocation>, x: f32, y: f32, z: f32, w: f32);
+    fn uniform_matrix3fv_with_f32_array(location: Option<&WebGlUniformLocation>, transpose: bool, data: &[f32]);
+    fn uniform_matrix4fv_with_f32_array(location: Option<&WebGlUniformLocation>, transpose: bool, data: &[f32]);
+    fn use_program(program: Option<&WebGlProgram>);
+    fn vertex_attrib_divisor(index: u32, divisor: u32);
+    fn vertex_attrib_pointer_with_i32(index: u32, size: i32, type_: u32, normalized: bool, stride: i32, offset: i32);
+    fn viewport(x: i32, y: i32, width: i32, height: i32);
+}
+
+impl Gl {
+    /// Checked like the calls above, except that the pixels being written
+    /// are only reported by their length.
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn read_pixels_with_opt_u8_array(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        type_: u32,
+        pixels: Option<&mut [u8]>,
+    ) -> Result<(), JsValue> {
+        #[cfg(feature = "debug-gl")]
+        let length = pixels.as_ref().map(|pixels| pixels.len());
+        let result = self
+            .0
+            .read_pixels_with_opt_u8_array(x, y, width, height, format, type_, pixels);
+        #[cfg(feature = "debug-gl")]
+        self.check(
+            "read_pixels_with_opt_u8_array",
+            &[&x, &y, &width, &height, &format, &type_, &length],
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_names_error_call_and_location() {
+        let location = std::panic::Location::caller();
+        let message = error_message(
+            WebGl2RenderingContext::INVALID_OPERATION,
+            "draw_arrays",
+            &[&WebGl2RenderingContext::TRIANGLES, &0, &6],
+            location,
+        );
+        assert_eq!(
+            message,
+            format!("INVALID_OPERATION in draw_arrays(4, 0, 6) at {location}")
+        );
+    }
+
+    #[test]
+    fn long_arguments_are_cut_short() {
+        let data = [0u8; 100];
+        let message = error_message(
+            WebGl2RenderingContext::INVALID_VALUE,
+            "buffer_data_with_u8_array",
+            &[&data.as_slice()],
+            std::panic::Location::caller(),
+        );
+        assert!(message.contains("([0, 0, 0"));
+        assert!(message.contains("…) at "));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::gl::Gl;
+use crate::timing::{FrameStats, FRAME_INTERVAL_60HZ};
+use js_sys::{Array, Float32Array, Int32Array};
+use wasm_bindgen::JsCast;
+use web_sys::{Document, HtmlElement, WebGl2RenderingContext};
+
+// Frames kept for the graph and the histogram.
+const FRAMES: usize = 120;
+const HISTOGRAM_BINS: usize = 8;
+// Bins of half a 60 Hz frame, the last one counts everything from four
+// frames on.
+const HISTOGRAM_BIN_WIDTH: f64 = FRAME_INTERVAL_60HZ / 2.0;
+// The DOM overlay is updated a few times a second to stay readable.
+const TEXT_UPDATE_FRAMES: u64 = 15;
+
+/// Where a [`FrameHud`] shows the statistics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudKind {
+    /// Text over the top left corner of the page.
+    Dom,
+    /// Graphs in the bottom left corner of the canvas, drawn with
+    /// scissored clears after every frame.
+    Gl,
+}
+
+impl HudKind {
+    /// Kind requested with `?hud` (DOM overlay) or `?hud=gl` in the URL of
+    /// the page.
+    pub fn requested() -> Option<HudKind> {
+        let search = web_sys::window()?.location().search().ok()?;
+        search
+            .trim_start_matches('?')
+            .split('&')
+            .find_map(|parameter| match parameter {
+                "hud" | "hud=dom" => Some(HudKind::Dom),
+                "hud=gl" => Some(HudKind::Gl),
+                _ => None,
+            })
+    }
+}
+
+enum View {
+    Dom(HtmlElement),
+    Gl(WebGl2RenderingContext),
+}
+
+/// Overlay showing the frame rate, CPU time per frame, dropped frames and
+/// a histogram of the frame times of an animation.
+pub struct FrameHud {
+    stats: FrameStats,
+    view: View,
+}
+
+impl FrameHud {
+    pub fn dom(document: &Document) -> Self {
+        let element = document
+            .create_element("pre")
+            .unwrap()
+            .unchecked_into::<HtmlElement>();
+        element.set_class_name("frame-hud");
+        drop(element.set_attribute(
+            "style",
+            "position: fixed; top: 0; left: 0; z-index: 1; margin: 0; padding: 4px 8px; \
+             background: rgba(0, 0, 0, 0.7); color: #fff; font: 12px monospace; \
+             pointer-events: none;",
+        ));
+        drop(document.body().unwrap().append_with_node_1(&element));
+        Self::new(View::Dom(element))
+    }
+
+    pub fn gl(gl: &WebGl2RenderingContext) -> Self {
+        Self::new(View::Gl(gl.clone()))
+    }
+
+    fn new(view: View) -> Self {
+        Self {
+            stats: FrameStats::new(FRAMES, FRAME_INTERVAL_60HZ),
+            view,
+        }
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Records a frame, see [`FrameStats::record`], and shows the updated
+    /// statistics. Has to be called after the frame is rendered.
+    pub fn record(&mut self, timestamp: f64, work: f64) {
+        self.stats.record(timestamp, work);
+        match &self.view {
+            View::Dom(element) => {
+                if self.stats.frames() % TEXT_UPDATE_FRAMES == 1 {
+                    element.set_text_content(Some(&self.text()));
+                }
+            }
+            View::Gl(gl) => draw_graphs(gl, &self.stats),
+        }
+    }
+
+    fn text(&self) -> String {
+        let histogram = self.stats.histogram(HISTOGRAM_BINS, HISTOGRAM_BIN_WIDTH);
+        let most = histogram.iter().copied().max().unwrap_or(0).max(1);
+        let bars: String = histogram
+            .iter()
+            .map(|&count| {
+                const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+                BARS[count * (BARS.len() - 1) / most]
+            })
+            .collect();
+        format!(
+            "{}\nframe times {bars} 0–{:.0} ms",
+            self.stats,
+            HISTOGRAM_BIN_WIDTH * HISTOGRAM_BINS as f64
+        )
+    }
+}
+
+impl Drop for FrameHud {
+    fn drop(&mut self) {
+        if let View::Dom(element) = &self.view {
+            element.remove();
+        }
+    }
+}
+
+// Draws a bar per frame, red when frames were dropped, and the histogram
+// next to it, restoring all state it changes so that state caches stay
+// valid.
+fn draw_graphs(gl: &WebGl2RenderingContext, stats: &FrameStats) {
+    let gl = Gl::from_ref(gl);
+    const BAR_WIDTH: i32 = 2;
+    const GRAPH_HEIGHT: i32 = 60;
+    const HISTOGRAM_BAR_WIDTH: i32 = 8;
+    type GL = WebGl2RenderingContext;
+
+    let scissor_test = gl.is_enabled(GL::SCISSOR_TEST);
+    let scissor_box = gl.get_parameter(GL::SCISSOR_BOX).unwrap();
+    let clear_color = gl.get_parameter(GL::COLOR_CLEAR_VALUE).unwrap();
+    let color_mask = gl.get_parameter(GL::COLOR_WRITEMASK).unwrap();
+
+    gl.enable(GL::SCISSOR_TEST);
+    gl.color_mask(true, true, true, true);
+    let fill = |x: i32, y: i32, width: i32, height: i32, color: [f32; 4]| {
+        gl.scissor(x, y, width, height);
+        gl.clear_color(color[0], color[1], color[2], color[3]);
+        gl.clear(GL::COLOR_BUFFER_BIT);
+    };
+
+    // Three frames fill the graph, the expected one is at a third of it.
+    let target = stats.target_interval();
+    let bar_height =
+        |interval: f64| ((interval / target / 3.0 * GRAPH_HEIGHT as f64) as i32).min(GRAPH_HEIGHT);
+    let graph_width = FRAMES as i32 * BAR_WIDTH;
+    let histogram_width = HISTOGRAM_BINS as i32 * HISTOGRAM_BAR_WIDTH;
+    fill(
+        0,
+        0,
+        graph_width + histogram_width + 4,
+        GRAPH_HEIGHT,
+        [0.0, 0.0, 0.0, 0.8],
+    );
+
+    let skipped = FRAMES as i32 - stats.intervals().len() as i32;
+    for (index, interval) in stats.intervals().enumerate() {
+        let color = if interval > target * 1.5 {
+            [1.0, 0.2, 0.2, 1.0]
+        } else {
+            [0.2, 1.0, 0.4, 1.0]
+        };
+        let x = (skipped + index as i32) * BAR_WIDTH;
+        fill(x, 0, BAR_WIDTH, bar_height(interval), color);
+    }
+    // The frame time the display expects.
+    fill(0, bar_height(target), graph_width, 1, [1.0, 1.0, 1.0, 0.6]);
+
+    let histogram = stats.histogram(HISTOGRAM_BINS, HISTOGRAM_BIN_WIDTH);
+    let most = histogram.iter().copied().max().unwrap_or(0).max(1);
+    for (index, count) in histogram.into_iter().enumerate() {
+        let height = (count * GRAPH_HEIGHT as usize / most) as i32;
+        let x = graph_width + 4 + index as i32 * HISTOGRAM_BAR_WIDTH;
+        fill(x, 0, HISTOGRAM_BAR_WIDTH - 1, height, [0.4, 0.7, 1.0, 1.0]);
+    }
+
+    if !scissor_test {
+        gl.disable(GL::SCISSOR_TEST);
+    }
+    let scissor_box = scissor_box.unchecked_into::<Int32Array>().to_vec();
+    gl.scissor(
+        scissor_box[0],
+        scissor_box[1],
+        scissor_box[2],
+        scissor_box[3],
+    );
+    let clear_color = clear_color.unchecked_into::<Float32Array>().to_vec();
+    gl.clear_color(
+        clear_color[0],
+        clear_color[1],
+        clear_color[2],
+        clear_color[3],
+    );
+    let color_mask: Vec<bool> = color_mask
+        .unchecked_into::<Array>()
+        .iter()
+        .map(|enabled| enabled.is_truthy())
+        .collect();
+    gl.color_mask(color_mask[0], color_mask[1], color_mask[2], color_mask[3]);
+}
@@ -0,0 +1,86 @@
+use crate::gl::Gl;
+use crate::vertex::{point_attributes, Vertex, VertexBuffer};
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram};
+
+/// Corner of a quad spanning -1.0 to 1.0 on both axes.
+#[derive(Clone, Copy, Debug, crate::Vertex)]
+#[repr(C)]
+pub struct QuadCorner {
+    pub corner: [f32; 2],
+}
+
+/// Corners of a quad, in order for drawing it as a `TRIANGLE_STRIP`.
+pub const QUAD_CORNERS: [QuadCorner; 4] = [
+    QuadCorner {
+        corner: [-1.0, -1.0],
+    },
+    QuadCorner {
+        corner: [1.0, -1.0],
+    },
+    QuadCorner {
+        corner: [-1.0, 1.0],
+    },
+    QuadCorner { corner: [1.0, 1.0] },
+];
+
+/// Buffer of per-instance attributes. Unlike a [`VertexBuffer`] its
+/// attributes advance once per instance rather than once per vertex.
+///
+/// WebGL2 requires at least one attribute advancing per vertex, so an
+/// instanced draw always combines this with a regular vertex buffer, e.g.
+/// a single point or the [`QUAD_CORNERS`].
+pub struct InstanceBuffer<I: Vertex> {
+    instances: VertexBuffer<I>,
+}
+
+impl<I: Vertex> InstanceBuffer<I> {
+    pub fn new(gl: &WebGl2RenderingContext, instances: &[I], usage: u32) -> Result<Self, JsValue> {
+        Ok(Self {
+            instances: VertexBuffer::new(gl, instances, usage)?,
+        })
+    }
+
+    /// Replaces all instances.
+    pub fn set_data(&mut self, gl: &WebGl2RenderingContext, instances: &[I]) {
+        self.instances.set_data(gl, instances);
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        self.instances.bind(gl);
+    }
+
+    /// Same as [`VertexBuffer::bind_attributes`], with the attribute
+    /// divisor set to 1.
+    pub fn bind_attributes(&self, gl: &WebGl2RenderingContext, program: &WebGlProgram) {
+        self.bind(gl);
+        point_attributes::<I>(gl, program, 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn gl_buffer(&self) -> &WebGlBuffer {
+        self.instances.gl_buffer()
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.instances.delete(gl);
+    }
+}
+
+/// Draws `vertex_count` vertices once for every instance in `instances`.
+pub fn draw_arrays_instanced<I: Vertex>(
+    gl: &WebGl2RenderingContext,
+    mode: u32,
+    vertex_count: i32,
+    instances: &InstanceBuffer<I>,
+) {
+    let gl = Gl::from_ref(gl);
+    gl.draw_arrays_instanced(mode, 0, vertex_count, instances.len() as i32);
+}
@@ -0,0 +1,33 @@
+// Lets `#[derive(Vertex)]` refer to `::webgl_common` from within this crate.
+extern crate self as webgl_common;
+
+pub mod capture;
+pub mod color;
+pub mod example;
+pub mod gl;
+pub mod hud;
+pub mod instance;
+pub mod math;
+pub mod post_process;
+pub mod program;
+pub mod random;
+pub mod render_target;
+pub mod resize;
+pub mod resources;
+pub mod software;
+pub mod state;
+pub mod timing;
+pub mod trace;
+pub mod vertex;
+
+pub use color::Color;
+pub use example::{Example, ExampleInfo, Host, Setup};
+pub use gl::Gl;
+pub use instance::{InstanceBuffer, QuadCorner, QUAD_CORNERS};
+pub use math::{Mat3, Mat4, Vec2, Vec3, Vec4};
+pub use random::Rng;
+pub use resources::ResourceRegistry;
+pub use software::{ClearContext, SoftwareContext, SoftwareExample};
+pub use state::StateCache;
+pub use vertex::{GrowableVertexBuffer, Vertex, VertexBuffer};
+pub use webgl_common_derive::Vertex;
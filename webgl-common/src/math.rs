@@ -0,0 +1,605 @@
+use crate::gl::Gl;
+use crate::vertex::AttributeFormat;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+
+// Vectors are plain `#[repr(C)]` structs of `f32`, laid out like arrays,
+// so they can be vertex attributes and matrices can be uploaded as they
+// are.
+macro_rules! vector {
+    ($name:ident { $($field:ident),+ } $components:literal) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        #[repr(C)]
+        pub struct $name {
+            $(pub $field: f32,)+
+        }
+
+        impl $name {
+            pub const ZERO: $name = $name { $($field: 0.0,)+ };
+
+            pub const fn new($($field: f32),+) -> $name {
+                $name { $($field,)+ }
+            }
+
+            pub fn to_array(self) -> [f32; $components] {
+                [$(self.$field),+]
+            }
+
+            pub fn dot(self, other: $name) -> f32 {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+            pub fn length(self) -> f32 {
+                self.dot(self).sqrt()
+            }
+
+            /// The vector scaled to a length of one, zero stays zero.
+            pub fn normalize(self) -> $name {
+                let length = self.length();
+                if length == 0.0 {
+                    self
+                } else {
+                    self * (1.0 / length)
+                }
+            }
+
+            pub fn lerp(self, other: $name, t: f32) -> $name {
+                self + (other - self) * t
+            }
+        }
+
+        impl From<[f32; $components]> for $name {
+            fn from([$($field),+]: [f32; $components]) -> $name {
+                $name { $($field,)+ }
+            }
+        }
+
+        impl From<$name> for [f32; $components] {
+            fn from(vector: $name) -> [f32; $components] {
+                vector.to_array()
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, other: $name) -> $name {
+                $name { $($field: self.$field + other.$field,)+ }
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: $name) {
+                *self = *self + other;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, other: $name) -> $name {
+                $name { $($field: self.$field - other.$field,)+ }
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: $name) {
+                *self = *self - other;
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = $name;
+
+            fn mul(self, scale: f32) -> $name {
+                $name { $($field: self.$field * scale,)+ }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name { $($field: -self.$field,)+ }
+            }
+        }
+
+        impl AttributeFormat for $name {
+            const COMPONENTS: i32 = $components;
+            const DATA_TYPE: u32 = WebGl2RenderingContext::FLOAT;
+        }
+    };
+}
+
+vector!(Vec2 { x, y } 2);
+vector!(Vec3 { x, y, z } 3);
+vector!(Vec4 { x, y, z, w } 4);
+
+impl Vec2 {
+    pub fn extend(self, z: f32) -> Vec3 {
+        Vec3::new(self.x, self.y, z)
+    }
+}
+
+impl Vec3 {
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn extend(self, w: f32) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+
+    pub fn truncate(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+impl Vec4 {
+    pub fn truncate(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
+/// A 3x3 matrix stored column by column, as GLSL expects it. Transforms 2D
+/// points given as `Vec3` with `z` set to 1.
+///
+/// `a * b` applies `b` first, then `a`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Mat3 {
+    pub cols: [Vec3; 3],
+}
+
+impl Mat3 {
+    pub const IDENTITY: Mat3 = Mat3::from_cols(
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    );
+
+    pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Mat3 {
+        Mat3 { cols: [x, y, z] }
+    }
+
+    pub fn translation(offset: Vec2) -> Mat3 {
+        Mat3::from_cols(
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            offset.extend(1.0),
+        )
+    }
+
+    pub fn scale(scale: Vec2) -> Mat3 {
+        Mat3::from_cols(
+            Vec3::new(scale.x, 0.0, 0.0),
+            Vec3::new(0.0, scale.y, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Counterclockwise rotation by `angle` radians.
+    pub fn rotation(angle: f32) -> Mat3 {
+        let (sin, cos) = angle.sin_cos();
+        Mat3::from_cols(
+            Vec3::new(cos, sin, 0.0),
+            Vec3::new(-sin, cos, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Converts positions on a canvas measuring `css_size` CSS pixels, from
+    /// its top left corner like in mouse events, to pixels of a drawing
+    /// buffer of `buffer_size`, from its bottom left corner like in WebGL.
+    pub fn css_to_buffer(css_size: Vec2, buffer_size: Vec2) -> Mat3 {
+        Mat3::translation(Vec2::new(0.0, buffer_size.y))
+            * Mat3::scale(Vec2::new(
+                buffer_size.x / css_size.x.max(1.0),
+                -buffer_size.y / css_size.y.max(1.0),
+            ))
+    }
+
+    pub fn row(&self, index: usize) -> Vec3 {
+        let [x, y, z] = self.cols.map(|col| col.to_array()[index]);
+        Vec3::new(x, y, z)
+    }
+
+    pub fn transpose(&self) -> Mat3 {
+        Mat3::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+    /// Applies the matrix to a point, including the translation.
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        (*self * point.extend(1.0)).truncate()
+    }
+
+    pub fn to_cols_array(&self) -> [f32; 9] {
+        let [x, y, z] = self.cols;
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z]
+    }
+
+    /// Sets a `mat3` uniform of the program in use.
+    pub fn upload(&self, gl: &Gl, location: Option<&WebGlUniformLocation>) {
+        gl.uniform_matrix3fv_with_f32_array(location, false, &self.to_cols_array());
+    }
+}
+
+impl Default for Mat3 {
+    fn default() -> Mat3 {
+        Mat3::IDENTITY
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3 {
+        let [x, y, z] = self.cols;
+        x * vector.x + y * vector.y + z * vector.z
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, other: Mat3) -> Mat3 {
+        Mat3 {
+            cols: other.cols.map(|col| self * col),
+        }
+    }
+}
+
+/// A 4x4 matrix stored column by column, as `uniformMatrix4fv` expects
+/// it. Projections follow the OpenGL conventions: the camera looks down
+/// the negative z axis and clip space depth goes from -1 to 1.
+///
+/// `a * b` applies `b` first, then `a`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Mat4 {
+    pub cols: [Vec4; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4::from_cols(
+        Vec4::new(1.0, 0.0, 0.0, 0.0),
+        Vec4::new(0.0, 1.0, 0.0, 0.0),
+        Vec4::new(0.0, 0.0, 1.0, 0.0),
+        Vec4::new(0.0, 0.0, 0.0, 1.0),
+    );
+
+    pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Mat4 {
+        Mat4 { cols: [x, y, z, w] }
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut matrix = Mat4::IDENTITY;
+        matrix.cols[3] = offset.extend(1.0);
+        matrix
+    }
+
+    pub fn scale(scale: Vec3) -> Mat4 {
+        Mat4::from_cols(
+            Vec4::new(scale.x, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, scale.y, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, scale.z, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Counterclockwise rotation by `angle` radians around `axis`, looking
+    /// at the origin from the positive side of the axis.
+    pub fn rotation(axis: Vec3, angle: f32) -> Mat4 {
+        let Vec3 { x, y, z } = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Mat4::from_cols(
+            Vec4::new(
+                t * x * x + cos,
+                t * x * y + sin * z,
+                t * x * z - sin * y,
+                0.0,
+            ),
+            Vec4::new(
+                t * x * y - sin * z,
+                t * y * y + cos,
+                t * y * z + sin * x,
+                0.0,
+            ),
+            Vec4::new(
+                t * x * z + sin * y,
+                t * y * z - sin * x,
+                t * z * z + cos,
+                0.0,
+            ),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Maps the box between the planes to clip space, `near` and `far`
+    /// being distances in front of the camera.
+    pub fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+        let (width, height, depth) = (right - left, top - bottom, far - near);
+        Mat4::from_cols(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, -2.0 / depth, 0.0),
+            Vec4::new(
+                -(right + left) / width,
+                -(top + bottom) / height,
+                -(far + near) / depth,
+                1.0,
+            ),
+        )
+    }
+
+    /// Projection working in pixels of a drawing buffer of `width` x
+    /// `height`, from its bottom left corner like `gl_FragCoord`, with
+    /// depths between -1 and 1.
+    pub fn pixel_ortho(width: f32, height: f32) -> Mat4 {
+        Mat4::ortho(0.0, width, 0.0, height, -1.0, 1.0)
+    }
+
+    /// Perspective projection with a vertical field of view of
+    /// `fov_y` radians.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        let focal = 1.0 / (fov_y / 2.0).tan();
+        let depth = near - far;
+        Mat4::from_cols(
+            Vec4::new(focal / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, focal, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, (far + near) / depth, -1.0),
+            Vec4::new(0.0, 0.0, 2.0 * far * near / depth, 0.0),
+        )
+    }
+
+    /// View matrix of a camera at `eye` looking at `target`, with `up`
+    /// pointing roughly upward on screen.
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+        let forward = (target - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+        Mat4::from_cols(
+            Vec4::new(right.x, up.x, -forward.x, 0.0),
+            Vec4::new(right.y, up.y, -forward.y, 0.0),
+            Vec4::new(right.z, up.z, -forward.z, 0.0),
+            Vec4::new(-right.dot(eye), -up.dot(eye), forward.dot(eye), 1.0),
+        )
+    }
+
+    pub fn row(&self, index: usize) -> Vec4 {
+        let [x, y, z, w] = self.cols.map(|col| col.to_array()[index]);
+        Vec4::new(x, y, z, w)
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        Mat4::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+    /// Applies the matrix to a point and divides by `w`.
+    pub fn project_point(&self, point: Vec3) -> Vec3 {
+        let Vec4 { x, y, z, w } = *self * point.extend(1.0);
+        Vec3::new(x, y, z) * (1.0 / w)
+    }
+
+    pub fn to_cols_array(&self) -> [f32; 16] {
+        let mut array = [0.0; 16];
+        for (chunk, col) in array.chunks_exact_mut(4).zip(self.cols) {
+            chunk.copy_from_slice(&col.to_array());
+        }
+        array
+    }
+
+    /// Sets a `mat4` uniform of the program in use.
+    pub fn upload(&self, gl: &Gl, location: Option<&WebGlUniformLocation>) {
+        gl.uniform_matrix4fv_with_f32_array(location, false, &self.to_cols_array());
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        Mat4::IDENTITY
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, vector: Vec4) -> Vec4 {
+        let [x, y, z, w] = self.cols;
+        x * vector.x + y * vector.y + z * vector.z + w * vector.w
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        Mat4 {
+            cols: other.cols.map(|col| self * col),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+    use std::f32::consts::FRAC_PI_2;
+    use std::mem::{align_of, size_of};
+
+    fn assert_close<const N: usize>(actual: [f32; N], expected: [f32; N]) {
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 1e-4,
+                "{actual:?} is not close to {expected:?}"
+            );
+        }
+    }
+
+    fn random_matrix(rng: &mut Rng) -> Mat4 {
+        let mut value = || 4.0 * rng.next_f32() - 2.0;
+        Mat4::from_cols(
+            Vec4::new(value(), value(), value(), value()),
+            Vec4::new(value(), value(), value(), value()),
+            Vec4::new(value(), value(), value(), value()),
+            Vec4::new(value(), value(), value(), value()),
+        )
+    }
+
+    #[test]
+    fn layouts_match_glsl() {
+        assert_eq!(size_of::<Vec2>(), 8);
+        assert_eq!(size_of::<Vec3>(), 12);
+        assert_eq!(size_of::<Vec4>(), 16);
+        assert_eq!(size_of::<Mat3>(), 36);
+        assert_eq!(size_of::<Mat4>(), 64);
+        assert_eq!(align_of::<Mat4>(), align_of::<f32>());
+        // Column major, the translation is in the last four values.
+        assert_eq!(
+            Mat4::translation(Vec3::new(1.0, 2.0, 3.0)).to_cols_array()[12..],
+            [1.0, 2.0, 3.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn vector_arithmetic() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, 5.0, 6.0);
+        assert_eq!(a + b, Vec3::new(5.0, 7.0, 9.0));
+        assert_eq!(b - a, Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(a.dot(b), 32.0);
+        assert_eq!(
+            Vec3::new(1.0, 0.0, 0.0).cross(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(Vec2::new(3.0, 4.0).length(), 5.0);
+        assert_eq!(Vec2::new(0.0, 2.0).normalize(), Vec2::new(0.0, 1.0));
+        assert_eq!(Vec4::ZERO.normalize(), Vec4::ZERO);
+        assert_eq!(a.lerp(b, 0.5), Vec3::new(2.5, 3.5, 4.5));
+    }
+
+    #[test]
+    fn composition_applies_right_to_left() {
+        let translate = Mat3::translation(Vec2::new(10.0, 0.0));
+        let rotate = Mat3::rotation(FRAC_PI_2);
+        let point = Vec2::new(1.0, 0.0);
+        assert_close(
+            (translate * rotate).transform_point(point).to_array(),
+            [10.0, 1.0],
+        );
+        assert_close(
+            (rotate * translate).transform_point(point).to_array(),
+            [0.0, 11.0],
+        );
+    }
+
+    #[test]
+    fn multiplication_is_associative_with_identity() {
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let (a, b, c) = (
+                random_matrix(&mut rng),
+                random_matrix(&mut rng),
+                random_matrix(&mut rng),
+            );
+            assert_eq!(Mat4::IDENTITY * a, a);
+            assert_eq!(a * Mat4::IDENTITY, a);
+            assert_close(((a * b) * c).to_cols_array(), (a * (b * c)).to_cols_array());
+            assert_eq!(a.transpose().transpose(), a);
+        }
+    }
+
+    #[test]
+    fn rotation_around_z_matches_2d_rotation() {
+        let rotation = Mat4::rotation(Vec3::new(0.0, 0.0, 2.0), FRAC_PI_2);
+        assert_close(
+            rotation.project_point(Vec3::new(1.0, 0.0, 5.0)).to_array(),
+            [0.0, 1.0, 5.0],
+        );
+        let around_x = Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), FRAC_PI_2);
+        assert_close(
+            around_x.project_point(Vec3::new(0.0, 1.0, 0.0)).to_array(),
+            [0.0, 0.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn ortho_maps_the_box_to_clip_space() {
+        let ortho = Mat4::ortho(-2.0, 6.0, 1.0, 5.0, 0.5, 10.5);
+        assert_close(
+            ortho.project_point(Vec3::new(-2.0, 1.0, -0.5)).to_array(),
+            [-1.0, -1.0, -1.0],
+        );
+        assert_close(
+            ortho.project_point(Vec3::new(6.0, 5.0, -10.5)).to_array(),
+            [1.0, 1.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn pixel_ortho_covers_the_drawing_buffer() {
+        let projection = Mat4::pixel_ortho(280.0, 210.0);
+        assert_close(
+            projection.project_point(Vec3::ZERO).to_array(),
+            [-1.0, -1.0, 0.0],
+        );
+        assert_close(
+            projection
+                .project_point(Vec3::new(140.0, 210.0, 0.0))
+                .to_array(),
+            [0.0, 1.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn css_positions_become_buffer_pixels() {
+        // A canvas shown at 280x210 CSS pixels on a screen with a
+        // devicePixelRatio of 2.
+        let transform = Mat3::css_to_buffer(Vec2::new(280.0, 210.0), Vec2::new(560.0, 420.0));
+        assert_close(
+            transform.transform_point(Vec2::new(0.0, 0.0)).to_array(),
+            [0.0, 420.0],
+        );
+        assert_close(
+            transform.transform_point(Vec2::new(70.0, 210.0)).to_array(),
+            [140.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_planes() {
+        let projection = Mat4::perspective(FRAC_PI_2, 2.0, 1.0, 100.0);
+        assert_close(
+            projection
+                .project_point(Vec3::new(2.0, 1.0, -1.0))
+                .to_array(),
+            [1.0, 1.0, -1.0],
+        );
+        assert_close(
+            projection
+                .project_point(Vec3::new(0.0, 0.0, -100.0))
+                .to_array(),
+            [0.0, 0.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn look_at_puts_the_target_in_front() {
+        let eye = Vec3::new(3.0, 4.0, 5.0);
+        let view = Mat4::look_at(eye, Vec3::ZERO, Vec3::new(0.0, 1.0, 0.0));
+        assert_close(view.project_point(eye).to_array(), [0.0, 0.0, 0.0]);
+        let distance = eye.length();
+        assert_close(
+            view.project_point(Vec3::ZERO).to_array(),
+            [0.0, 0.0, -distance],
+        );
+        // Up stays up on screen.
+        let above = view.project_point(Vec3::new(0.0, 1.0, 0.0));
+        assert!(above.y > 0.0 && above.x.abs() < 1e-4);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::gl::Gl;
+use crate::program::{link_program, ProgramError};
+use crate::render_target::{DepthStencil, FramebufferError, RenderTarget};
+use crate::resources;
+use crate::{QuadCorner, VertexBuffer, QUAD_CORNERS};
+use std::fmt;
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+/// Full screen effect applied by a [`PostProcessChain`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Effect {
+    Grayscale,
+    /// Gaussian blur, `radius` in pixels.
+    Blur {
+        radius: f32,
+    },
+    /// Darkens the corners, `strength` between 0.0 and 1.0.
+    Vignette {
+        strength: f32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PostProcessError {
+    Program(ProgramError),
+    Framebuffer(FramebufferError),
+    Create(&'static str),
+}
+
+impl fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostProcessError::Program(error) => write!(f, "post-process pass: {error}"),
+            PostProcessError::Framebuffer(error) => write!(f, "post-process target: {error}"),
+            PostProcessError::Create(object) => write!(f, "unable to create {object}"),
+        }
+    }
+}
+
+impl std::error::Error for PostProcessError {}
+
+impl From<ProgramError> for PostProcessError {
+    fn from(error: ProgramError) -> Self {
+        PostProcessError::Program(error)
+    }
+}
+
+impl From<FramebufferError> for PostProcessError {
+    fn from(error: FramebufferError) -> Self {
+        PostProcessError::Framebuffer(error)
+    }
+}
+
+impl From<PostProcessError> for JsValue {
+    fn from(error: PostProcessError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+// One full screen shader together with the vertex array feeding it the
+// quad corners.
+struct Pass {
+    program: WebGlProgram,
+    vertex_array: WebGlVertexArrayObject,
+    texel_size: Option<WebGlUniformLocation>,
+    // `direction` of the blur or `strength` of the vignette.
+    parameter: Option<WebGlUniformLocation>,
+}
+
+impl Pass {
+    fn new(
+        gl: &WebGl2RenderingContext,
+        corner_buffer: &VertexBuffer<QuadCorner>,
+        fragment_source: &str,
+        parameter: Option<&str>,
+    ) -> Result<Self, PostProcessError> {
+        let gl = Gl::from_ref(gl);
+        let program = link_program(
+            gl,
+            include_str!("post_process/fullscreen.vert"),
+            fragment_source,
+        )?;
+
+        let vertex_array = match resources::create_vertex_array(gl) {
+            Some(vertex_array) => vertex_array,
+            None => {
+                resources::delete_program(gl, Some(&program));
+                return Err(PostProcessError::Create("vertex array"));
+            }
+        };
+        gl.bind_vertex_array(Some(&vertex_array));
+        corner_buffer.bind_attributes(gl, &program);
+        gl.bind_vertex_array(None);
+
+        // The source texture is always bound to texture unit 0.
+        gl.use_program(Some(&program));
+        gl.uniform1i(gl.get_uniform_location(&program, "source").as_ref(), 0);
+        gl.use_program(None);
+
+        Ok(Self {
+            texel_size: gl.get_uniform_location(&program, "texelSize"),
+            parameter: parameter.and_then(|name| gl.get_uniform_location(&program, name)),
+            program,
+            vertex_array,
+        })
+    }
+
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        resources::delete_vertex_array(gl, Some(&self.vertex_array));
+        resources::delete_program(gl, Some(&self.program));
+    }
+}
+
+// Shaders of the chain and the uniform holding their parameter, in the
+// order of `PostProcessChain::passes`.
+const PASSES: [(&str, Option<&str>); 4] = [
+    (include_str!("post_process/copy.frag"), None),
+    (include_str!("post_process/grayscale.frag"), None),
+    (include_str!("post_process/blur.frag"), Some("direction")),
+    (include_str!("post_process/vignette.frag"), Some("strength")),
+];
+
+// What a step draws. Blur is split in a horizontal and a vertical draw.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Draw {
+    Copy,
+    Grayscale,
+    Blur([f32; 2]),
+    Vignette(f32),
+}
+
+impl Draw {
+    // Index of the pass in `PASSES`.
+    fn pass(self) -> usize {
+        match self {
+            Draw::Copy => 0,
+            Draw::Grayscale => 1,
+            Draw::Blur(_) => 2,
+            Draw::Vignette(_) => 3,
+        }
+    }
+}
+
+// A single draw of the chain, reading one of the two targets and writing
+// into the other, or into the canvas when `destination` is `None`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    draw: Draw,
+    source: usize,
+    destination: Option<usize>,
+}
+
+// Plans the draws applying `effects` to the scene rendered into the first
+// target. Without effects the scene is copied to the canvas.
+fn steps(effects: &[Effect]) -> Vec<Step> {
+    let mut draws: Vec<Draw> = effects
+        .iter()
+        .flat_map(|effect| match *effect {
+            Effect::Grayscale => vec![Draw::Grayscale],
+            Effect::Blur { radius } => vec![Draw::Blur([radius, 0.0]), Draw::Blur([0.0, radius])],
+            Effect::Vignette { strength } => vec![Draw::Vignette(strength)],
+        })
+        .collect();
+    if draws.is_empty() {
+        draws.push(Draw::Copy);
+    }
+
+    let last = draws.len() - 1;
+    draws
+        .into_iter()
+        .enumerate()
+        .map(|(index, draw)| Step {
+            draw,
+            source: index % 2,
+            destination: (index != last).then_some((index + 1) % 2),
+        })
+        .collect()
+}
+
+/// Renders a scene into an off-screen target and applies a list of
+/// [`Effect`]s on the way to the canvas.
+///
+/// Call [`begin`](Self::begin) before drawing the scene and
+/// [`finish`](Self::finish) afterwards. The passes expect blending and the
+/// scissor test to be disabled.
+pub struct PostProcessChain {
+    corner_buffer: VertexBuffer<QuadCorner>,
+    // One pass per entry of `PASSES`.
+    passes: Vec<Pass>,
+    // Effects read from one target and write into the other.
+    targets: [RenderTarget; 2],
+    effects: Vec<Effect>,
+}
+
+impl PostProcessChain {
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, PostProcessError> {
+        let corner_buffer =
+            VertexBuffer::new(gl, &QUAD_CORNERS, WebGl2RenderingContext::STATIC_DRAW)
+                .map_err(|_| PostProcessError::Create("vertex buffer"))?;
+
+        let mut passes = Vec::with_capacity(PASSES.len());
+        for (fragment_source, parameter) in PASSES {
+            match Pass::new(gl, &corner_buffer, fragment_source, parameter) {
+                Ok(pass) => passes.push(pass),
+                Err(error) => {
+                    delete_all(gl, &corner_buffer, &passes, &[]);
+                    return Err(error);
+                }
+            }
+        }
+
+        let first = RenderTarget::new(gl, width, height, DepthStencil::None).inspect_err(|_| {
+            delete_all(gl, &corner_buffer, &passes, &[]);
+        })?;
+        let second =
+            RenderTarget::new(gl, width, height, DepthStencil::None).inspect_err(|_| {
+                delete_all(gl, &corner_buffer, &passes, &[&first]);
+            })?;
+
+        Ok(Self {
+            corner_buffer,
+            passes,
+            targets: [first, second],
+            effects: Vec::new(),
+        })
+    }
+
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    pub fn set_effects(&mut self, effects: Vec<Effect>) {
+        self.effects = effects;
+    }
+
+    pub fn resize(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+    ) -> Result<(), FramebufferError> {
+        for target in &mut self.targets {
+            target.resize(gl, width, height)?;
+        }
+        Ok(())
+    }
+
+    /// Directs rendering of the scene into the off-screen target.
+    pub fn begin(&self, gl: &WebGl2RenderingContext) {
+        self.targets[0].bind(gl);
+    }
+
+    /// Applies the effects to the rendered scene, the last one drawing
+    /// into the canvas.
+    pub fn finish(&self, gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        for step in steps(&self.effects) {
+            let source = &self.targets[step.source];
+            match step.destination {
+                Some(destination) => self.targets[destination].bind(gl),
+                None => RenderTarget::bind_default(gl),
+            }
+
+            let pass = &self.passes[step.draw.pass()];
+            gl.use_program(Some(&pass.program));
+            gl.bind_vertex_array(Some(&pass.vertex_array));
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(source.texture()));
+            gl.uniform2f(
+                pass.texel_size.as_ref(),
+                1.0 / source.width() as f32,
+                1.0 / source.height() as f32,
+            );
+            match step.draw {
+                Draw::Blur(direction) => {
+                    gl.uniform2f(pass.parameter.as_ref(), direction[0], direction[1])
+                }
+                Draw::Vignette(strength) => gl.uniform1f(pass.parameter.as_ref(), strength),
+                Draw::Copy | Draw::Grayscale => {}
+            }
+            gl.draw_arrays(
+                WebGl2RenderingContext::TRIANGLE_STRIP,
+                0,
+                self.corner_buffer.len() as i32,
+            );
+        }
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        gl.bind_vertex_array(None);
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        let [first, second] = &self.targets;
+        delete_all(gl, &self.corner_buffer, &self.passes, &[first, second]);
+    }
+}
+
+// Deletes the objects of a chain, also of one only partly created.
+fn delete_all(
+    gl: &WebGl2RenderingContext,
+    corner_buffer: &VertexBuffer<QuadCorner>,
+    passes: &[Pass],
+    targets: &[&RenderTarget],
+) {
+    for pass in passes {
+        pass.delete(gl);
+    }
+    for target in targets {
+        target.delete(gl);
+    }
+    corner_buffer.delete(gl);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draws(steps: &[Step]) -> Vec<Draw> {
+        steps.iter().map(|step| step.draw).collect()
+    }
+
+    #[test]
+    fn scene_is_copied_without_effects() {
+        assert_eq!(
+            steps(&[]),
+            [Step {
+                draw: Draw::Copy,
+                source: 0,
+                destination: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn blur_is_drawn_in_two_directions() {
+        assert_eq!(
+            draws(&steps(&[Effect::Blur { radius: 3.0 }])),
+            [Draw::Blur([3.0, 0.0]), Draw::Blur([0.0, 3.0])]
+        );
+    }
+
+    #[test]
+    fn steps_alternate_between_targets() {
+        let steps = steps(&[
+            Effect::Grayscale,
+            Effect::Blur { radius: 2.0 },
+            Effect::Vignette { strength: 0.5 },
+        ]);
+        assert_eq!(
+            draws(&steps),
+            [
+                Draw::Grayscale,
+                Draw::Blur([2.0, 0.0]),
+                Draw::Blur([0.0, 2.0]),
+                Draw::Vignette(0.5),
+            ]
+        );
+        let targets: Vec<_> = steps
+            .iter()
+            .map(|step| (step.source, step.destination))
+            .collect();
+        assert_eq!(
+            targets,
+            [(0, Some(1)), (1, Some(0)), (0, Some(1)), (1, None)]
+        );
+    }
+}
@@ -0,0 +1,26 @@
+#version 100
+precision mediump float;
+
+uniform sampler2D source;
+uniform vec2 texelSize;
+// Blur radius along one axis, in texels. The blur is separable,
+// so it runs once horizontally and once vertically.
+uniform vec2 direction;
+
+varying vec2 textureCoord;
+
+vec4 sampleBoth(vec2 offset) {
+  return texture2D(source, textureCoord + offset)
+       + texture2D(source, textureCoord - offset);
+}
+
+void main() {
+  // Nine tap Gaussian kernel, spread over the blur radius.
+  vec2 stepSize = 0.25 * direction * texelSize;
+  vec4 color = texture2D(source, textureCoord) * 0.227027;
+  color += sampleBoth(1.0 * stepSize) * 0.1945946;
+  color += sampleBoth(2.0 * stepSize) * 0.1216216;
+  color += sampleBoth(3.0 * stepSize) * 0.054054;
+  color += sampleBoth(4.0 * stepSize) * 0.016216;
+  gl_FragColor = color;
+}
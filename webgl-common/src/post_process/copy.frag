@@ -0,0 +1,10 @@
+#version 100
+precision mediump float;
+
+uniform sampler2D source;
+
+varying vec2 textureCoord;
+
+void main() {
+  gl_FragColor = texture2D(source, textureCoord);
+}
@@ -0,0 +1,11 @@
+#version 100
+precision highp float;
+
+attribute vec2 corner;
+
+varying vec2 textureCoord;
+
+void main() {
+  textureCoord = 0.5 * corner + 0.5;
+  gl_Position = vec4(corner, 0.0, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 100
+precision mediump float;
+
+uniform sampler2D source;
+
+varying vec2 textureCoord;
+
+void main() {
+  vec4 color = texture2D(source, textureCoord);
+  float luminance = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+  gl_FragColor = vec4(vec3(luminance), color.a);
+}
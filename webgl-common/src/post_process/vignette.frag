@@ -0,0 +1,14 @@
+#version 100
+precision mediump float;
+
+uniform sampler2D source;
+uniform float strength;
+
+varying vec2 textureCoord;
+
+void main() {
+  vec4 color = texture2D(source, textureCoord);
+  float distanceFromCenter = distance(textureCoord, vec2(0.5));
+  float vignette = 1.0 - strength * smoothstep(0.3, 0.75, distanceFromCenter);
+  gl_FragColor = vec4(color.rgb * vignette, color.a);
+}
@@ -0,0 +1,112 @@
+use crate::gl::Gl;
+use crate::resources;
+use std::fmt;
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader};
+
+/// Failure to build a program, with the info log reported by WebGL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgramError {
+    /// `createShader` or `createProgram` returned null, e.g. because the
+    /// context is lost.
+    Create,
+    Compile {
+        shader_type: u32,
+        log: String,
+    },
+    Link {
+        log: String,
+    },
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::Create => write!(f, "unable to create shader or program object"),
+            ProgramError::Compile { shader_type, log } => {
+                let stage = if *shader_type == WebGl2RenderingContext::VERTEX_SHADER {
+                    "vertex"
+                } else {
+                    "fragment"
+                };
+                write!(f, "{stage} shader failed to compile: {log}")
+            }
+            ProgramError::Link { log } => write!(f, "program failed to link: {log}"),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+impl From<ProgramError> for JsValue {
+    fn from(error: ProgramError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+pub fn compile_shader(
+    gl: &WebGl2RenderingContext,
+    shader_type: u32,
+    source: &str,
+) -> Result<WebGlShader, ProgramError> {
+    let gl = Gl::from_ref(gl);
+    let shader = resources::create_shader(gl, shader_type).ok_or(ProgramError::Create)?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+
+    if gl
+        .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(shader)
+    } else {
+        let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+        resources::delete_shader(gl, Some(&shader));
+        Err(ProgramError::Compile { shader_type, log })
+    }
+}
+
+/// Compiles both shaders and links them into a program. The shaders are
+/// detached and deleted afterwards, only the program has to be cleaned up.
+pub fn link_program(
+    gl: &WebGl2RenderingContext,
+    vertex_source: &str,
+    fragment_source: &str,
+) -> Result<WebGlProgram, ProgramError> {
+    let gl = Gl::from_ref(gl);
+    let vertex_shader = compile_shader(gl, WebGl2RenderingContext::VERTEX_SHADER, vertex_source)?;
+    let fragment_shader =
+        match compile_shader(gl, WebGl2RenderingContext::FRAGMENT_SHADER, fragment_source) {
+            Ok(fragment_shader) => fragment_shader,
+            Err(error) => {
+                resources::delete_shader(gl, Some(&vertex_shader));
+                return Err(error);
+            }
+        };
+
+    let program = resources::create_program(gl);
+    if let Some(program) = &program {
+        gl.attach_shader(program, &vertex_shader);
+        gl.attach_shader(program, &fragment_shader);
+
+        gl.link_program(program);
+        gl.detach_shader(program, &vertex_shader);
+        gl.detach_shader(program, &fragment_shader);
+    }
+    resources::delete_shader(gl, Some(&vertex_shader));
+    resources::delete_shader(gl, Some(&fragment_shader));
+    let program = program.ok_or(ProgramError::Create)?;
+
+    if gl
+        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(program)
+    } else {
+        let log = gl.get_program_info_log(&program).unwrap_or_default();
+        resources::delete_program(gl, Some(&program));
+        Err(ProgramError::Link { log })
+    }
+}
@@ -0,0 +1,323 @@
+use crate::gl::Gl;
+use crate::resources;
+use std::fmt;
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer, WebGlRenderbuffer, WebGlTexture};
+
+/// Which depth and stencil buffers a [`RenderTarget`] gets besides its
+/// color texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepthStencil {
+    None,
+    Depth,
+    DepthStencil,
+}
+
+/// Reasons for a [`RenderTarget`] to be unusable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FramebufferError {
+    /// Creating the named WebGL object returned null, e.g. because the
+    /// context is lost.
+    Create(&'static str),
+    IncompleteAttachment,
+    MissingAttachment,
+    IncompleteDimensions,
+    Unsupported,
+    IncompleteMultisample,
+    /// Status returned by `checkFramebufferStatus` not known to WebGL2.
+    Unknown(u32),
+}
+
+impl FramebufferError {
+    /// Maps the result of `checkFramebufferStatus`, `None` meaning the
+    /// framebuffer is complete.
+    pub fn from_status(status: u32) -> Option<Self> {
+        match status {
+            WebGl2RenderingContext::FRAMEBUFFER_COMPLETE => None,
+            WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+                Some(FramebufferError::IncompleteAttachment)
+            }
+            WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+                Some(FramebufferError::MissingAttachment)
+            }
+            WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => {
+                Some(FramebufferError::IncompleteDimensions)
+            }
+            WebGl2RenderingContext::FRAMEBUFFER_UNSUPPORTED => Some(FramebufferError::Unsupported),
+            WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+                Some(FramebufferError::IncompleteMultisample)
+            }
+            status => Some(FramebufferError::Unknown(status)),
+        }
+    }
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FramebufferError::Create(object) => write!(f, "unable to create {object}"),
+            FramebufferError::IncompleteAttachment => {
+                write!(f, "framebuffer incomplete: attachment is not complete")
+            }
+            FramebufferError::MissingAttachment => {
+                write!(f, "framebuffer incomplete: no image attached")
+            }
+            FramebufferError::IncompleteDimensions => {
+                write!(f, "framebuffer incomplete: attachments differ in size")
+            }
+            FramebufferError::Unsupported => {
+                write!(
+                    f,
+                    "framebuffer incomplete: attachment formats are not supported"
+                )
+            }
+            FramebufferError::IncompleteMultisample => {
+                write!(
+                    f,
+                    "framebuffer incomplete: attachments differ in sample count"
+                )
+            }
+            FramebufferError::Unknown(status) => {
+                write!(f, "framebuffer incomplete: unknown status {status:#06x}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FramebufferError {}
+
+impl From<FramebufferError> for JsValue {
+    fn from(error: FramebufferError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Off-screen framebuffer rendering into a color texture, optionally with
+/// a depth or depth/stencil renderbuffer.
+pub struct RenderTarget {
+    framebuffer: WebGlFramebuffer,
+    texture: WebGlTexture,
+    renderbuffer: Option<WebGlRenderbuffer>,
+    depth_stencil: DepthStencil,
+    width: i32,
+    height: i32,
+}
+
+impl RenderTarget {
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        depth_stencil: DepthStencil,
+    ) -> Result<Self, FramebufferError> {
+        let gl = Gl::from_ref(gl);
+        let framebuffer = resources::create_framebuffer(gl);
+        let texture = resources::create_texture(gl);
+        let renderbuffer = match depth_stencil {
+            DepthStencil::None => Some(None),
+            DepthStencil::Depth | DepthStencil::DepthStencil => {
+                resources::create_renderbuffer(gl).map(Some)
+            }
+        };
+        let (framebuffer, texture, renderbuffer) = match (framebuffer, texture, renderbuffer) {
+            (Some(framebuffer), Some(texture), Some(renderbuffer)) => {
+                (framebuffer, texture, renderbuffer)
+            }
+            (framebuffer, texture, renderbuffer) => {
+                let missing = if framebuffer.is_none() {
+                    "framebuffer"
+                } else if texture.is_none() {
+                    "texture"
+                } else {
+                    "renderbuffer"
+                };
+                resources::delete_framebuffer(gl, framebuffer.as_ref());
+                resources::delete_texture(gl, texture.as_ref());
+                resources::delete_renderbuffer(gl, renderbuffer.flatten().as_ref());
+                return Err(FramebufferError::Create(missing));
+            }
+        };
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        for (parameter, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        let mut render_target = Self {
+            framebuffer,
+            texture,
+            renderbuffer,
+            depth_stencil,
+            width: 0,
+            height: 0,
+        };
+        if let Err(error) = render_target.resize(gl, width, height) {
+            render_target.delete(gl);
+            return Err(error);
+        }
+        Ok(render_target)
+    }
+
+    /// Reallocates the attachments for the new size and checks the
+    /// framebuffer is still complete. The content is lost.
+    pub fn resize(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+    ) -> Result<(), FramebufferError> {
+        let gl = Gl::from_ref(gl);
+        // Zero sized attachments are incomplete, keep at least one pixel.
+        self.width = width.max(1);
+        self.height = height.max(1);
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        // Allocating storage without data can only fail for invalid
+        // arguments, which would be reported by the status check below.
+        drop(
+            gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                WebGl2RenderingContext::TEXTURE_2D,
+                0,
+                WebGl2RenderingContext::RGBA8 as i32,
+                self.width,
+                self.height,
+                0,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::UNSIGNED_BYTE,
+                None,
+            ),
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&self.texture),
+            0,
+        );
+
+        if let Some(renderbuffer) = &self.renderbuffer {
+            let (internal_format, attachment) = match self.depth_stencil {
+                DepthStencil::DepthStencil => (
+                    WebGl2RenderingContext::DEPTH24_STENCIL8,
+                    WebGl2RenderingContext::DEPTH_STENCIL_ATTACHMENT,
+                ),
+                _ => (
+                    WebGl2RenderingContext::DEPTH_COMPONENT16,
+                    WebGl2RenderingContext::DEPTH_ATTACHMENT,
+                ),
+            };
+            gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(renderbuffer));
+            gl.renderbuffer_storage(
+                WebGl2RenderingContext::RENDERBUFFER,
+                internal_format,
+                self.width,
+                self.height,
+            );
+            gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+            gl.framebuffer_renderbuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                attachment,
+                WebGl2RenderingContext::RENDERBUFFER,
+                Some(renderbuffer),
+            );
+        }
+
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+
+        match FramebufferError::from_status(status) {
+            None => Ok(()),
+            Some(error) => Err(error),
+        }
+    }
+
+    /// Directs rendering into this target and sets the viewport to cover it.
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+    }
+
+    /// Directs rendering back to the drawing buffer of the canvas.
+    pub fn bind_default(gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.viewport(0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height());
+    }
+
+    pub fn texture(&self) -> &WebGlTexture {
+        &self.texture
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        resources::delete_framebuffer(gl, Some(&self.framebuffer));
+        resources::delete_texture(gl, Some(&self.texture));
+        resources::delete_renderbuffer(gl, self.renderbuffer.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_framebuffer_has_no_error() {
+        assert_eq!(
+            FramebufferError::from_status(WebGl2RenderingContext::FRAMEBUFFER_COMPLETE),
+            None
+        );
+    }
+
+    #[test]
+    fn incomplete_status_maps_to_error() {
+        assert_eq!(
+            FramebufferError::from_status(
+                WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT
+            ),
+            Some(FramebufferError::MissingAttachment)
+        );
+        assert_eq!(
+            FramebufferError::from_status(WebGl2RenderingContext::FRAMEBUFFER_UNSUPPORTED),
+            Some(FramebufferError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn unknown_status_is_kept() {
+        let error = FramebufferError::from_status(0x1234).unwrap();
+        assert_eq!(error, FramebufferError::Unknown(0x1234));
+        assert_eq!(
+            error.to_string(),
+            "framebuffer incomplete: unknown status 0x1234"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use js_sys::{Array, Reflect};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    HtmlCanvasElement, ResizeObserver, ResizeObserverBoxOptions, ResizeObserverEntry,
+    ResizeObserverOptions, ResizeObserverSize,
+};
+
+/// Size of the drawing buffer for a canvas measuring `width` x `height`
+/// device pixels, scaled down to at most `max_pixels` pixels while keeping
+/// the aspect ratio. Neither side is ever zero.
+pub fn drawing_buffer_size(width: f64, height: f64, max_pixels: Option<u32>) -> (u32, u32) {
+    let (mut width, mut height) = (width.round().max(1.0), height.round().max(1.0));
+    if let Some(max_pixels) = max_pixels {
+        let pixels = width * height;
+        if pixels > max_pixels as f64 {
+            let scale = (max_pixels as f64 / pixels).sqrt();
+            width = (width * scale).floor().max(1.0);
+            height = (height * scale).floor().max(1.0);
+        }
+    }
+    (width as u32, height as u32)
+}
+
+// Browsers without `devicePixelContentBoxSize` (Safari) also reject it as
+// the box to observe.
+fn supports_device_pixel_box() -> bool {
+    Reflect::get(&js_sys::global(), &JsValue::from_str("ResizeObserverEntry"))
+        .and_then(|entry| Reflect::get(&entry, &JsValue::from_str("prototype")))
+        .and_then(|prototype| {
+            Reflect::has(&prototype, &JsValue::from_str("devicePixelContentBoxSize"))
+        })
+        .unwrap_or(false)
+}
+
+// Content box of the entry in device pixels. Without
+// `devicePixelContentBoxSize` the CSS size is multiplied by the
+// `devicePixelRatio`, which may be off by a pixel due to rounding.
+fn device_pixel_size(entry: &ResizeObserverEntry) -> (f64, f64) {
+    if supports_device_pixel_box() {
+        let sizes = entry.device_pixel_content_box_size();
+        if let Some(size) = sizes.get(0).dyn_ref::<ResizeObserverSize>() {
+            return (size.inline_size(), size.block_size());
+        }
+    }
+    let ratio = web_sys::window().unwrap().device_pixel_ratio();
+    let rect = entry.content_rect();
+    (rect.width() * ratio, rect.height() * ratio)
+}
+
+/// Keeps the drawing buffer of a canvas matched to the size it is shown
+/// at, in device pixels, until dropped.
+pub struct CanvasResizer {
+    observer: ResizeObserver,
+    // Called by the observer, has to live as long as it.
+    _callback: Closure<dyn FnMut(Array)>,
+}
+
+impl CanvasResizer {
+    /// Starts observing `canvas`. Whenever the size of its drawing buffer
+    /// changes, `on_resize` is called with the new width and height.
+    ///
+    /// The observer reports the initial size shortly after starting, so
+    /// the drawing buffer is corrected before the next frame.
+    pub fn new<F>(
+        canvas: &HtmlCanvasElement,
+        max_pixels: Option<u32>,
+        mut on_resize: F,
+    ) -> Result<Self, JsValue>
+    where
+        F: FnMut(u32, u32) + 'static,
+    {
+        let canvas2 = canvas.clone();
+        let callback = Closure::<dyn FnMut(Array)>::new(move |entries: Array| {
+            // Only the canvas is observed, the last entry is the latest.
+            let entry = match entries.pop().dyn_into::<ResizeObserverEntry>() {
+                Ok(entry) => entry,
+                Err(_) => return,
+            };
+            let (width, height) = device_pixel_size(&entry);
+            let (width, height) = drawing_buffer_size(width, height, max_pixels);
+            if width == canvas2.width() && height == canvas2.height() {
+                return;
+            }
+            canvas2.set_width(width);
+            canvas2.set_height(height);
+            on_resize(width, height);
+        });
+
+        let observer = ResizeObserver::new(callback.as_ref().unchecked_ref())?;
+        if supports_device_pixel_box() {
+            let options = ResizeObserverOptions::new();
+            options.set_box(ResizeObserverBoxOptions::DevicePixelContentBox);
+            observer.observe_with_options(canvas, &options);
+        } else {
+            observer.observe(canvas);
+        }
+
+        Ok(Self {
+            observer,
+            _callback: callback,
+        })
+    }
+}
+
+impl Drop for CanvasResizer {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_is_rounded_to_whole_pixels() {
+        assert_eq!(drawing_buffer_size(559.6, 419.5, None), (560, 420));
+    }
+
+    #[test]
+    fn empty_canvas_keeps_one_pixel() {
+        assert_eq!(drawing_buffer_size(0.0, 0.0, None), (1, 1));
+        assert_eq!(drawing_buffer_size(300.0, 0.0, Some(1000)), (300, 1));
+    }
+
+    #[test]
+    fn pixel_cap_keeps_aspect_ratio() {
+        let (width, height) = drawing_buffer_size(3840.0, 2160.0, Some(1920 * 1080));
+        assert_eq!((width, height), (1920, 1080));
+
+        let (width, height) = drawing_buffer_size(1000.0, 500.0, Some(20_000));
+        assert!(width * height <= 20_000);
+        assert_eq!(width, 2 * height);
+    }
+
+    #[test]
+    fn small_canvas_is_not_scaled_up() {
+        assert_eq!(
+            drawing_buffer_size(280.0, 210.0, Some(1920 * 1080)),
+            (280, 210)
+        );
+    }
+}
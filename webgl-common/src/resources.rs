@@ -0,0 +1,415 @@
+use crate::gl::Gl;
+use crate::program::{self, ProgramError};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer,
+    WebGlShader, WebGlTexture, WebGlVertexArrayObject,
+};
+
+/// Kinds of objects counted while they are alive, see [`live`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Buffer,
+    Framebuffer,
+    Program,
+    Renderbuffer,
+    Shader,
+    Texture,
+    VertexArray,
+    /// DOM event listener or animation frame callback.
+    Listener,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 8] = [
+        ResourceKind::Buffer,
+        ResourceKind::Framebuffer,
+        ResourceKind::Program,
+        ResourceKind::Renderbuffer,
+        ResourceKind::Shader,
+        ResourceKind::Texture,
+        ResourceKind::VertexArray,
+        ResourceKind::Listener,
+    ];
+}
+
+/// Number of live objects of every [`ResourceKind`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceCounts([usize; ResourceKind::ALL.len()]);
+
+impl ResourceCounts {
+    pub fn get(&self, kind: ResourceKind) -> usize {
+        self.0[kind as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// Kinds with more live objects now than in `before`, together with
+    /// the number of additional objects.
+    pub fn leaked_since(&self, before: &ResourceCounts) -> Vec<(ResourceKind, usize)> {
+        ResourceKind::ALL
+            .into_iter()
+            .filter(|&kind| self.get(kind) > before.get(kind))
+            .map(|kind| (kind, self.get(kind) - before.get(kind)))
+            .collect()
+    }
+}
+
+thread_local! {
+    static LIVE: Cell<ResourceCounts> = Cell::new(ResourceCounts::default());
+    // Live objects owned by a `ResourceRegistry`, by kind and label.
+    static LABELS: RefCell<BTreeMap<(ResourceKind, String), usize>> =
+        const { RefCell::new(BTreeMap::new()) };
+}
+
+pub fn created(kind: ResourceKind) {
+    LIVE.with(|live| {
+        let mut counts = live.get();
+        counts.0[kind as usize] += 1;
+        live.set(counts);
+    });
+}
+
+pub fn released(kind: ResourceKind) {
+    LIVE.with(|live| {
+        let mut counts = live.get();
+        counts.0[kind as usize] = counts.0[kind as usize].saturating_sub(1);
+        live.set(counts);
+    });
+}
+
+/// Objects created through this module, or reported with [`created`],
+/// which were not deleted yet.
+pub fn live() -> ResourceCounts {
+    LIVE.with(Cell::get)
+}
+
+// Counting wrappers around `create*` and `delete*` of the context. Every
+// object has to be deleted exactly once to keep the counts right.
+macro_rules! counted_object {
+    ($create:ident, $delete:ident, $object:ty, $kind:ident) => {
+        pub fn $create(gl: &WebGl2RenderingContext) -> Option<$object> {
+            let object = gl.$create();
+            if object.is_some() {
+                created(ResourceKind::$kind);
+            }
+            object
+        }
+
+        pub fn $delete(gl: &WebGl2RenderingContext, object: Option<&$object>) {
+            if object.is_some() {
+                gl.$delete(object);
+                released(ResourceKind::$kind);
+            }
+        }
+    };
+}
+
+counted_object!(create_buffer, delete_buffer, WebGlBuffer, Buffer);
+counted_object!(
+    create_framebuffer,
+    delete_framebuffer,
+    WebGlFramebuffer,
+    Framebuffer
+);
+counted_object!(create_program, delete_program, WebGlProgram, Program);
+counted_object!(
+    create_renderbuffer,
+    delete_renderbuffer,
+    WebGlRenderbuffer,
+    Renderbuffer
+);
+counted_object!(create_texture, delete_texture, WebGlTexture, Texture);
+counted_object!(
+    create_vertex_array,
+    delete_vertex_array,
+    WebGlVertexArrayObject,
+    VertexArray
+);
+
+pub fn create_shader(gl: &WebGl2RenderingContext, shader_type: u32) -> Option<WebGlShader> {
+    let gl = Gl::from_ref(gl);
+    let shader = gl.create_shader(shader_type);
+    if shader.is_some() {
+        created(ResourceKind::Shader);
+    }
+    shader
+}
+
+pub fn delete_shader(gl: &WebGl2RenderingContext, shader: Option<&WebGlShader>) {
+    let gl = Gl::from_ref(gl);
+    if shader.is_some() {
+        gl.delete_shader(shader);
+        released(ResourceKind::Shader);
+    }
+}
+
+/// Objects owned by a [`ResourceRegistry`] which were not deleted yet,
+/// by kind and label.
+pub fn live_labels() -> BTreeMap<(ResourceKind, String), usize> {
+    LABELS.with(|labels| labels.borrow().clone())
+}
+
+/// Describes `leaks`, as returned by [`Host::stop`](crate::Host::stop),
+/// naming the labels of registered objects of the leaked kinds.
+pub fn leak_report(leaks: &[(ResourceKind, usize)]) -> String {
+    format_leak_report(leaks, &live_labels())
+}
+
+fn format_leak_report(
+    leaks: &[(ResourceKind, usize)],
+    labels: &BTreeMap<(ResourceKind, String), usize>,
+) -> String {
+    let mut report = String::new();
+    for &(kind, count) in leaks {
+        let _ = write!(report, "{kind:?}: {count} not released");
+        let names: Vec<String> = labels
+            .iter()
+            .filter(|((label_kind, _), _)| *label_kind == kind)
+            .map(|((_, label), count)| match count {
+                1 => format!("\"{label}\""),
+                count => format!("\"{label}\" x{count}"),
+            })
+            .collect();
+        if !names.is_empty() {
+            let _ = write!(report, " ({})", names.join(", "));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+fn count_label(kind: ResourceKind, label: &str, delta: isize) {
+    LABELS.with(|labels| {
+        let mut labels = labels.borrow_mut();
+        let key = (kind, label.to_string());
+        let count = labels.get(&key).copied().unwrap_or(0) as isize + delta;
+        if count > 0 {
+            labels.insert(key, count as usize);
+        } else {
+            labels.remove(&key);
+        }
+    });
+}
+
+// Kinds of objects a `ResourceRegistry` owns, the GL objects among the
+// `ResourceKind`s.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ObjectKind {
+    Buffer,
+    Framebuffer,
+    Program,
+    Renderbuffer,
+    Shader,
+    Texture,
+    VertexArray,
+}
+
+impl ObjectKind {
+    fn resource_kind(self) -> ResourceKind {
+        match self {
+            ObjectKind::Buffer => ResourceKind::Buffer,
+            ObjectKind::Framebuffer => ResourceKind::Framebuffer,
+            ObjectKind::Program => ResourceKind::Program,
+            ObjectKind::Renderbuffer => ResourceKind::Renderbuffer,
+            ObjectKind::Shader => ResourceKind::Shader,
+            ObjectKind::Texture => ResourceKind::Texture,
+            ObjectKind::VertexArray => ResourceKind::VertexArray,
+        }
+    }
+}
+
+struct Registered {
+    kind: ObjectKind,
+    label: String,
+    object: JsValue,
+}
+
+/// Owns GL objects created through it and deletes them, newest first,
+/// when cleared or dropped. Every object gets a label naming it in
+/// [`leak_report`].
+pub struct ResourceRegistry {
+    gl: WebGl2RenderingContext,
+    objects: Vec<Registered>,
+}
+
+// Creating functions of the registry, failing with the label of the
+// object which could not be created.
+macro_rules! registered_object {
+    ($create:ident, $object:ty, $kind:ident) => {
+        pub fn $create(&mut self, label: &str) -> Result<$object, JsValue> {
+            let object =
+                $create(&self.gl).ok_or_else(|| creation_error(ResourceKind::$kind, label))?;
+            Ok(self.register(ObjectKind::$kind, label, object))
+        }
+    };
+}
+
+fn creation_error(kind: ResourceKind, label: &str) -> JsValue {
+    JsValue::from_str(&format!("unable to create {kind:?} \"{label}\""))
+}
+
+impl ResourceRegistry {
+    pub fn new(gl: &WebGl2RenderingContext) -> Self {
+        Self {
+            gl: gl.clone(),
+            objects: Vec::new(),
+        }
+    }
+
+    registered_object!(create_buffer, WebGlBuffer, Buffer);
+    registered_object!(create_framebuffer, WebGlFramebuffer, Framebuffer);
+    registered_object!(create_program, WebGlProgram, Program);
+    registered_object!(create_renderbuffer, WebGlRenderbuffer, Renderbuffer);
+    registered_object!(create_texture, WebGlTexture, Texture);
+    registered_object!(create_vertex_array, WebGlVertexArrayObject, VertexArray);
+
+    pub fn create_shader(&mut self, shader_type: u32, label: &str) -> Result<WebGlShader, JsValue> {
+        let shader = create_shader(&self.gl, shader_type)
+            .ok_or_else(|| creation_error(ResourceKind::Shader, label))?;
+        Ok(self.register(ObjectKind::Shader, label, shader))
+    }
+
+    /// Builds a program with [`program::link_program`] and registers it.
+    pub fn link_program(
+        &mut self,
+        label: &str,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<WebGlProgram, ProgramError> {
+        let program = program::link_program(&self.gl, vertex_source, fragment_source)?;
+        Ok(self.register(ObjectKind::Program, label, program))
+    }
+
+    fn register<T: JsCast>(&mut self, kind: ObjectKind, label: &str, object: T) -> T {
+        count_label(kind.resource_kind(), label, 1);
+        self.objects.push(Registered {
+            kind,
+            label: label.to_string(),
+            object: object.as_ref().clone(),
+        });
+        object
+    }
+
+    /// Deletes `object` before the registry is cleared. Objects not owned
+    /// by the registry are left alone.
+    pub fn delete<T: AsRef<JsValue>>(&mut self, object: &T) {
+        let object = object.as_ref();
+        if let Some(index) = self
+            .objects
+            .iter()
+            .rposition(|registered| &registered.object == object)
+        {
+            let registered = self.objects.remove(index);
+            self.delete_registered(&registered);
+        }
+    }
+
+    /// Number of live objects of `kind` with `label`.
+    pub fn count(&self, kind: ResourceKind, label: &str) -> usize {
+        self.objects
+            .iter()
+            .filter(|registered| {
+                registered.kind.resource_kind() == kind && registered.label == label
+            })
+            .count()
+    }
+
+    /// Number of live objects of every kind.
+    pub fn counts(&self) -> ResourceCounts {
+        let mut counts = ResourceCounts::default();
+        for registered in &self.objects {
+            counts.0[registered.kind.resource_kind() as usize] += 1;
+        }
+        counts
+    }
+
+    /// Deletes every object, in reverse order of creation, so that e.g.
+    /// framebuffers go before the textures attached to them.
+    pub fn clear(&mut self) {
+        while let Some(registered) = self.objects.pop() {
+            self.delete_registered(&registered);
+        }
+    }
+
+    fn delete_registered(&self, registered: &Registered) {
+        let gl = &self.gl;
+        let object = &registered.object;
+        match registered.kind {
+            ObjectKind::Buffer => delete_buffer(gl, Some(object.unchecked_ref())),
+            ObjectKind::Framebuffer => delete_framebuffer(gl, Some(object.unchecked_ref())),
+            ObjectKind::Program => delete_program(gl, Some(object.unchecked_ref())),
+            ObjectKind::Renderbuffer => delete_renderbuffer(gl, Some(object.unchecked_ref())),
+            ObjectKind::Shader => delete_shader(gl, Some(object.unchecked_ref())),
+            ObjectKind::Texture => delete_texture(gl, Some(object.unchecked_ref())),
+            ObjectKind::VertexArray => delete_vertex_array(gl, Some(object.unchecked_ref())),
+        }
+        count_label(registered.kind.resource_kind(), &registered.label, -1);
+    }
+}
+
+impl Drop for ResourceRegistry {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaks_are_counted_per_kind() {
+        let before = live();
+        created(ResourceKind::Buffer);
+        created(ResourceKind::Buffer);
+        created(ResourceKind::Texture);
+        released(ResourceKind::Texture);
+
+        assert_eq!(
+            live().leaked_since(&before),
+            vec![(ResourceKind::Buffer, 2)]
+        );
+
+        released(ResourceKind::Buffer);
+        released(ResourceKind::Buffer);
+        assert!(live().leaked_since(&before).is_empty());
+    }
+
+    #[test]
+    fn labels_are_counted_until_released() {
+        count_label(ResourceKind::Program, "glow", 1);
+        count_label(ResourceKind::Program, "glow", 1);
+        count_label(ResourceKind::Program, "sprites", 1);
+        count_label(ResourceKind::Program, "sprites", -1);
+
+        let labels = live_labels();
+        assert_eq!(
+            labels.get(&(ResourceKind::Program, "glow".to_string())),
+            Some(&2)
+        );
+        assert!(!labels.contains_key(&(ResourceKind::Program, "sprites".to_string())));
+    }
+
+    #[test]
+    fn report_names_labels_of_leaked_kinds() {
+        let mut labels = BTreeMap::new();
+        labels.insert((ResourceKind::Buffer, "points".to_string()), 1);
+        labels.insert((ResourceKind::Buffer, "corners".to_string()), 2);
+        labels.insert((ResourceKind::Texture, "font".to_string()), 1);
+
+        let report = format_leak_report(
+            &[(ResourceKind::Buffer, 3), (ResourceKind::Listener, 1)],
+            &labels,
+        );
+        assert_eq!(
+            report,
+            "Buffer: 3 not released (\"corners\" x2, \"points\")\nListener: 1 not released\n"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::capture::flip_rows;
+use crate::gl::Gl;
+use crate::random::Rng;
+use crate::state::StateCache;
+use web_sys::WebGl2RenderingContext;
+
+/// The calls of a WebGL2 context the clearing and scissoring examples
+/// make, so that they draw into a [`Gl`] in the browser and into a
+/// [`SoftwareContext`] natively.
+pub trait ClearContext {
+    fn drawing_buffer_width(&self) -> i32;
+    fn drawing_buffer_height(&self) -> i32;
+    fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32);
+    fn clear_color(&mut self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn clear(&mut self, mask: u32);
+    fn scissor(&mut self, x: i32, y: i32, width: i32, height: i32);
+    fn color_mask(&mut self, red: bool, green: bool, blue: bool, alpha: bool);
+    fn enable(&mut self, capability: u32);
+    fn disable(&mut self, capability: u32);
+}
+
+/// An example drawing only with a [`ClearContext`], run natively by the
+/// headless renderer.
+pub trait SoftwareExample {
+    /// Size of the drawing buffer of every canvas of the example.
+    const CANVAS_SIZES: &'static [[u32; 2]];
+
+    /// Sets the example up on contexts of [`CANVAS_SIZES`](Self::CANVAS_SIZES)
+    /// in the state it is in once the user started it, drawing random
+    /// numbers from `rng`.
+    fn new(contexts: Vec<SoftwareContext>, rng: Rng) -> Self;
+
+    /// Advances the example by `dt` seconds, see
+    /// [`Example::update`](crate::Example::update).
+    fn update(&mut self, _dt: f64) {}
+
+    fn render(&mut self);
+
+    fn contexts(&mut self) -> Vec<&mut SoftwareContext>;
+}
+
+// Calls are spelled out with the inherent methods, which the methods of
+// the trait would shadow. `$size` and `$clear` are the types answering the
+// size of the drawing buffer and clearing, on the context `$context`.
+macro_rules! forward_clear_context {
+    ($type:ty, $size:ty, $clear:ty, |$this:ident| $context:expr) => {
+        impl ClearContext for $type {
+            fn drawing_buffer_width(&self) -> i32 {
+                let $this = self;
+                <$size>::drawing_buffer_width($context)
+            }
+
+            fn drawing_buffer_height(&self) -> i32 {
+                let $this = self;
+                <$size>::drawing_buffer_height($context)
+            }
+
+            fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+                <$type>::viewport(self, x, y, width, height);
+            }
+
+            fn clear_color(&mut self, red: f32, green: f32, blue: f32, alpha: f32) {
+                <$type>::clear_color(self, red, green, blue, alpha);
+            }
+
+            fn clear(&mut self, mask: u32) {
+                let $this = self;
+                <$clear>::clear($context, mask);
+            }
+
+            fn scissor(&mut self, x: i32, y: i32, width: i32, height: i32) {
+                <$type>::scissor(self, x, y, width, height);
+            }
+
+            fn color_mask(&mut self, red: bool, green: bool, blue: bool, alpha: bool) {
+                <$type>::color_mask(self, red, green, blue, alpha);
+            }
+
+            fn enable(&mut self, capability: u32) {
+                <$type>::enable(self, capability);
+            }
+
+            fn disable(&mut self, capability: u32) {
+                <$type>::disable(self, capability);
+            }
+        }
+    };
+}
+
+forward_clear_context!(Gl, WebGl2RenderingContext, Gl, |gl| gl);
+forward_clear_context!(SoftwareContext, SoftwareContext, SoftwareContext, |gl| gl);
+// Only the state is cached, clearing goes straight to the context.
+forward_clear_context!(StateCache, WebGl2RenderingContext, Gl, |cache| cache.gl());
+
+/// A WebGL context drawing into memory, for running the clearing and
+/// scissoring examples and replaying their traces without a browser.
+///
+/// Only the color buffer exists. Clearing honors the scissor test and the
+/// color mask, draw calls are not supported.
+#[derive(Clone, Debug)]
+pub struct SoftwareContext {
+    width: i32,
+    height: i32,
+    // RGBA, rows bottom to top like the drawing buffer of WebGL.
+    pixels: Vec<u8>,
+    clear_color: [f32; 4],
+    scissor_box: [i32; 4],
+    scissor_test: bool,
+    viewport: [i32; 4],
+    color_mask: [bool; 4],
+}
+
+impl SoftwareContext {
+    /// A context with the default state of WebGL and a transparent black
+    /// drawing buffer of `width` x `height` pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width as i32, height as i32);
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            clear_color: [0.0; 4],
+            scissor_box: [0, 0, width, height],
+            scissor_test: false,
+            viewport: [0, 0, width, height],
+            color_mask: [true; 4],
+        }
+    }
+
+    pub fn drawing_buffer_width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn drawing_buffer_height(&self) -> i32 {
+        self.height
+    }
+
+    /// Replaces the drawing buffer with a transparent black one of the new
+    /// size, like setting the size of a canvas does. The state is kept.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width as i32;
+        self.height = height as i32;
+        self.pixels = vec![0; width as usize * height as usize * 4];
+    }
+
+    /// Clears the drawing buffer to transparent black, as browsers do
+    /// after presenting a frame unless the context was created with
+    /// `preserveDrawingBuffer`.
+    pub fn present(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    pub fn clear_color(&mut self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.clear_color = [red, green, blue, alpha];
+    }
+
+    pub fn scissor(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.scissor_box = [x, y, width, height];
+    }
+
+    pub fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.viewport = [x, y, width, height];
+    }
+
+    pub fn color_mask(&mut self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.color_mask = [red, green, blue, alpha];
+    }
+
+    /// Enables a capability, only `SCISSOR_TEST` has an effect.
+    pub fn enable(&mut self, capability: u32) {
+        if capability == WebGl2RenderingContext::SCISSOR_TEST {
+            self.scissor_test = true;
+        }
+    }
+
+    pub fn disable(&mut self, capability: u32) {
+        if capability == WebGl2RenderingContext::SCISSOR_TEST {
+            self.scissor_test = false;
+        }
+    }
+
+    pub fn is_enabled(&self, capability: u32) -> bool {
+        capability == WebGl2RenderingContext::SCISSOR_TEST && self.scissor_test
+    }
+
+    pub fn get_viewport(&self) -> [i32; 4] {
+        self.viewport
+    }
+
+    pub fn get_scissor_box(&self) -> [i32; 4] {
+        self.scissor_box
+    }
+
+    /// Clears the color buffer if `mask` contains `COLOR_BUFFER_BIT`,
+    /// depth and stencil bits are ignored.
+    pub fn clear(&mut self, mask: u32) {
+        if mask & WebGl2RenderingContext::COLOR_BUFFER_BIT == 0 {
+            return;
+        }
+
+        let (mut left, mut bottom, mut right, mut top) = (0, 0, self.width, self.height);
+        if self.scissor_test {
+            let [x, y, width, height] = self.scissor_box;
+            left = left.max(x);
+            bottom = bottom.max(y);
+            right = right.min(x.saturating_add(width.max(0)));
+            top = top.min(y.saturating_add(height.max(0)));
+        }
+
+        let color = self
+            .clear_color
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+        for y in bottom..top {
+            for x in left..right {
+                let start = (y * self.width + x) as usize * 4;
+                let pixel = &mut self.pixels[start..start + 4];
+                for channel in 0..4 {
+                    if self.color_mask[channel] {
+                        pixel[channel] = color[channel];
+                    }
+                }
+            }
+        }
+    }
+
+    /// Color of the pixel at `x`, `y`, counted from the bottom left like
+    /// `readPixels` does.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[start..start + 4].try_into().unwrap()
+    }
+
+    /// The drawing buffer as a top-down RGBA image, as
+    /// [`read_drawing_buffer`](crate::capture::read_drawing_buffer)
+    /// returns it for a real context.
+    pub fn read_drawing_buffer(&self) -> (u32, u32, Vec<u8>) {
+        let mut pixels = self.pixels.clone();
+        flip_rows(&mut pixels, self.width as usize, self.height as usize);
+        (self.width as u32, self.height as u32, pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: u32 = WebGl2RenderingContext::COLOR_BUFFER_BIT;
+    const SCISSOR_TEST: u32 = WebGl2RenderingContext::SCISSOR_TEST;
+
+    #[test]
+    fn starts_transparent_black() {
+        let gl = SoftwareContext::new(4, 3);
+        assert_eq!(gl.pixel(3, 2), [0, 0, 0, 0]);
+        assert_eq!(gl.get_viewport(), [0, 0, 4, 3]);
+    }
+
+    #[test]
+    fn clear_fills_the_drawing_buffer() {
+        let mut gl = SoftwareContext::new(4, 3);
+        gl.clear_color(0.0, 1.0, 0.0, 1.0);
+        gl.clear(COLOR);
+        assert_eq!(gl.pixel(0, 0), [0, 255, 0, 255]);
+        assert_eq!(gl.pixel(3, 2), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn clear_without_color_bit_does_nothing() {
+        let mut gl = SoftwareContext::new(2, 2);
+        gl.clear_color(1.0, 1.0, 1.0, 1.0);
+        gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+        assert_eq!(gl.pixel(0, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn scissor_test_limits_clear() {
+        let mut gl = SoftwareContext::new(10, 10);
+        gl.enable(SCISSOR_TEST);
+        gl.scissor(2, 3, 4, 5);
+        gl.clear_color(1.0, 1.0, 0.0, 1.0);
+        gl.clear(COLOR);
+
+        assert_eq!(gl.pixel(2, 3), [255, 255, 0, 255]);
+        assert_eq!(gl.pixel(5, 7), [255, 255, 0, 255]);
+        assert_eq!(gl.pixel(1, 3), [0, 0, 0, 0]);
+        assert_eq!(gl.pixel(6, 3), [0, 0, 0, 0]);
+        assert_eq!(gl.pixel(2, 8), [0, 0, 0, 0]);
+
+        // The box is ignored once the test is disabled.
+        gl.disable(SCISSOR_TEST);
+        gl.clear(COLOR);
+        assert_eq!(gl.pixel(9, 9), [255, 255, 0, 255]);
+    }
+
+    #[test]
+    fn scissor_box_is_clipped_to_the_drawing_buffer() {
+        let mut gl = SoftwareContext::new(4, 4);
+        gl.enable(SCISSOR_TEST);
+        gl.scissor(-2, 2, 100, 100);
+        gl.clear_color(1.0, 0.0, 0.0, 1.0);
+        gl.clear(COLOR);
+        assert_eq!(gl.pixel(0, 2), [255, 0, 0, 255]);
+        assert_eq!(gl.pixel(3, 3), [255, 0, 0, 255]);
+        assert_eq!(gl.pixel(0, 1), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn color_mask_keeps_masked_channels() {
+        let mut gl = SoftwareContext::new(1, 1);
+        gl.clear_color(0.2, 0.4, 0.6, 1.0);
+        gl.clear(COLOR);
+        gl.color_mask(false, true, false, true);
+        gl.clear_color(1.0, 1.0, 1.0, 0.5);
+        gl.clear(COLOR);
+        assert_eq!(gl.pixel(0, 0), [51, 255, 153, 128]);
+    }
+
+    #[test]
+    fn drawing_buffer_is_read_top_down() {
+        let mut gl = SoftwareContext::new(1, 2);
+        gl.enable(SCISSOR_TEST);
+        gl.scissor(0, 1, 1, 1);
+        gl.clear_color(1.0, 1.0, 1.0, 1.0);
+        gl.clear(COLOR);
+
+        let (width, height, pixels) = gl.read_drawing_buffer();
+        assert_eq!((width, height), (1, 2));
+        assert_eq!(pixels, [255, 255, 255, 255, 0, 0, 0, 0]);
+    }
+}
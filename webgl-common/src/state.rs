@@ -0,0 +1,285 @@
+use crate::gl::Gl;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram};
+
+/// Pieces of state tracked by a [`StateCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StateCall {
+    ClearColor,
+    Scissor,
+    Viewport,
+    ColorMask,
+    /// `enable` and `disable`.
+    Capability,
+    UseProgram,
+    BindBuffer,
+}
+
+impl StateCall {
+    pub const ALL: [StateCall; 7] = [
+        StateCall::ClearColor,
+        StateCall::Scissor,
+        StateCall::Viewport,
+        StateCall::ColorMask,
+        StateCall::Capability,
+        StateCall::UseProgram,
+        StateCall::BindBuffer,
+    ];
+}
+
+/// Number of calls a [`StateCache`] passed on to the context and number
+/// of calls it skipped because they would not have changed anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateCounters {
+    issued: [usize; StateCall::ALL.len()],
+    skipped: [usize; StateCall::ALL.len()],
+}
+
+impl StateCounters {
+    pub fn issued(&self, call: StateCall) -> usize {
+        self.issued[call as usize]
+    }
+
+    pub fn skipped(&self, call: StateCall) -> usize {
+        self.skipped[call as usize]
+    }
+
+    pub fn total_issued(&self) -> usize {
+        self.issued.iter().sum()
+    }
+
+    pub fn total_skipped(&self) -> usize {
+        self.skipped.iter().sum()
+    }
+
+    fn count(&mut self, call: StateCall, issued: bool) {
+        if issued {
+            self.issued[call as usize] += 1;
+        } else {
+            self.skipped[call as usize] += 1;
+        }
+    }
+}
+
+// Last value set, `None` until the first call or after invalidating,
+// when the value held by the context is unknown.
+#[derive(Debug)]
+struct Cached<T>(Option<T>);
+
+impl<T: PartialEq> Cached<T> {
+    // Whether `value` differs from the cached one, which it replaces.
+    fn update(&mut self, value: T) -> bool {
+        if self.0.as_ref() == Some(&value) {
+            return false;
+        }
+        self.0 = Some(value);
+        true
+    }
+}
+
+impl<T> Default for Cached<T> {
+    fn default() -> Self {
+        Cached(None)
+    }
+}
+
+// The same for state with one value per target, e.g. capabilities.
+#[derive(Debug)]
+struct CachedByTarget<T>(Vec<(u32, T)>);
+
+impl<T: PartialEq> CachedByTarget<T> {
+    fn update(&mut self, target: u32, value: T) -> bool {
+        match self.0.iter_mut().find(|(cached, _)| *cached == target) {
+            Some((_, cached)) if *cached == value => false,
+            Some((_, cached)) => {
+                *cached = value;
+                true
+            }
+            None => {
+                self.0.push((target, value));
+                true
+            }
+        }
+    }
+}
+
+impl<T> Default for CachedByTarget<T> {
+    fn default() -> Self {
+        CachedByTarget(Vec::new())
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    clear_color: Cached<[f32; 4]>,
+    scissor: Cached<[i32; 4]>,
+    viewport: Cached<[i32; 4]>,
+    color_mask: Cached<[bool; 4]>,
+    capabilities: CachedByTarget<bool>,
+    program: Cached<Option<WebGlProgram>>,
+    buffers: CachedByTarget<Option<WebGlBuffer>>,
+}
+
+/// Sets state of a context only when it changes.
+///
+/// The cache assumes it is the only one changing the tracked state. After
+/// the context is changed directly, e.g. by a [`RenderTarget`] setting the
+/// viewport or by the [`Host`] setting it before [`Example::resize`],
+/// [`invalidate`](Self::invalidate) has to be called.
+///
+/// [`RenderTarget`]: crate::render_target::RenderTarget
+/// [`Host`]: crate::example::Host
+/// [`Example::resize`]: crate::example::Example::resize
+pub struct StateCache {
+    gl: Gl,
+    state: State,
+    counters: StateCounters,
+}
+
+impl StateCache {
+    pub fn new(gl: &WebGl2RenderingContext) -> Self {
+        Self {
+            gl: Gl::new(gl.clone()),
+            state: State::default(),
+            counters: StateCounters::default(),
+        }
+    }
+
+    /// The context, for calls which are not cached.
+    pub fn gl(&self) -> &Gl {
+        &self.gl
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn clear_color(&mut self, red: f32, green: f32, blue: f32, alpha: f32) {
+        if self.track(StateCall::ClearColor, |state| {
+            state.clear_color.update([red, green, blue, alpha])
+        }) {
+            self.gl.clear_color(red, green, blue, alpha);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn scissor(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        if self.track(StateCall::Scissor, |state| {
+            state.scissor.update([x, y, width, height])
+        }) {
+            self.gl.scissor(x, y, width, height);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        if self.track(StateCall::Viewport, |state| {
+            state.viewport.update([x, y, width, height])
+        }) {
+            self.gl.viewport(x, y, width, height);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn color_mask(&mut self, red: bool, green: bool, blue: bool, alpha: bool) {
+        if self.track(StateCall::ColorMask, |state| {
+            state.color_mask.update([red, green, blue, alpha])
+        }) {
+            self.gl.color_mask(red, green, blue, alpha);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn enable(&mut self, capability: u32) {
+        if self.track(StateCall::Capability, |state| {
+            state.capabilities.update(capability, true)
+        }) {
+            self.gl.enable(capability);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn disable(&mut self, capability: u32) {
+        if self.track(StateCall::Capability, |state| {
+            state.capabilities.update(capability, false)
+        }) {
+            self.gl.disable(capability);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn use_program(&mut self, program: Option<&WebGlProgram>) {
+        if self.track(StateCall::UseProgram, |state| {
+            state.program.update(program.cloned())
+        }) {
+            self.gl.use_program(program);
+        }
+    }
+
+    #[cfg_attr(feature = "debug-gl", track_caller)]
+    pub fn bind_buffer(&mut self, target: u32, buffer: Option<&WebGlBuffer>) {
+        if self.track(StateCall::BindBuffer, |state| {
+            state.buffers.update(target, buffer.cloned())
+        }) {
+            self.gl.bind_buffer(target, buffer);
+        }
+    }
+
+    /// Forgets all cached state, so that the next call of every kind is
+    /// passed on to the context.
+    pub fn invalidate(&mut self) {
+        self.state = State::default();
+    }
+
+    pub fn counters(&self) -> StateCounters {
+        self.counters
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.counters = StateCounters::default();
+    }
+
+    fn track(&mut self, call: StateCall, update: impl FnOnce(&mut State) -> bool) -> bool {
+        let changed = update(&mut self.state);
+        self.counters.count(call, changed);
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_value_is_always_set() {
+        let mut clear_color = Cached::default();
+        assert!(clear_color.update([0.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn same_value_is_skipped() {
+        let mut scissor = Cached::default();
+        assert!(scissor.update([40, 20, 60, 130]));
+        assert!(!scissor.update([40, 20, 60, 130]));
+        assert!(scissor.update([40, 20, 60, 131]));
+    }
+
+    #[test]
+    fn targets_are_cached_separately() {
+        let mut capabilities = CachedByTarget::default();
+        assert!(capabilities.update(WebGl2RenderingContext::SCISSOR_TEST, true));
+        assert!(capabilities.update(WebGl2RenderingContext::BLEND, true));
+        assert!(!capabilities.update(WebGl2RenderingContext::SCISSOR_TEST, true));
+        assert!(capabilities.update(WebGl2RenderingContext::BLEND, false));
+    }
+
+    #[test]
+    fn counters_split_issued_and_skipped() {
+        let mut counters = StateCounters::default();
+        counters.count(StateCall::Scissor, true);
+        counters.count(StateCall::Scissor, false);
+        counters.count(StateCall::Scissor, false);
+        counters.count(StateCall::ClearColor, true);
+
+        assert_eq!(counters.issued(StateCall::Scissor), 1);
+        assert_eq!(counters.skipped(StateCall::Scissor), 2);
+        assert_eq!(counters.total_issued(), 2);
+        assert_eq!(counters.total_skipped(), 2);
+    }
+}
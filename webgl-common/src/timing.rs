@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Interval between frames of a 60 Hz display, in milliseconds.
+pub const FRAME_INTERVAL_60HZ: f64 = 1000.0 / 60.0;
+
+/// Frame times of the last frames of an animation, from the timestamps of
+/// the frames and the CPU time spent on each of them, all in milliseconds.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    capacity: usize,
+    target_interval: f64,
+    last_timestamp: Option<f64>,
+    // Time from the previous frame and CPU time of the last frames,
+    // oldest first.
+    intervals: VecDeque<f64>,
+    work: VecDeque<f64>,
+    frames: u64,
+    dropped: u64,
+}
+
+impl FrameStats {
+    /// Keeps the last `capacity` frames, expecting a frame every
+    /// `target_interval` milliseconds.
+    pub fn new(capacity: usize, target_interval: f64) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            target_interval,
+            last_timestamp: None,
+            intervals: VecDeque::with_capacity(capacity),
+            work: VecDeque::with_capacity(capacity),
+            frames: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Records a frame which started at `timestamp` and kept the CPU busy
+    /// for `work` milliseconds. The first frame has no interval.
+    pub fn record(&mut self, timestamp: f64, work: f64) {
+        if let Some(last) = self.last_timestamp {
+            let interval = timestamp - last;
+            self.dropped += self.missed_frames(interval);
+            push_limited(&mut self.intervals, interval, self.capacity);
+        }
+        self.last_timestamp = Some(timestamp);
+        push_limited(&mut self.work, work, self.capacity);
+        self.frames += 1;
+    }
+
+    // Frames the display showed without a new one in an interval, half a
+    // frame of jitter is tolerated.
+    fn missed_frames(&self, interval: f64) -> u64 {
+        if interval <= self.target_interval * 1.5 {
+            return 0;
+        }
+        (interval / self.target_interval).round() as u64 - 1
+    }
+
+    /// Number of frames recorded since the start.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Number of frames dropped since the start.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Time between the last frames, oldest first.
+    pub fn intervals(&self) -> impl ExactSizeIterator<Item = f64> + '_ {
+        self.intervals.iter().copied()
+    }
+
+    pub fn target_interval(&self) -> f64 {
+        self.target_interval
+    }
+
+    /// Average time between the last frames, `None` before the second
+    /// frame.
+    pub fn mean_interval(&self) -> Option<f64> {
+        mean(&self.intervals)
+    }
+
+    pub fn fps(&self) -> Option<f64> {
+        self.mean_interval()
+            .filter(|&interval| interval > 0.0)
+            .map(|interval| 1000.0 / interval)
+    }
+
+    /// Average CPU time of the last frames.
+    pub fn mean_work(&self) -> Option<f64> {
+        mean(&self.work)
+    }
+
+    pub fn max_work(&self) -> Option<f64> {
+        self.work.iter().copied().reduce(f64::max)
+    }
+
+    /// Counts the last intervals in `bins` bins of `bin_width`
+    /// milliseconds, longer intervals are counted in the last bin.
+    pub fn histogram(&self, bins: usize, bin_width: f64) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        for interval in &self.intervals {
+            let bin = (interval / bin_width).max(0.0) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        counts
+    }
+}
+
+fn push_limited(values: &mut VecDeque<f64>, value: f64, capacity: usize) {
+    if values.len() == capacity {
+        values.pop_front();
+    }
+    values.push_back(value);
+}
+
+fn mean(values: &VecDeque<f64>) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fps() {
+            Some(fps) => write!(f, "{fps:.1} fps")?,
+            None => write!(f, "-- fps")?,
+        }
+        if let (Some(mean), Some(max)) = (self.mean_work(), self.max_work()) {
+            write!(f, ", CPU {mean:.2} ms (max {max:.2} ms)")?;
+        }
+        write!(f, ", {} dropped", self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(timestamps: &[f64]) -> FrameStats {
+        let mut stats = FrameStats::new(4, FRAME_INTERVAL_60HZ);
+        for &timestamp in timestamps {
+            stats.record(timestamp, 1.0);
+        }
+        stats
+    }
+
+    #[test]
+    fn first_frame_has_no_interval() {
+        let stats = stats(&[100.0]);
+        assert_eq!(stats.frames(), 1);
+        assert_eq!(stats.fps(), None);
+        assert_eq!(stats.mean_work(), Some(1.0));
+    }
+
+    #[test]
+    fn fps_of_steady_frames() {
+        let stats = stats(&[0.0, 20.0, 40.0, 60.0]);
+        assert_eq!(stats.mean_interval(), Some(20.0));
+        assert_eq!(stats.fps(), Some(50.0));
+        assert_eq!(stats.dropped_frames(), 0);
+    }
+
+    #[test]
+    fn long_intervals_drop_frames() {
+        let frame = FRAME_INTERVAL_60HZ;
+        // Jitter is tolerated, a gap of three frames drops two.
+        let stats = stats(&[0.0, frame * 1.4, frame * 4.4]);
+        assert_eq!(stats.dropped_frames(), 2);
+    }
+
+    #[test]
+    fn only_the_last_frames_are_kept() {
+        let mut stats = stats(&[0.0, 100.0, 200.0, 300.0, 400.0]);
+        stats.record(410.0, 5.0);
+        assert_eq!(
+            stats.intervals().collect::<Vec<_>>(),
+            [100.0, 100.0, 100.0, 10.0]
+        );
+        assert_eq!(stats.max_work(), Some(5.0));
+        // Dropped frames are counted since the start.
+        assert_eq!(stats.dropped_frames(), 20);
+    }
+
+    #[test]
+    fn histogram_counts_long_intervals_in_last_bin() {
+        let stats = stats(&[0.0, 4.0, 10.0, 60.0, 61.0]);
+        assert_eq!(stats.histogram(3, 5.0), [2, 1, 1]);
+    }
+
+    #[test]
+    fn summary() {
+        let stats = stats(&[0.0, 20.0]);
+        assert_eq!(
+            stats.to_string(),
+            "50.0 fps, CPU 1.00 ms (max 1.00 ms), 0 dropped"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Traces of the calls the examples make to WebGL, recorded in the browser
+//! and replayed either in the browser or with the software rasterizer.
+
+use std::fmt;
+
+mod binary;
+mod json;
+mod record;
+pub mod replay;
+
+pub use record::{
+    end_frame, finish, is_recording, requested_frames, start, trace_key_frames, TRACE_FRAMES,
+    TRACE_KEY,
+};
+
+/// Element type of a typed array passed to WebGL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrayKind {
+    Int8,
+    Uint8,
+    Uint8Clamped,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl ArrayKind {
+    pub const ALL: [ArrayKind; 9] = [
+        ArrayKind::Int8,
+        ArrayKind::Uint8,
+        ArrayKind::Uint8Clamped,
+        ArrayKind::Int16,
+        ArrayKind::Uint16,
+        ArrayKind::Int32,
+        ArrayKind::Uint32,
+        ArrayKind::Float32,
+        ArrayKind::Float64,
+    ];
+
+    /// Name of the JavaScript constructor, e.g. `Float32Array`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArrayKind::Int8 => "Int8Array",
+            ArrayKind::Uint8 => "Uint8Array",
+            ArrayKind::Uint8Clamped => "Uint8ClampedArray",
+            ArrayKind::Int16 => "Int16Array",
+            ArrayKind::Uint16 => "Uint16Array",
+            ArrayKind::Int32 => "Int32Array",
+            ArrayKind::Uint32 => "Uint32Array",
+            ArrayKind::Float32 => "Float32Array",
+            ArrayKind::Float64 => "Float64Array",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ArrayKind> {
+        ArrayKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn element_size(self) -> usize {
+        match self {
+            ArrayKind::Int8 | ArrayKind::Uint8 | ArrayKind::Uint8Clamped => 1,
+            ArrayKind::Int16 | ArrayKind::Uint16 => 2,
+            ArrayKind::Int32 | ArrayKind::Uint32 | ArrayKind::Float32 => 4,
+            ArrayKind::Float64 => 8,
+        }
+    }
+
+    /// Elements of the little endian `bytes`, converted to numbers.
+    pub fn elements(self, bytes: &[u8]) -> Vec<f64> {
+        bytes
+            .chunks_exact(self.element_size())
+            .map(|chunk| match self {
+                ArrayKind::Int8 => chunk[0] as i8 as f64,
+                ArrayKind::Uint8 | ArrayKind::Uint8Clamped => chunk[0] as f64,
+                ArrayKind::Int16 => i16::from_le_bytes([chunk[0], chunk[1]]) as f64,
+                ArrayKind::Uint16 => u16::from_le_bytes([chunk[0], chunk[1]]) as f64,
+                ArrayKind::Int32 => i32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+                ArrayKind::Uint32 => u32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+                ArrayKind::Float32 => f32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+                ArrayKind::Float64 => f64::from_le_bytes(chunk.try_into().unwrap()),
+            })
+            .collect()
+    }
+
+    /// Inverse of [`elements`](Self::elements).
+    pub fn bytes(self, elements: &[f64]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(elements.len() * self.element_size());
+        for &element in elements {
+            match self {
+                ArrayKind::Int8 => bytes.push(element as i8 as u8),
+                ArrayKind::Uint8 | ArrayKind::Uint8Clamped => bytes.push(element as u8),
+                ArrayKind::Int16 => bytes.extend((element as i16).to_le_bytes()),
+                ArrayKind::Uint16 => bytes.extend((element as u16).to_le_bytes()),
+                ArrayKind::Int32 => bytes.extend((element as i32).to_le_bytes()),
+                ArrayKind::Uint32 => bytes.extend((element as u32).to_le_bytes()),
+                ArrayKind::Float32 => bytes.extend((element as f32).to_le_bytes()),
+                ArrayKind::Float64 => bytes.extend(element.to_le_bytes()),
+            }
+        }
+        bytes
+    }
+}
+
+/// Argument or result of a traced call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// `null` or `undefined`.
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// WebGL object, numbered in the order the trace first saw it.
+    Object(u32),
+    /// Contents of a typed array, e.g. the data of `bufferData`.
+    Array {
+        kind: ArrayKind,
+        bytes: Vec<u8>,
+    },
+    /// Plain JavaScript array.
+    List(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Number(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value:?}"),
+            Value::Object(id) => write!(f, "#{id}"),
+            Value::Array { kind, bytes } => {
+                let elements = kind.elements(bytes);
+                write!(f, "{}[", kind.name())?;
+                // Buffer contents can be huge, the start is enough to
+                // recognize them.
+                for (index, element) in elements.iter().take(8).enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if *kind == ArrayKind::Float32 {
+                        write!(f, "{}", *element as f32)?;
+                    } else {
+                        write!(f, "{element}")?;
+                    }
+                }
+                if elements.len() > 8 {
+                    write!(f, ", … {} more", elements.len() - 8)?;
+                }
+                write!(f, "]")
+            }
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// One call of a method of a WebGL context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    /// Index into [`Trace::contexts`].
+    pub context: usize,
+    /// Method name as in JavaScript, e.g. `clearColor`.
+    pub name: String,
+    pub args: Vec<Value>,
+    pub result: Value,
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        write!(f, ")")?;
+        if self.result != Value::Null {
+            write!(f, " -> {}", self.result)?;
+        }
+        Ok(())
+    }
+}
+
+/// Calls made since the previous frame, ending with those of rendering it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub calls: Vec<Call>,
+}
+
+/// Recorded calls, frame by frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    /// Drawing buffer size of every traced context when it was first seen.
+    pub contexts: Vec<[u32; 2]>,
+    pub frames: Vec<Frame>,
+}
+
+/// Failure to decode a trace file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceError(pub String);
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid trace: {}", self.0)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl Trace {
+    /// Encodes the trace as human readable JSON.
+    pub fn to_json(&self) -> String {
+        json::encode(self)
+    }
+
+    /// Encodes the trace in a compact binary form, for traces with many
+    /// frames or large buffers.
+    pub fn to_binary(&self) -> Vec<u8> {
+        binary::encode(self)
+    }
+
+    /// Decodes a trace in either of the two encodings.
+    pub fn decode(bytes: &[u8]) -> Result<Trace, TraceError> {
+        if bytes.starts_with(binary::MAGIC) {
+            binary::decode(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|_| TraceError("neither binary nor UTF-8 JSON".to_string()))?;
+            json::decode(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn sample_trace() -> Trace {
+        let call = |name: &str, args: Vec<Value>, result: Value| Call {
+            context: 0,
+            name: name.to_string(),
+            args,
+            result,
+        };
+        Trace {
+            contexts: vec![[280, 210], [300, 150]],
+            frames: vec![
+                Frame {
+                    calls: vec![
+                        call("createBuffer", vec![], Value::Object(1)),
+                        call(
+                            "bufferData",
+                            vec![
+                                Value::Number(34962.0),
+                                Value::Array {
+                                    kind: ArrayKind::Float32,
+                                    bytes: ArrayKind::Float32.bytes(&[0.5, -1.0, 0.1]),
+                                },
+                                Value::Number(35044.0),
+                            ],
+                            Value::Null,
+                        ),
+                        call(
+                            "shaderSource",
+                            vec![
+                                Value::Object(2),
+                                Value::String("void main() {\n}\n\"".into()),
+                            ],
+                            Value::Null,
+                        ),
+                    ],
+                },
+                Frame {
+                    calls: vec![
+                        Call {
+                            context: 1,
+                            ..call(
+                                "colorMask",
+                                vec![Value::Bool(true), Value::Bool(false)],
+                                Value::Null,
+                            )
+                        },
+                        call(
+                            "drawBuffers",
+                            vec![Value::List(vec![Value::Number(36064.0)])],
+                            Value::Null,
+                        ),
+                        call("getError", vec![], Value::Number(0.0)),
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn array_elements_round_trip() {
+        for kind in ArrayKind::ALL {
+            let elements = [0.0, 1.0, 2.0, 100.0];
+            assert_eq!(kind.elements(&kind.bytes(&elements)), elements, "{kind:?}");
+        }
+        assert_eq!(ArrayKind::from_name("Uint16Array"), Some(ArrayKind::Uint16));
+        assert_eq!(ArrayKind::from_name("DataView"), None);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let trace = sample_trace();
+        assert_eq!(Trace::decode(trace.to_json().as_bytes()), Ok(trace));
+    }
+
+    #[test]
+    fn binary_round_trips() {
+        let trace = sample_trace();
+        assert_eq!(Trace::decode(&trace.to_binary()), Ok(trace));
+    }
+
+    #[test]
+    fn truncated_binary_is_an_error() {
+        let bytes = sample_trace().to_binary();
+        assert!(Trace::decode(&bytes[..bytes.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn calls_are_displayed_like_javascript() {
+        let trace = sample_trace();
+        let calls: Vec<String> = trace.frames[0].calls.iter().map(Call::to_string).collect();
+        assert_eq!(calls[0], "createBuffer() -> #1");
+        assert_eq!(
+            calls[1],
+            "bufferData(34962, Float32Array[0.5, -1, 0.1], 35044)"
+        );
+    }
+}
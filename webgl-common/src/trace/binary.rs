@@ -0,0 +1,196 @@
+// Binary encoding of traces. All integers are little endian `u32`,
+// strings and byte arrays are prefixed with their length and every value
+// with a tag byte.
+
+use super::{ArrayKind, Call, Frame, Trace, TraceError, Value};
+
+pub const MAGIC: &[u8] = b"GLTRACE\0";
+const VERSION: u32 = 1;
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const NUMBER: u8 = 3;
+const STRING: u8 = 4;
+const OBJECT: u8 = 5;
+const ARRAY: u8 = 6;
+const LIST: u8 = 7;
+
+pub fn encode(trace: &Trace) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    put_u32(&mut out, VERSION);
+    put_u32(&mut out, trace.contexts.len() as u32);
+    for [width, height] in &trace.contexts {
+        put_u32(&mut out, *width);
+        put_u32(&mut out, *height);
+    }
+    put_u32(&mut out, trace.frames.len() as u32);
+    for frame in &trace.frames {
+        put_u32(&mut out, frame.calls.len() as u32);
+        for call in &frame.calls {
+            put_u32(&mut out, call.context as u32);
+            put_bytes(&mut out, call.name.as_bytes());
+            put_u32(&mut out, call.args.len() as u32);
+            for arg in &call.args {
+                put_value(&mut out, arg);
+            }
+            put_value(&mut out, &call.result);
+        }
+    }
+    out
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend(value.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend(bytes);
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(NULL),
+        Value::Bool(false) => out.push(FALSE),
+        Value::Bool(true) => out.push(TRUE),
+        Value::Number(value) => {
+            out.push(NUMBER);
+            out.extend(value.to_le_bytes());
+        }
+        Value::String(value) => {
+            out.push(STRING);
+            put_bytes(out, value.as_bytes());
+        }
+        Value::Object(id) => {
+            out.push(OBJECT);
+            put_u32(out, *id);
+        }
+        Value::Array { kind, bytes } => {
+            out.push(ARRAY);
+            out.push(*kind as u8);
+            put_bytes(out, bytes);
+        }
+        Value::List(values) => {
+            out.push(LIST);
+            put_u32(out, values.len() as u32);
+            for value in values {
+                put_value(out, value);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], TraceError> {
+        if self.bytes.len() < length {
+            return Err(TraceError("unexpected end of binary trace".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, TraceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, TraceError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    // Counts are checked against the remaining length, so that a corrupt
+    // count fails instead of allocating gigabytes.
+    fn count(&mut self) -> Result<usize, TraceError> {
+        let count = self.u32()? as usize;
+        if count > self.bytes.len() {
+            return Err(TraceError(format!("count {count} exceeds the trace")));
+        }
+        Ok(count)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], TraceError> {
+        let length = self.count()?;
+        self.take(length)
+    }
+
+    fn string(&mut self) -> Result<String, TraceError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| TraceError("string is not UTF-8".to_string()))
+    }
+
+    fn value(&mut self) -> Result<Value, TraceError> {
+        Ok(match self.u8()? {
+            NULL => Value::Null,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            NUMBER => Value::Number(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            STRING => Value::String(self.string()?),
+            OBJECT => Value::Object(self.u32()?),
+            ARRAY => {
+                let kind = self.u8()?;
+                let kind = *ArrayKind::ALL
+                    .get(kind as usize)
+                    .ok_or_else(|| TraceError(format!("unknown array type {kind}")))?;
+                Value::Array {
+                    kind,
+                    bytes: self.bytes()?.to_vec(),
+                }
+            }
+            LIST => {
+                let count = self.count()?;
+                Value::List((0..count).map(|_| self.value()).collect::<Result<_, _>>()?)
+            }
+            tag => return Err(TraceError(format!("unknown value tag {tag}"))),
+        })
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Trace, TraceError> {
+    let mut reader = Reader {
+        bytes: &bytes[MAGIC.len()..],
+    };
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(TraceError(format!("unsupported version {version}")));
+    }
+
+    let context_count = reader.count()?;
+    let contexts = (0..context_count)
+        .map(|_| Ok([reader.u32()?, reader.u32()?]))
+        .collect::<Result<_, TraceError>>()?;
+
+    let frame_count = reader.count()?;
+    let mut frames = Vec::with_capacity(frame_count);
+    for _ in 0..frame_count {
+        let call_count = reader.count()?;
+        let mut calls = Vec::with_capacity(call_count);
+        for _ in 0..call_count {
+            let context = reader.u32()? as usize;
+            let name = reader.string()?;
+            let arg_count = reader.count()?;
+            let args = (0..arg_count)
+                .map(|_| reader.value())
+                .collect::<Result<_, _>>()?;
+            let result = reader.value()?;
+            calls.push(Call {
+                context,
+                name,
+                args,
+                result,
+            });
+        }
+        frames.push(Frame { calls });
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(TraceError(
+            "trailing bytes after the last frame".to_string(),
+        ));
+    }
+    Ok(Trace { contexts, frames })
+}
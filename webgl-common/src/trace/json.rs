@@ -0,0 +1,453 @@
+// JSON encoding of traces. Values map to JSON as directly as possible:
+// objects become `{"object": id}`, typed arrays
+// `{"array": "Float32Array", "elements": [...]}` and numbers JSON can not
+// represent `{"number": "NaN"}`.
+
+use super::{ArrayKind, Call, Frame, Trace, TraceError, Value};
+use std::fmt::Write;
+
+pub fn encode(trace: &Trace) -> String {
+    let mut out = String::new();
+    out.push_str("{\n  \"contexts\": [");
+    for (index, [width, height]) in trace.contexts.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "[{width}, {height}]");
+    }
+    out.push_str("],\n  \"frames\": [");
+    for (index, frame) in trace.frames.iter().enumerate() {
+        out.push_str(if index > 0 { ",\n    " } else { "\n    " });
+        out.push_str("{\"calls\": [");
+        for (index, call) in frame.calls.iter().enumerate() {
+            out.push_str(if index > 0 { ",\n      " } else { "\n      " });
+            encode_call(&mut out, call);
+        }
+        out.push_str("\n    ]}");
+    }
+    out.push_str("\n  ]\n}\n");
+    out
+}
+
+fn encode_call(out: &mut String, call: &Call) {
+    out.push_str("{\"name\": ");
+    encode_string(out, &call.name);
+    if call.context != 0 {
+        let _ = write!(out, ", \"context\": {}", call.context);
+    }
+    out.push_str(", \"args\": [");
+    for (index, arg) in call.args.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        encode_value(out, arg);
+    }
+    out.push(']');
+    if call.result != Value::Null {
+        out.push_str(", \"result\": ");
+        encode_value(out, &call.result);
+    }
+    out.push('}');
+}
+
+fn encode_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => {
+            let _ = write!(out, "{value}");
+        }
+        Value::Number(value) => encode_number(out, *value),
+        Value::String(value) => encode_string(out, value),
+        Value::Object(id) => {
+            let _ = write!(out, "{{\"object\": {id}}}");
+        }
+        Value::Array { kind, bytes } => {
+            out.push_str("{\"array\": ");
+            encode_string(out, kind.name());
+            out.push_str(", \"elements\": [");
+            for (index, element) in kind.elements(bytes).into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                // The shortest representation of the `f32` parses back to
+                // the same `f32`.
+                if *kind == ArrayKind::Float32 && element.is_finite() {
+                    let _ = write!(out, "{}", element as f32);
+                } else {
+                    encode_number(out, element);
+                }
+            }
+            out.push_str("]}");
+        }
+        Value::List(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                encode_value(out, value);
+            }
+            out.push(']');
+        }
+    }
+}
+
+fn encode_number(out: &mut String, value: f64) {
+    if value.is_finite() {
+        let _ = write!(out, "{value}");
+    } else {
+        let _ = write!(out, "{{\"number\": \"{value}\"}}");
+    }
+}
+
+fn encode_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// Parsed JSON, members of objects in the order they appear.
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn member(&self, name: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member, _)| member == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+fn error<T>(message: impl Into<String>) -> Result<T, TraceError> {
+    Err(TraceError(message.into()))
+}
+
+pub fn decode(text: &str) -> Result<Trace, TraceError> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+    let json = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() {
+        return error("trailing characters after JSON");
+    }
+
+    let contexts = match json.member("contexts") {
+        Some(Json::Array(contexts)) => contexts
+            .iter()
+            .map(|context| match context {
+                Json::Array(size) => match size.as_slice() {
+                    [Json::Number(width), Json::Number(height)] => {
+                        Ok([*width as u32, *height as u32])
+                    }
+                    _ => error("context size is not [width, height]"),
+                },
+                _ => error("context size is not [width, height]"),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return error("missing \"contexts\""),
+    };
+    let frames = match json.member("frames") {
+        Some(Json::Array(frames)) => frames.iter().map(decode_frame).collect::<Result<_, _>>()?,
+        _ => return error("missing \"frames\""),
+    };
+    Ok(Trace { contexts, frames })
+}
+
+fn decode_frame(json: &Json) -> Result<Frame, TraceError> {
+    match json.member("calls") {
+        Some(Json::Array(calls)) => Ok(Frame {
+            calls: calls.iter().map(decode_call).collect::<Result<_, _>>()?,
+        }),
+        _ => error("frame without \"calls\""),
+    }
+}
+
+fn decode_call(json: &Json) -> Result<Call, TraceError> {
+    let name = match json.member("name") {
+        Some(Json::String(name)) => name.clone(),
+        _ => return error("call without \"name\""),
+    };
+    let context = match json.member("context") {
+        Some(Json::Number(context)) => *context as usize,
+        None => 0,
+        _ => return error("\"context\" is not a number"),
+    };
+    let args = match json.member("args") {
+        Some(Json::Array(args)) => args.iter().map(decode_value).collect::<Result<_, _>>()?,
+        _ => return error("call without \"args\""),
+    };
+    let result = json
+        .member("result")
+        .map_or(Ok(Value::Null), decode_value)?;
+    Ok(Call {
+        context,
+        name,
+        args,
+        result,
+    })
+}
+
+fn decode_value(json: &Json) -> Result<Value, TraceError> {
+    Ok(match json {
+        Json::Null => Value::Null,
+        Json::Bool(value) => Value::Bool(*value),
+        Json::Number(value) => Value::Number(*value),
+        Json::String(value) => Value::String(value.clone()),
+        Json::Array(values) => {
+            Value::List(values.iter().map(decode_value).collect::<Result<_, _>>()?)
+        }
+        Json::Object(_) => {
+            if let Some(Json::Number(id)) = json.member("object") {
+                Value::Object(*id as u32)
+            } else if let Some(Json::String(number)) = json.member("number") {
+                match number.parse() {
+                    Ok(number) => Value::Number(number),
+                    Err(_) => return error(format!("invalid number {number:?}")),
+                }
+            } else if let (Some(Json::String(kind)), Some(Json::Array(elements))) =
+                (json.member("array"), json.member("elements"))
+            {
+                let kind = match ArrayKind::from_name(kind) {
+                    Some(kind) => kind,
+                    None => return error(format!("unknown array type {kind:?}")),
+                };
+                let elements = elements
+                    .iter()
+                    .map(|element| match decode_value(element)? {
+                        Value::Number(element) => Ok(element),
+                        _ => error("array element is not a number"),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array {
+                    kind,
+                    bytes: kind.bytes(&elements),
+                }
+            } else {
+                return error("unknown value object");
+            }
+        }
+    })
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\n' | b'\r' | b'\t') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), TraceError> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            error(format!(
+                "expected '{}' at byte {}",
+                byte as char, self.position
+            ))
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, TraceError> {
+        if self.bytes[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            error(format!("unexpected character at byte {}", self.position))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, TraceError> {
+        match self.peek() {
+            Some(b'n') => self.parse_literal("null", Json::Null),
+            Some(b't') => self.parse_literal("true", Json::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.parse_value()?);
+                    if self.peek() == Some(b',') {
+                        self.position += 1;
+                    } else {
+                        self.expect(b']')?;
+                        return Ok(Json::Array(values));
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let name = self.parse_string()?;
+                    self.expect(b':')?;
+                    members.push((name, self.parse_value()?));
+                    if self.peek() == Some(b',') {
+                        self.position += 1;
+                    } else {
+                        self.expect(b'}')?;
+                        return Ok(Json::Object(members));
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') =
+                    self.bytes.get(self.position)
+                {
+                    self.position += 1;
+                }
+                let number = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+                number
+                    .parse()
+                    .map(Json::Number)
+                    .or_else(|_| error(format!("invalid number {number:?}")))
+            }
+            Some(_) => error(format!("unexpected character at byte {}", self.position)),
+            None => error("unexpected end of JSON"),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, TraceError> {
+        if self.bytes.get(self.position) != Some(&b'"') {
+            return error(format!("expected string at byte {}", self.position));
+        }
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.bytes.get(self.position) {
+                None => return error("unterminated string"),
+                Some(b'"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    let escaped = self.bytes.get(self.position + 1).copied();
+                    self.position += 2;
+                    match escaped {
+                        Some(b'"') => bytes.push(b'"'),
+                        Some(b'\\') => bytes.push(b'\\'),
+                        Some(b'/') => bytes.push(b'/'),
+                        Some(b'n') => bytes.push(b'\n'),
+                        Some(b'r') => bytes.push(b'\r'),
+                        Some(b't') => bytes.push(b'\t'),
+                        Some(b'b') => bytes.push(0x08),
+                        Some(b'f') => bytes.push(0x0c),
+                        Some(b'u') => {
+                            let hex = self
+                                .bytes
+                                .get(self.position..self.position + 4)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            self.position += 4;
+                            // Surrogate pairs do not occur in traces, which
+                            // only escape control characters.
+                            let c = hex.and_then(char::from_u32).unwrap_or('\u{fffd}');
+                            bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        _ => return error("invalid escape in string"),
+                    }
+                }
+                Some(&byte) => {
+                    bytes.push(byte);
+                    self.position += 1;
+                }
+            }
+        }
+        String::from_utf8(bytes).or_else(|_| error("string is not UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let mut parser = Parser {
+            bytes: br#" {"a": [1, -2.5e3, true, null], "b": "x\nA"} "#,
+            position: 0,
+        };
+        assert_eq!(
+            parser.parse_value(),
+            Ok(Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(-2500.0),
+                        Json::Bool(true),
+                        Json::Null,
+                    ])
+                ),
+                ("b".to_string(), Json::String("x\nA".to_string())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers_round_trip() {
+        let mut out = String::new();
+        encode_value(&mut out, &Value::Number(f64::NEG_INFINITY));
+        assert_eq!(out, r#"{"number": "-inf"}"#);
+
+        let mut parser = Parser {
+            bytes: out.as_bytes(),
+            position: 0,
+        };
+        let json = parser.parse_value().unwrap();
+        assert_eq!(decode_value(&json), Ok(Value::Number(f64::NEG_INFINITY)));
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(decode("{\"contexts\": [], \"frames\": [").is_err());
+        assert!(decode("{\"contexts\": []}").is_err());
+        assert!(decode("{\"contexts\": [], \"frames\": []} x").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+// Recording wraps every method of `WebGL2RenderingContext.prototype`, so
+// that calls of all contexts, including those made while an example
+// initializes, end up in the trace.
+
+use super::{ArrayKind, Call, Frame, Trace, Value};
+use js_sys::{Array, Function, Map, Object, Reflect, Uint8Array};
+use std::cell::RefCell;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{KeyboardEvent, WebGl2RenderingContext};
+
+/// Key which records a trace of the next frame, or with Shift of the
+/// next [`TRACE_FRAMES`] frames.
+pub const TRACE_KEY: &str = "t";
+
+pub const TRACE_FRAMES: usize = 60;
+
+type RecordCallback = Closure<dyn FnMut(JsValue, String, Array, JsValue)>;
+
+struct Recording {
+    trace: Trace,
+    frame: Frame,
+    frames_left: usize,
+    // Traced contexts and objects, mapped to their index and id.
+    contexts: Map,
+    objects: Map,
+    prototype: Object,
+    originals: Vec<(JsValue, JsValue)>,
+    // Called by the wrapped methods, has to live until they are restored.
+    _record: RecordCallback,
+    on_finish: Box<dyn FnOnce(Trace)>,
+}
+
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// Number of frames to trace when `event` is a press of [`TRACE_KEY`].
+pub fn trace_key_frames(event: &KeyboardEvent) -> Option<usize> {
+    let pressed = event.key().eq_ignore_ascii_case(TRACE_KEY)
+        && !event.repeat()
+        && !event.ctrl_key()
+        && !event.meta_key()
+        && !event.alt_key();
+    pressed.then(|| if event.shift_key() { TRACE_FRAMES } else { 1 })
+}
+
+/// Number of frames requested with `?trace=N` in the URL of the page, to
+/// be traced from the start of the example.
+pub fn requested_frames() -> Option<usize> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("trace="))
+        .and_then(|frames| frames.parse().ok())
+        .filter(|&frames| frames > 0)
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.with(|recording| recording.borrow().is_some())
+}
+
+// Copies typed arrays before the call, as views into the memory of the
+// module become invalid as soon as it grows.
+const WRAPPER: &str = "
+    return function (...args) {
+        const copies = args.map((arg) =>
+            ArrayBuffer.isView(arg) && !(arg instanceof DataView) ? arg.slice() : arg);
+        const result = original.apply(this, args);
+        record(this, name, copies, result);
+        return result;
+    };
+";
+
+/// Records every call to a WebGL2 context until [`end_frame`] was called
+/// `frames` times, then passes the trace to `on_finish`. Does nothing if a
+/// trace is already being recorded.
+pub fn start(frames: usize, on_finish: impl FnOnce(Trace) + 'static) {
+    if is_recording() {
+        return;
+    }
+
+    let prototype: Object = Reflect::get(
+        &js_sys::global(),
+        &JsValue::from_str("WebGL2RenderingContext"),
+    )
+    .and_then(|constructor| Reflect::get(&constructor, &JsValue::from_str("prototype")))
+    .unwrap()
+    .unchecked_into();
+
+    let record = RecordCallback::new(|context, name, args, result| {
+        RECORDING.with(|recording| {
+            if let Some(recording) = recording.borrow_mut().as_mut() {
+                recording.record(&context, name, &args, &result);
+            }
+        });
+    });
+
+    let factory = Function::new_with_args("original, record, name", WRAPPER);
+    let mut originals = Vec::new();
+    for name in Object::get_own_property_names(&prototype).iter() {
+        if name == "constructor" {
+            continue;
+        }
+        // Accessors such as `drawingBufferWidth` are left alone.
+        let descriptor = Object::get_own_property_descriptor(&prototype, &name);
+        let original = Reflect::get(&descriptor, &JsValue::from_str("value")).unwrap();
+        if !original.is_function() {
+            continue;
+        }
+        let wrapper = factory
+            .call3(&JsValue::UNDEFINED, &original, record.as_ref(), &name)
+            .unwrap();
+        drop(Reflect::set(&prototype, &name, &wrapper));
+        originals.push((name, original));
+    }
+
+    RECORDING.with(|recording| {
+        recording.replace(Some(Recording {
+            trace: Trace::default(),
+            frame: Frame::default(),
+            frames_left: frames,
+            contexts: Map::new(),
+            objects: Map::new(),
+            prototype,
+            originals,
+            _record: record,
+            on_finish: Box::new(on_finish),
+        }))
+    });
+}
+
+/// Ends the current frame of the trace being recorded, finishing the
+/// trace after its last frame.
+pub fn end_frame() {
+    let finished = RECORDING.with(|recording| {
+        let mut recording = recording.borrow_mut();
+        let Some(current) = recording.as_mut() else {
+            return false;
+        };
+        let frame = std::mem::take(&mut current.frame);
+        current.trace.frames.push(frame);
+        current.frames_left -= 1;
+        current.frames_left == 0
+    });
+    if finished {
+        finish();
+    }
+}
+
+/// Stops recording, passing the frames recorded so far to the callback
+/// given to [`start`].
+pub fn finish() {
+    let Some(mut recording) = RECORDING.with(|recording| recording.take()) else {
+        return;
+    };
+    for (name, original) in &recording.originals {
+        drop(Reflect::set(&recording.prototype, name, original));
+    }
+    if !recording.frame.calls.is_empty() {
+        let frame = std::mem::take(&mut recording.frame);
+        recording.trace.frames.push(frame);
+    }
+    (recording.on_finish)(recording.trace);
+}
+
+impl Recording {
+    fn record(&mut self, context: &JsValue, name: String, args: &Array, result: &JsValue) {
+        let context = self.context_index(context);
+        let args = args.iter().map(|arg| self.value(&arg)).collect();
+        let result = self.value(result);
+        self.frame.calls.push(Call {
+            context,
+            name,
+            args,
+            result,
+        });
+    }
+
+    fn context_index(&mut self, context: &JsValue) -> usize {
+        if let Some(index) = self.contexts.get(context).as_f64() {
+            return index as usize;
+        }
+        let gl = context.unchecked_ref::<WebGl2RenderingContext>();
+        let index = self.trace.contexts.len();
+        self.trace.contexts.push([
+            gl.drawing_buffer_width() as u32,
+            gl.drawing_buffer_height() as u32,
+        ]);
+        self.contexts.set(context, &JsValue::from(index as u32));
+        index
+    }
+
+    fn value(&mut self, value: &JsValue) -> Value {
+        if value.is_null() || value.is_undefined() {
+            Value::Null
+        } else if let Some(value) = value.as_bool() {
+            Value::Bool(value)
+        } else if let Some(value) = value.as_f64() {
+            Value::Number(value)
+        } else if let Some(value) = value.as_string() {
+            Value::String(value)
+        } else if Array::is_array(value) {
+            let values = value.unchecked_ref::<Array>();
+            Value::List(values.iter().map(|value| self.value(&value)).collect())
+        } else if let Some(kind) = array_kind(value) {
+            let property = |name| Reflect::get(value, &JsValue::from_str(name)).unwrap();
+            let bytes = Uint8Array::new_with_byte_offset_and_length(
+                &property("buffer"),
+                property("byteOffset").as_f64().unwrap() as u32,
+                property("byteLength").as_f64().unwrap() as u32,
+            );
+            Value::Array {
+                kind,
+                bytes: bytes.to_vec(),
+            }
+        } else {
+            // Everything else is a WebGL object, e.g. a buffer or a
+            // uniform location.
+            if let Some(id) = self.objects.get(value).as_f64() {
+                return Value::Object(id as u32);
+            }
+            let id = self.objects.size() + 1;
+            self.objects.set(value, &JsValue::from(id));
+            Value::Object(id)
+        }
+    }
+}
+
+fn array_kind(value: &JsValue) -> Option<ArrayKind> {
+    let constructor = Reflect::get(value, &JsValue::from_str("constructor")).ok()?;
+    let name = Reflect::get(&constructor, &JsValue::from_str("name"))
+        .ok()?
+        .as_string()?;
+    ArrayKind::from_name(&name)
+}
@@ -0,0 +1,219 @@
+use super::{ArrayKind, Call, Trace, Value};
+use crate::software::SoftwareContext;
+use js_sys::{Array, Function, Reflect, Uint8Array};
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::WebGl2RenderingContext;
+
+/// Result of replaying a trace.
+pub struct Replay<C> {
+    /// One context for every context of the trace.
+    pub contexts: Vec<C>,
+    /// Calls which could not be replayed, by name, with their count.
+    pub skipped: BTreeMap<String, usize>,
+}
+
+fn number(call: &Call, index: usize) -> f64 {
+    match call.args.get(index) {
+        Some(Value::Number(value)) => *value,
+        Some(Value::Bool(value)) => *value as u8 as f64,
+        _ => 0.0,
+    }
+}
+
+fn boolean(call: &Call, index: usize) -> bool {
+    match call.args.get(index) {
+        Some(Value::Bool(value)) => *value,
+        Some(Value::Number(value)) => *value != 0.0,
+        _ => false,
+    }
+}
+
+// Queries do not change anything the software context could draw.
+fn is_query(name: &str) -> bool {
+    name.starts_with("get") || name.starts_with("is") || name == "checkFramebufferStatus"
+}
+
+/// Replays the first `frames` frames of `trace` with the software
+/// rasterizer, one [`SoftwareContext`] per traced context.
+pub fn replay_software(trace: &Trace, frames: usize) -> Replay<SoftwareContext> {
+    let mut contexts: Vec<SoftwareContext> = trace
+        .contexts
+        .iter()
+        .map(|&[width, height]| SoftwareContext::new(width, height))
+        .collect();
+    let mut skipped = BTreeMap::new();
+
+    for call in trace
+        .frames
+        .iter()
+        .take(frames)
+        .flat_map(|frame| &frame.calls)
+    {
+        let gl = match contexts.get_mut(call.context) {
+            Some(gl) => gl,
+            None => {
+                *skipped.entry(call.name.clone()).or_default() += 1;
+                continue;
+            }
+        };
+        let int = |index| number(call, index) as i32;
+        match call.name.as_str() {
+            "clearColor" => gl.clear_color(
+                number(call, 0) as f32,
+                number(call, 1) as f32,
+                number(call, 2) as f32,
+                number(call, 3) as f32,
+            ),
+            "clear" => gl.clear(number(call, 0) as u32),
+            "scissor" => gl.scissor(int(0), int(1), int(2), int(3)),
+            "viewport" => gl.viewport(int(0), int(1), int(2), int(3)),
+            "colorMask" => gl.color_mask(
+                boolean(call, 0),
+                boolean(call, 1),
+                boolean(call, 2),
+                boolean(call, 3),
+            ),
+            "enable" => gl.enable(number(call, 0) as u32),
+            "disable" => gl.disable(number(call, 0) as u32),
+            name if is_query(name) => {}
+            name => *skipped.entry(name.to_string()).or_default() += 1,
+        }
+    }
+
+    Replay { contexts, skipped }
+}
+
+fn to_js(value: &Value, objects: &HashMap<u32, JsValue>) -> Option<JsValue> {
+    Some(match value {
+        Value::Null => JsValue::NULL,
+        Value::Bool(value) => JsValue::from_bool(*value),
+        Value::Number(value) => JsValue::from_f64(*value),
+        Value::String(value) => JsValue::from_str(value),
+        Value::Object(id) => objects.get(id)?.clone(),
+        Value::Array { kind, bytes } => typed_array(*kind, bytes),
+        Value::List(values) => values
+            .iter()
+            .map(|value| to_js(value, objects))
+            .collect::<Option<Array>>()?
+            .into(),
+    })
+}
+
+fn typed_array(kind: ArrayKind, bytes: &[u8]) -> JsValue {
+    let buffer = Uint8Array::from(bytes).buffer();
+    let constructor = Reflect::get(&js_sys::global(), &JsValue::from_str(kind.name())).unwrap();
+    Reflect::construct(
+        constructor.unchecked_ref::<Function>(),
+        &Array::of1(&buffer),
+    )
+    .unwrap()
+}
+
+/// Replays the first `frames` frames of `trace` on `contexts`, which
+/// should be fresh contexts of the traced sizes. Returns the calls which
+/// were skipped because they use objects created before the trace started,
+/// or which failed.
+pub fn replay_webgl(
+    trace: &Trace,
+    contexts: &[WebGl2RenderingContext],
+    frames: usize,
+) -> BTreeMap<String, usize> {
+    let mut objects: HashMap<u32, JsValue> = HashMap::new();
+    let mut skipped = BTreeMap::new();
+
+    for call in trace
+        .frames
+        .iter()
+        .take(frames)
+        .flat_map(|frame| &frame.calls)
+    {
+        let replayed = contexts.get(call.context).and_then(|gl| {
+            let args = call
+                .args
+                .iter()
+                .map(|arg| to_js(arg, &objects))
+                .collect::<Option<Array>>()?;
+            let method = Reflect::get(gl, &JsValue::from_str(&call.name))
+                .ok()?
+                .dyn_into::<Function>()
+                .ok()?;
+            let result = Reflect::apply(&method, gl, &args).ok()?;
+            if let Value::Object(id) = call.result {
+                objects.insert(id, result);
+            }
+            Some(())
+        });
+        if replayed.is_none() {
+            *skipped.entry(call.name.clone()).or_default() += 1;
+        }
+    }
+
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::Frame;
+
+    fn call(context: usize, name: &str, args: &[f64]) -> Call {
+        Call {
+            context,
+            name: name.to_string(),
+            args: args.iter().map(|&arg| Value::Number(arg)).collect(),
+            result: Value::Null,
+        }
+    }
+
+    #[test]
+    fn software_replay_clears_with_scissor() {
+        let trace = Trace {
+            contexts: vec![[8, 8], [2, 2]],
+            frames: vec![
+                Frame {
+                    calls: vec![
+                        call(0, "clearColor", &[0.0, 0.0, 0.0, 1.0]),
+                        call(
+                            0,
+                            "clear",
+                            &[WebGl2RenderingContext::COLOR_BUFFER_BIT as f64],
+                        ),
+                        call(1, "clearColor", &[1.0, 1.0, 0.0, 1.0]),
+                        call(
+                            1,
+                            "clear",
+                            &[WebGl2RenderingContext::COLOR_BUFFER_BIT as f64],
+                        ),
+                    ],
+                },
+                Frame {
+                    calls: vec![
+                        call(0, "enable", &[WebGl2RenderingContext::SCISSOR_TEST as f64]),
+                        call(0, "scissor", &[2.0, 2.0, 3.0, 3.0]),
+                        call(0, "clearColor", &[1.0, 0.0, 0.0, 1.0]),
+                        call(
+                            0,
+                            "clear",
+                            &[WebGl2RenderingContext::COLOR_BUFFER_BIT as f64],
+                        ),
+                        call(0, "getError", &[]),
+                        call(0, "drawArrays", &[0.0, 0.0, 1.0]),
+                    ],
+                },
+            ],
+        };
+
+        let replay = replay_software(&trace, 1);
+        assert_eq!(replay.contexts[0].pixel(3, 3), [0, 0, 0, 255]);
+        assert_eq!(replay.contexts[1].pixel(1, 1), [255, 255, 0, 255]);
+
+        let replay = replay_software(&trace, 2);
+        assert_eq!(replay.contexts[0].pixel(3, 3), [255, 0, 0, 255]);
+        assert_eq!(replay.contexts[0].pixel(1, 1), [0, 0, 0, 255]);
+        assert_eq!(
+            replay.skipped.into_iter().collect::<Vec<_>>(),
+            vec![("drawArrays".to_string(), 1)]
+        );
+    }
+}
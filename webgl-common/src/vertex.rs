@@ -0,0 +1,483 @@
+use crate::gl::Gl;
+use crate::resources;
+use std::marker::PhantomData;
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram};
+
+/// Description of a single vertex attribute, as passed to
+/// `vertexAttribPointer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    /// Name of the attribute in the vertex shader.
+    pub name: &'static str,
+    /// Number of components, between 1 and 4.
+    pub components: i32,
+    /// Component type, e.g. `WebGl2RenderingContext::FLOAT`.
+    pub data_type: u32,
+    pub normalized: bool,
+    /// Offset in bytes from the start of the vertex.
+    pub offset: i32,
+}
+
+/// Memory layout of one vertex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+    pub attributes: &'static [VertexAttribute],
+    /// Size in bytes of one vertex.
+    pub stride: i32,
+}
+
+/// A `#[repr(C)]` struct which can be uploaded to a vertex buffer.
+///
+/// Use `#[derive(Vertex)]` instead of implementing this by hand.
+///
+/// # Safety
+///
+/// `LAYOUT` has to describe the struct exactly and the struct must not
+/// contain any padding bytes, as its values are uploaded as raw bytes.
+///
+/// The derive refuses structs without `#[repr(C)]`:
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, webgl_common::Vertex)]
+/// struct Point {
+///     position: [f32; 2],
+/// }
+/// ```
+///
+/// and structs with padding between or after their fields:
+///
+/// ```compile_fail,E0080
+/// #[derive(Clone, Copy, webgl_common::Vertex)]
+/// #[repr(C)]
+/// struct Point {
+///     color: [u8; 3],
+///     position: [f32; 2],
+/// }
+/// ```
+pub unsafe trait Vertex: Copy + 'static {
+    const LAYOUT: VertexLayout;
+}
+
+/// Field types usable in a `#[derive(Vertex)]` struct.
+pub trait AttributeFormat {
+    const COMPONENTS: i32;
+    const DATA_TYPE: u32;
+}
+
+macro_rules! attribute_format {
+    ($($ty:ty => $data_type:ident),* $(,)?) => {
+        $(
+            impl AttributeFormat for $ty {
+                const COMPONENTS: i32 = 1;
+                const DATA_TYPE: u32 = WebGl2RenderingContext::$data_type;
+            }
+
+            impl<const N: usize> AttributeFormat for [$ty; N] {
+                const COMPONENTS: i32 = {
+                    assert!(N >= 1 && N <= 4, "vertex attributes have 1 to 4 components");
+                    N as i32
+                };
+                const DATA_TYPE: u32 = WebGl2RenderingContext::$data_type;
+            }
+        )*
+    };
+}
+
+attribute_format! {
+    f32 => FLOAT,
+    i8 => BYTE,
+    u8 => UNSIGNED_BYTE,
+    i16 => SHORT,
+    u16 => UNSIGNED_SHORT,
+}
+
+/// Views a slice of vertices as the bytes which get uploaded to the GPU.
+pub fn as_bytes<V: Vertex>(vertices: &[V]) -> &[u8] {
+    // SAFETY: `Vertex` guarantees a padding free `#[repr(C)]` layout, so
+    // every byte of the slice is initialized.
+    unsafe {
+        std::slice::from_raw_parts(
+            vertices.as_ptr() as *const u8,
+            std::mem::size_of_val(vertices),
+        )
+    }
+}
+
+/// Array buffer holding vertices of type `V`, which knows how to describe
+/// its own layout to a program.
+pub struct VertexBuffer<V: Vertex> {
+    buffer: WebGlBuffer,
+    usage: u32,
+    len: usize,
+    vertex: PhantomData<V>,
+}
+
+impl<V: Vertex> VertexBuffer<V> {
+    pub fn new(gl: &WebGl2RenderingContext, vertices: &[V], usage: u32) -> Result<Self, JsValue> {
+        let buffer = resources::create_buffer(gl)
+            .ok_or_else(|| JsValue::from_str("Unable to create vertex buffer"))?;
+        let mut vertex_buffer = Self {
+            buffer,
+            usage,
+            len: 0,
+            vertex: PhantomData,
+        };
+        vertex_buffer.set_data(gl, vertices);
+        Ok(vertex_buffer)
+    }
+
+    /// Replaces the whole content of the buffer.
+    pub fn set_data(&mut self, gl: &WebGl2RenderingContext, vertices: &[V]) {
+        let gl = Gl::from_ref(gl);
+        self.bind(gl);
+        gl.buffer_data_with_u8_array(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            as_bytes(vertices),
+            self.usage,
+        );
+        self.len = vertices.len();
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.buffer));
+    }
+
+    /// Binds the buffer and points every attribute of the vertex layout at
+    /// it. Attributes are looked up by name in `program`, those the program
+    /// does not use are skipped.
+    pub fn bind_attributes(&self, gl: &WebGl2RenderingContext, program: &WebGlProgram) {
+        self.bind(gl);
+        point_attributes::<V>(gl, program, 0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn gl_buffer(&self) -> &WebGlBuffer {
+        &self.buffer
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        resources::delete_buffer(gl, Some(&self.buffer));
+    }
+}
+
+/// Vertex buffer which vertices can be appended to one at a time.
+///
+/// The GPU storage grows by doubling its capacity, so appending only
+/// uploads the new vertex with `bufferSubData` most of the time. A copy of
+/// the vertices is kept on the CPU side to refill the storage when it grows.
+pub struct GrowableVertexBuffer<V: Vertex> {
+    buffer: WebGlBuffer,
+    vertices: Vec<V>,
+    capacity: usize,
+}
+
+impl<V: Vertex> GrowableVertexBuffer<V> {
+    pub fn with_capacity(gl: &WebGl2RenderingContext, capacity: usize) -> Result<Self, JsValue> {
+        let buffer = resources::create_buffer(gl)
+            .ok_or_else(|| JsValue::from_str("Unable to create vertex buffer"))?;
+        let growable_buffer = Self {
+            buffer,
+            vertices: Vec::with_capacity(capacity),
+            capacity: capacity.max(1),
+        };
+        growable_buffer.allocate(gl);
+        Ok(growable_buffer)
+    }
+
+    /// Appends a vertex, doubling the capacity of the GPU storage when it
+    /// is full.
+    pub fn push(&mut self, gl: &WebGl2RenderingContext, vertex: V) {
+        let gl = Gl::from_ref(gl);
+        self.vertices.push(vertex);
+        match Upload::for_push(self.vertices.len(), self.capacity, V::LAYOUT.stride) {
+            Upload::Grow { capacity } => {
+                self.capacity = capacity;
+                self.allocate(gl);
+                gl.buffer_sub_data_with_i32_and_u8_array(
+                    WebGl2RenderingContext::ARRAY_BUFFER,
+                    0,
+                    as_bytes(&self.vertices),
+                );
+            }
+            Upload::Append { offset } => {
+                self.bind(gl);
+                gl.buffer_sub_data_with_i32_and_u8_array(
+                    WebGl2RenderingContext::ARRAY_BUFFER,
+                    offset,
+                    as_bytes(&[vertex]),
+                );
+            }
+        }
+    }
+
+    /// Removes the last vertex. The GPU storage is left untouched, only the
+    /// number of vertices to draw changes.
+    pub fn pop(&mut self) -> Option<V> {
+        self.vertices.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.buffer));
+    }
+
+    /// See [`VertexBuffer::bind_attributes`].
+    pub fn bind_attributes(&self, gl: &WebGl2RenderingContext, program: &WebGlProgram) {
+        self.bind(gl);
+        point_attributes::<V>(gl, program, 0);
+    }
+
+    pub fn vertices(&self) -> &[V] {
+        &self.vertices
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Number of vertices the GPU storage can hold before it has to grow.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn gl_buffer(&self) -> &WebGlBuffer {
+        &self.buffer
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        resources::delete_buffer(gl, Some(&self.buffer));
+    }
+
+    fn allocate(&self, gl: &WebGl2RenderingContext) {
+        let gl = Gl::from_ref(gl);
+        self.bind(gl);
+        gl.buffer_data_with_i32(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            self.capacity as i32 * V::LAYOUT.stride,
+            WebGl2RenderingContext::DYNAMIC_DRAW,
+        );
+    }
+}
+
+/// How a push reaches the GPU storage of a [`GrowableVertexBuffer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Upload {
+    /// The storage is reallocated with room for `capacity` vertices and
+    /// refilled with all of them.
+    Grow { capacity: usize },
+    /// Only the new vertex is written, `offset` bytes into the storage.
+    Append { offset: i32 },
+}
+
+impl Upload {
+    /// The upload needed once the buffer holds `len` vertices, the last
+    /// one new, with storage for `capacity` vertices of `stride` bytes.
+    fn for_push(len: usize, capacity: usize, stride: i32) -> Self {
+        if len > capacity {
+            let mut capacity = capacity.max(1);
+            while len > capacity {
+                capacity *= 2;
+            }
+            Upload::Grow { capacity }
+        } else {
+            Upload::Append {
+                offset: (len - 1) as i32 * stride,
+            }
+        }
+    }
+}
+
+/// Points the attributes of `V` at the currently bound array buffer.
+/// A `divisor` of 0 advances the attributes per vertex, 1 per instance.
+pub(crate) fn point_attributes<V: Vertex>(
+    gl: &WebGl2RenderingContext,
+    program: &WebGlProgram,
+    divisor: u32,
+) {
+    let gl = Gl::from_ref(gl);
+    for attribute in V::LAYOUT.attributes {
+        let location = gl.get_attrib_location(program, attribute.name);
+        if location < 0 {
+            continue;
+        }
+        let location = location as u32;
+        gl.enable_vertex_attrib_array(location);
+        gl.vertex_attrib_pointer_with_i32(
+            location,
+            attribute.components,
+            attribute.data_type,
+            attribute.normalized,
+            V::LAYOUT.stride,
+            attribute.offset,
+        );
+        gl.vertex_attrib_divisor(location, divisor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[derive(Clone, Copy, crate::Vertex)]
+    #[repr(C)]
+    struct ColoredVertex {
+        position: [f32; 2],
+        size: f32,
+        color: [f32; 3],
+    }
+
+    #[derive(Clone, Copy, crate::Vertex)]
+    #[repr(C)]
+    struct PackedVertex {
+        position: [i16; 2],
+        #[vertex(name = "tint", normalized)]
+        color: [u8; 4],
+    }
+
+    fn attribute(
+        name: &'static str,
+        components: i32,
+        data_type: u32,
+        normalized: bool,
+        offset: i32,
+    ) -> VertexAttribute {
+        VertexAttribute {
+            name,
+            components,
+            data_type,
+            normalized,
+            offset,
+        }
+    }
+
+    #[test]
+    fn derived_layout_follows_the_fields() {
+        let layout = ColoredVertex::LAYOUT;
+        assert_eq!(
+            layout.attributes,
+            [
+                attribute("position", 2, WebGl2RenderingContext::FLOAT, false, 0),
+                attribute("size", 1, WebGl2RenderingContext::FLOAT, false, 8),
+                attribute("color", 3, WebGl2RenderingContext::FLOAT, false, 12),
+            ]
+        );
+        assert_eq!(layout.stride as usize, size_of::<ColoredVertex>());
+    }
+
+    #[test]
+    fn derived_layout_takes_names_and_normalization() {
+        let layout = PackedVertex::LAYOUT;
+        assert_eq!(
+            layout.attributes,
+            [
+                attribute("position", 2, WebGl2RenderingContext::SHORT, false, 0),
+                attribute("tint", 4, WebGl2RenderingContext::UNSIGNED_BYTE, true, 4),
+            ]
+        );
+        assert_eq!(layout.stride as usize, size_of::<PackedVertex>());
+    }
+
+    // Pushes `count` vertices of 12 bytes into storage for `capacity`
+    // vertices, updating both and returning the uploads.
+    fn push_many(len: &mut usize, capacity: &mut usize, count: usize) -> Vec<Upload> {
+        (0..count)
+            .map(|_| {
+                *len += 1;
+                let upload = Upload::for_push(*len, *capacity, 12);
+                if let Upload::Grow { capacity: grown } = upload {
+                    *capacity = grown;
+                }
+                upload
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pushes_append_after_the_last_vertex() {
+        assert_eq!(Upload::for_push(1, 4, 12), Upload::Append { offset: 0 });
+        assert_eq!(Upload::for_push(4, 4, 12), Upload::Append { offset: 36 });
+    }
+
+    #[test]
+    fn storage_grows_from_zero_and_one() {
+        assert_eq!(Upload::for_push(1, 0, 12), Upload::Grow { capacity: 1 });
+        assert_eq!(Upload::for_push(2, 1, 12), Upload::Grow { capacity: 2 });
+        let (mut len, mut capacity) = (0, 1);
+        let uploads = push_many(&mut len, &mut capacity, 5);
+        assert_eq!(
+            uploads,
+            [
+                Upload::Append { offset: 0 },
+                Upload::Grow { capacity: 2 },
+                Upload::Grow { capacity: 4 },
+                Upload::Append { offset: 36 },
+                Upload::Grow { capacity: 8 },
+            ]
+        );
+        assert_eq!(capacity, 8);
+    }
+
+    #[test]
+    fn storage_doubles_as_often_as_needed() {
+        assert_eq!(Upload::for_push(9, 2, 12), Upload::Grow { capacity: 16 });
+        assert_eq!(Upload::for_push(17, 16, 12), Upload::Grow { capacity: 32 });
+    }
+
+    #[test]
+    fn pop_and_clear_reuse_the_storage() {
+        let (mut len, mut capacity) = (0, 1);
+        push_many(&mut len, &mut capacity, 6);
+        assert_eq!(capacity, 8);
+
+        // Popping two and pushing them back writes in place.
+        len -= 2;
+        let uploads = push_many(&mut len, &mut capacity, 2);
+        assert_eq!(
+            uploads,
+            [Upload::Append { offset: 48 }, Upload::Append { offset: 60 }]
+        );
+
+        // After clearing, the storage fills up from the start again.
+        len = 0;
+        let uploads = push_many(&mut len, &mut capacity, 9);
+        assert_eq!(uploads[0], Upload::Append { offset: 0 });
+        assert_eq!(uploads[7], Upload::Append { offset: 84 });
+        assert_eq!(uploads[8], Upload::Grow { capacity: 16 });
+    }
+
+    #[test]
+    fn bytes_cover_every_vertex() {
+        let vertices = [
+            PackedVertex {
+                position: [1, -1],
+                color: [255, 128, 0, 255],
+            },
+            PackedVertex {
+                position: [0x0102, 0],
+                color: [1, 2, 3, 4],
+            },
+        ];
+        let bytes = as_bytes(&vertices);
+        assert_eq!(bytes.len(), 2 * PackedVertex::LAYOUT.stride as usize);
+        assert_eq!(bytes[4..8], [255, 128, 0, 255]);
+        assert_eq!(bytes[8..10], 0x0102i16.to_ne_bytes());
+    }
+}